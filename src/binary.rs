@@ -0,0 +1,614 @@
+//! A fast binary format for saving and loading `World`s whose data is plain-old-data.
+//!
+//! Rather than serializing each component individually, each chunk's component columns are
+//! written as raw bytes, and loaded by copying them straight into freshly built chunks. Every
+//! component and tag type in the world must be registered in a `PodRegistry`.
+//!
+//! The data begins with a header describing the stable ID and size of each type, and the
+//! layout of each archetype. It is followed by the contents of each chunk: its tag values, the IDs
+//! of its entities, and its component columns. Data is written in the native byte order of the
+//! machine, and can only be loaded on machines which share it.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::binary::PodRegistry;
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! struct Position(f32, f32);
+//!
+//! let universe = Universe::new(None);
+//! let mut world = universe.create_world();
+//! let entity = world.insert_from((), vec![(Position(1., 2.),)])[0];
+//!
+//! let mut registry = PodRegistry::default();
+//! // `Position` is valid for any bit pattern
+//! unsafe { registry.register_component::<Position>("position") };
+//!
+//! let mut data = Vec::new();
+//! world.write_binary(&registry, &mut data).unwrap();
+//!
+//! let load_universe = Universe::new(None);
+//! let loaded = World::read_binary(&load_universe, &registry, data.as_slice()).unwrap();
+//!
+//! assert_eq!(Position(1., 2.), *loaded.component::<Position>(entity).unwrap());
+//! ```
+
+use std::io::{self, Read, Write};
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::registry::{StableTypeId, TypeRegistry};
+use crate::storage::{Archetype, Chunk, ChunkBuilder, TagStorageVTable};
+use crate::*;
+
+const MAGIC: &[u8; 4] = b"LGNW";
+const FORMAT_VERSION: u32 = 2;
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+#[derive(Clone)]
+struct ComponentRegistration {
+    id: StableTypeId,
+    size: usize,
+}
+
+#[derive(Clone)]
+struct TagRegistration {
+    id: StableTypeId,
+    size: usize,
+    vtable: TagStorageVTable,
+}
+
+/// A registry of the plain-old-data component and tag types which can be written to the binary
+/// format, and the stable IDs which identify them in written data.
+#[derive(Default, Clone)]
+pub struct PodRegistry {
+    types: TypeRegistry,
+    components: FnvHashMap<ComponentTypeId, ComponentRegistration>,
+    tags: FnvHashMap<TagTypeId, TagRegistration>,
+}
+
+impl PodRegistry {
+    /// Registers the component type `T` under the given name, with a stable ID derived from the
+    /// name.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be valid for any bit pattern, and so must not contain references, pointers or
+    /// other types with invalid values, such as `bool` or enums.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub unsafe fn register_component<T: Component + Copy>(&mut self, name: &str) -> &mut Self {
+        self.register_component_with_id::<T>(name, StableTypeId::from_name(name))
+    }
+
+    /// Registers the component type `T` under the given name and stable ID.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be valid for any bit pattern, and so must not contain references, pointers or
+    /// other types with invalid values, such as `bool` or enums.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub unsafe fn register_component_with_id<T: Component + Copy>(
+        &mut self,
+        name: &str,
+        id: StableTypeId,
+    ) -> &mut Self {
+        let ty = T::type_id();
+        self.types.register_component_raw(ty, id, name);
+        self.components.insert(
+            ty,
+            ComponentRegistration {
+                id,
+                size: std::mem::size_of::<T>(),
+            },
+        );
+        self
+    }
+
+    /// Registers the tag type `T` under the given name, with a stable ID derived from the name.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be valid for any bit pattern, and so must not contain references, pointers or
+    /// other types with invalid values, such as `bool` or enums.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub unsafe fn register_tag<T: Tag + Copy>(&mut self, name: &str) -> &mut Self {
+        self.register_tag_with_id::<T>(name, StableTypeId::from_name(name))
+    }
+
+    /// Registers the tag type `T` under the given name and stable ID.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be valid for any bit pattern, and so must not contain references, pointers or
+    /// other types with invalid values, such as `bool` or enums.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub unsafe fn register_tag_with_id<T: Tag + Copy>(
+        &mut self,
+        name: &str,
+        id: StableTypeId,
+    ) -> &mut Self {
+        let ty = T::type_id();
+        self.types.register_tag_raw(ty, id, name);
+        self.tags.insert(
+            ty,
+            TagRegistration {
+                id,
+                size: std::mem::size_of::<T>(),
+                vtable: TagStorageVTable::from::<T>(),
+            },
+        );
+        self
+    }
+
+    /// Gets the stable IDs and names of the registered types.
+    pub fn types(&self) -> &TypeRegistry {
+        &self.types
+    }
+}
+
+/// An error returned when reading or writing the binary format.
+#[derive(Debug)]
+pub enum BinaryError {
+    /// An I/O error occurred.
+    Io(io::Error),
+    /// A component type in the world has not been registered in the `PodRegistry`.
+    UnregisteredComponent(ComponentTypeId),
+    /// A tag type in the world has not been registered in the `PodRegistry`.
+    UnregisteredTag(TagTypeId),
+    /// The data contains a stable type ID which has not been registered in the `PodRegistry`.
+    UnknownType(StableTypeId),
+    /// The data is corrupt, or was written by an incompatible version or machine.
+    InvalidData(String),
+    /// The entity IDs in the data have already been allocated in the target `Universe`.
+    EntityIdsInUse,
+}
+
+impl From<io::Error> for BinaryError {
+    fn from(err: io::Error) -> Self {
+        BinaryError::Io(err)
+    }
+}
+
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BinaryError::Io(err) => write!(f, "{}", err),
+            BinaryError::UnregisteredComponent(ty) => {
+                write!(f, "component type {:?} is not registered", ty)
+            }
+            BinaryError::UnregisteredTag(ty) => write!(f, "tag type {:?} is not registered", ty),
+            BinaryError::UnknownType(id) => write!(f, "unknown type {}", id),
+            BinaryError::InvalidData(reason) => write!(f, "invalid data: {}", reason),
+            BinaryError::EntityIdsInUse => write!(
+                f,
+                "entity IDs have already been allocated in the target universe"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinaryError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: usize) -> Result<(), BinaryError> {
+    if value > u32::MAX as usize {
+        return Err(BinaryError::InvalidData(format!(
+            "{} is too large to be written",
+            value
+        )));
+    }
+
+    writer.write_all(&(value as u32).to_ne_bytes())?;
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<usize, BinaryError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_ne_bytes(bytes) as usize)
+}
+
+fn write_id<W: Write>(writer: &mut W, id: StableTypeId) -> Result<(), BinaryError> {
+    writer.write_all(&id.to_bytes())?;
+    Ok(())
+}
+
+fn read_id<R: Read>(reader: &mut R) -> Result<StableTypeId, BinaryError> {
+    let mut bytes = [0u8; 16];
+    reader.read_exact(&mut bytes)?;
+    Ok(StableTypeId::from_bytes(bytes))
+}
+
+fn write_indices<W: Write>(writer: &mut W, indices: &[usize]) -> Result<(), BinaryError> {
+    write_u32(writer, indices.len())?;
+    for index in indices {
+        write_u32(writer, *index)?;
+    }
+    Ok(())
+}
+
+fn read_indices<R: Read>(reader: &mut R, bound: usize) -> Result<Vec<usize>, BinaryError> {
+    let len = read_u32(reader)?;
+    let mut indices = Vec::new();
+    for _ in 0..len {
+        let index = read_u32(reader)?;
+        if index >= bound {
+            return Err(BinaryError::InvalidData("type index out of range".into()));
+        }
+        indices.push(index);
+    }
+    Ok(indices)
+}
+
+/// Reads exactly `len` bytes into uninitialized memory.
+unsafe fn read_raw<R: Read>(reader: &mut R, ptr: *mut u8, len: usize) -> Result<(), BinaryError> {
+    std::ptr::write_bytes(ptr, 0, len);
+    reader.read_exact(std::slice::from_raw_parts_mut(ptr, len))?;
+    Ok(())
+}
+
+/// The type layout of an archetype, as indices into the header's type tables.
+struct ArchetypeLayout {
+    tags: Vec<usize>,
+    components: Vec<usize>,
+    chunk_count: usize,
+}
+
+impl World {
+    /// Writes the world in the binary format.
+    ///
+    /// Fails without writing any data if any entity has a component or tag type which has not
+    /// been registered in `registry`.
+    pub fn write_binary<W: Write>(
+        &self,
+        registry: &PodRegistry,
+        mut writer: W,
+    ) -> Result<(), BinaryError> {
+        let archetypes = self
+            .archetypes
+            .iter()
+            .filter(|archetype| archetype.chunks().iter().any(|chunk| chunk.len() > 0))
+            .collect::<Vec<_>>();
+
+        // build the type tables
+        let mut component_types = Vec::<ComponentTypeId>::new();
+        let mut tag_types = Vec::<TagTypeId>::new();
+        let mut layouts = Vec::new();
+        for archetype in archetypes.iter() {
+            let mut tags = Vec::new();
+            for ty in archetype.tags.iter() {
+                if !registry.tags.contains_key(ty) {
+                    return Err(BinaryError::UnregisteredTag(*ty));
+                }
+
+                tags.push(match tag_types.iter().position(|t| t == ty) {
+                    Some(i) => i,
+                    None => {
+                        tag_types.push(*ty);
+                        tag_types.len() - 1
+                    }
+                });
+            }
+
+            let mut components = Vec::new();
+            for ty in archetype.components.iter() {
+                if !registry.components.contains_key(ty) {
+                    return Err(BinaryError::UnregisteredComponent(*ty));
+                }
+
+                components.push(match component_types.iter().position(|t| t == ty) {
+                    Some(i) => i,
+                    None => {
+                        component_types.push(*ty);
+                        component_types.len() - 1
+                    }
+                });
+            }
+
+            layouts.push(ArchetypeLayout {
+                tags,
+                components,
+                chunk_count: archetype.chunks().iter().filter(|c| c.len() > 0).count(),
+            });
+        }
+
+        // header
+        writer.write_all(MAGIC)?;
+        write_u32(&mut writer, FORMAT_VERSION as usize)?;
+        write_u32(&mut writer, BYTE_ORDER_MARK as usize)?;
+        write_u32(&mut writer, component_types.len())?;
+        for ty in component_types.iter() {
+            let registration = &registry.components[ty];
+            write_id(&mut writer, registration.id)?;
+            write_u32(&mut writer, registration.size)?;
+        }
+        write_u32(&mut writer, tag_types.len())?;
+        for ty in tag_types.iter() {
+            let registration = &registry.tags[ty];
+            write_id(&mut writer, registration.id)?;
+            write_u32(&mut writer, registration.size)?;
+        }
+        write_u32(&mut writer, layouts.len())?;
+        for layout in layouts.iter() {
+            write_indices(&mut writer, &layout.tags)?;
+            write_indices(&mut writer, &layout.components)?;
+            write_u32(&mut writer, layout.chunk_count)?;
+        }
+
+        // chunks
+        for (archetype, layout) in archetypes.iter().zip(layouts.iter()) {
+            for chunk in archetype.chunks().iter().filter(|c| c.len() > 0) {
+                World::write_chunk(
+                    &mut writer,
+                    registry,
+                    chunk,
+                    layout,
+                    &component_types,
+                    &tag_types,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_chunk<W: Write>(
+        writer: &mut W,
+        registry: &PodRegistry,
+        chunk: &Chunk,
+        layout: &ArchetypeLayout,
+        component_types: &[ComponentTypeId],
+        tag_types: &[TagTypeId],
+    ) -> Result<(), BinaryError> {
+        for index in layout.tags.iter() {
+            let ty = &tag_types[*index];
+            let size = registry.tags[ty].size;
+            let tag = unsafe {
+                std::slice::from_raw_parts(chunk.tag_raw(ty).unwrap().as_ptr() as *const u8, size)
+            };
+            writer.write_all(tag)?;
+        }
+
+        write_u32(writer, chunk.len())?;
+        for entity in unsafe { chunk.entities() } {
+            write_u32(writer, entity.index as usize)?;
+            write_u32(writer, entity.version.0 as usize)?;
+        }
+
+        for index in layout.components.iter() {
+            let bytes = chunk.component_bytes(&component_types[*index]).unwrap();
+            writer.write_all(&bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a world which was written via `World::write_binary` into a new `World` within
+    /// `universe`.
+    ///
+    /// Entities keep the IDs they were written with. Reading fails if any of those IDs may
+    /// already have been allocated by another world in `universe`.
+    pub fn read_binary<R: Read>(
+        universe: &Universe,
+        registry: &PodRegistry,
+        reader: R,
+    ) -> Result<World, BinaryError> {
+        let mut world = universe.create_world();
+        let entities = World::read_binary_chunks(&mut world, registry, reader)?;
+        if !world.allocator.restore_ids(entities.into_values()) {
+            return Err(BinaryError::EntityIdsInUse);
+        }
+
+        world.relocate_all();
+        Ok(world)
+    }
+
+    /// Reads a world which was written via `World::write_binary` into this world.
+    ///
+    /// Each entity which is read is allocated a new ID from this world's universe, so the data
+    /// can be loaded any number of times. Components registered in `mappers` have their `Entity`
+    /// references rewritten to the new IDs.
+    ///
+    /// Returns a map from each entity's written ID to its new ID. Nothing is added to the world
+    /// if reading fails.
+    pub fn read_binary_into<R: Read>(
+        &mut self,
+        registry: &PodRegistry,
+        mappers: &EntityMappers,
+        reader: R,
+    ) -> Result<EntityMap, BinaryError> {
+        let mut loaded = self.create_sibling();
+        World::read_binary_chunks(&mut loaded, registry, reader)?;
+        Ok(self.merge_foreign(loaded, mappers))
+    }
+
+    /// Reads the archetypes and chunks of the binary format into `world`, without allocating the
+    /// IDs of the entities which are read.
+    ///
+    /// Returns the IDs of the entities which were read.
+    fn read_binary_chunks<R: Read>(
+        world: &mut World,
+        registry: &PodRegistry,
+        mut reader: R,
+    ) -> Result<FnvHashMap<EntityIndex, Entity>, BinaryError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(BinaryError::InvalidData("not a legion world".into()));
+        }
+        if read_u32(&mut reader)? != FORMAT_VERSION as usize {
+            return Err(BinaryError::InvalidData(
+                "unsupported format version".into(),
+            ));
+        }
+        if read_u32(&mut reader)? != BYTE_ORDER_MARK as usize {
+            return Err(BinaryError::InvalidData("mismatched byte order".into()));
+        }
+
+        // resolve the type tables against the registry
+        let mut component_types = Vec::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let id = read_id(&mut reader)?;
+            let size = read_u32(&mut reader)?;
+            let ty = registry
+                .types
+                .component_by_id(id)
+                .ok_or(BinaryError::UnknownType(id))?;
+            if registry.components[&ty].size != size {
+                return Err(BinaryError::InvalidData(format!(
+                    "size of component type `{}` has changed",
+                    registry.types.component(&ty).unwrap().name()
+                )));
+            }
+            component_types.push(ty);
+        }
+
+        let mut tag_types = Vec::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let id = read_id(&mut reader)?;
+            let size = read_u32(&mut reader)?;
+            let ty = registry
+                .types
+                .tag_by_id(id)
+                .ok_or(BinaryError::UnknownType(id))?;
+            if registry.tags[&ty].size != size {
+                return Err(BinaryError::InvalidData(format!(
+                    "size of tag type `{}` has changed",
+                    registry.types.tag(&ty).unwrap().name()
+                )));
+            }
+            tag_types.push(ty);
+        }
+
+        let mut layouts = Vec::new();
+        for _ in 0..read_u32(&mut reader)? {
+            layouts.push(ArchetypeLayout {
+                tags: read_indices(&mut reader, tag_types.len())?,
+                components: read_indices(&mut reader, component_types.len())?,
+                chunk_count: read_u32(&mut reader)?,
+            });
+        }
+
+        let mut entities = FnvHashMap::default();
+        for layout in layouts.iter() {
+            let tags = layout
+                .tags
+                .iter()
+                .map(|i| tag_types[*i])
+                .collect::<Vec<_>>();
+            let components = layout
+                .components
+                .iter()
+                .map(|i| component_types[*i])
+                .collect::<Vec<_>>();
+            let tag_set = tags.iter().cloned().collect::<FnvHashSet<_>>();
+            let component_set = components.iter().cloned().collect::<FnvHashSet<_>>();
+            if tag_set.len() != tags.len() || component_set.len() != components.len() {
+                return Err(BinaryError::InvalidData(
+                    "archetype contains duplicate types".into(),
+                ));
+            }
+
+            let archetype_index = world.find_or_create_archetype(component_set, tag_set);
+            for _ in 0..layout.chunk_count {
+                let mut builder = ChunkBuilder::new();
+                builder.set_pool(world.chunk_pool.clone());
+                for ty in tags.iter() {
+                    let registration = &registry.tags[ty];
+                    builder.register_tag_raw(*ty, registration.size, registration.vtable);
+                }
+                for ty in components.iter() {
+                    builder.register_component_raw(*ty, registry.components[ty].size, None);
+                }
+
+                let archetype = &mut world.archetypes[archetype_index as usize];
+                let chunk = World::read_chunk(
+                    &mut reader,
+                    registry,
+                    builder,
+                    archetype,
+                    &tags,
+                    &components,
+                    &mut entities,
+                )?;
+                archetype.push_chunk(chunk);
+            }
+        }
+
+        Ok(entities)
+    }
+
+    fn read_chunk<R: Read>(
+        reader: &mut R,
+        registry: &PodRegistry,
+        builder: ChunkBuilder,
+        archetype: &mut Archetype,
+        tags: &[TagTypeId],
+        components: &[ComponentTypeId],
+        entities: &mut FnvHashMap<EntityIndex, Entity>,
+    ) -> Result<Chunk, BinaryError> {
+        let mut chunk = builder.build(archetype.allocate_chunk_id());
+        for ty in tags.iter() {
+            let size = registry.tags[ty].size;
+            unsafe {
+                let tag = chunk.tag_init_unchecked(ty).unwrap();
+                read_raw(reader, tag.as_ptr(), size)?;
+            }
+        }
+
+        let len = read_u32(reader)?;
+        if len == 0 || len > chunk.capacity() {
+            return Err(BinaryError::InvalidData(format!(
+                "chunk contains {} entities, but has capacity for {}",
+                len,
+                chunk.capacity()
+            )));
+        }
+
+        let mut chunk_entities = Vec::with_capacity(len);
+        for _ in 0..len {
+            let index = read_u32(reader)? as EntityIndex;
+            let version = Wrapping(read_u32(reader)? as u32);
+            let entity = Entity::new(index, version);
+            // entity IDs which differ only in their version would still collide when allocated
+            if entities.insert(index, entity).is_some() {
+                return Err(BinaryError::InvalidData(format!(
+                    "duplicate entity {}",
+                    entity
+                )));
+            }
+            chunk_entities.push(entity);
+        }
+
+        for ty in components.iter() {
+            let size = registry.components[ty].size;
+            unsafe {
+                let column = chunk.components_mut_raw_untyped(ty, 0).unwrap();
+                read_raw(reader, column.as_ptr(), size * len)?;
+            }
+        }
+
+        // all columns have been initialized, so the chunk can now take ownership of them
+        unsafe { chunk.entities_unchecked().extend(chunk_entities) };
+        Ok(chunk)
+    }
+}
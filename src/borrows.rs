@@ -273,6 +273,16 @@ pub struct BorrowedIter<'a, I: 'a + Iterator> {
     state: Borrow<'a>,
 }
 
+impl<'a, I: 'a + Iterator> BorrowedIter<'a, I> {
+    /// Constructs a new `BorrowedIter<'a, I>`.
+    pub fn new(inner: I, borrow: Borrow<'a>) -> BorrowedIter<'a, I> {
+        BorrowedIter {
+            inner,
+            state: borrow,
+        }
+    }
+}
+
 impl<'a, I: 'a + Iterator> Iterator for BorrowedIter<'a, I> {
     type Item = I::Item;
 
@@ -287,6 +297,95 @@ impl<'a, I: 'a + Iterator> Iterator for BorrowedIter<'a, I> {
 
 impl<'a, I: 'a + ExactSizeIterator> ExactSizeIterator for BorrowedIter<'a, I> {}
 
+/// A mutable reference to a component which records when it is written.
+///
+/// The component's change tick is only updated when the reference is dereferenced mutably.
+pub struct TrackedMut<'a, T: 'a> {
+    value: &'a mut T,
+    tick: &'a mut usize,
+    version: usize,
+}
+
+impl<'a, T: 'a> TrackedMut<'a, T> {
+    /// Constructs a new `TrackedMut<'a, T>`, which sets `tick` to `version` when written.
+    pub fn new(value: &'a mut T, tick: &'a mut usize, version: usize) -> TrackedMut<'a, T> {
+        TrackedMut {
+            value,
+            tick,
+            version,
+        }
+    }
+}
+
+impl<'a, T: 'a> Deref for TrackedMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T: 'a> DerefMut for TrackedMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        *self.tick = self.version;
+        self.value
+    }
+}
+
+impl<'a, T: 'a> AsRef<T> for TrackedMut<'a, T> {
+    fn as_ref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: 'a + Debug> Debug for TrackedMut<'a, T> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.value.fmt(formatter)
+    }
+}
+
+impl<'a, T: 'a + Display> Display for TrackedMut<'a, T> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.value.fmt(formatter)
+    }
+}
+
+/// An iterator which yields a `TrackedMut` for each element of a slice.
+pub struct TrackedIter<'a, T: 'a> {
+    values: IterMut<'a, T>,
+    ticks: IterMut<'a, usize>,
+    version: usize,
+}
+
+impl<'a, T: 'a> TrackedIter<'a, T> {
+    /// Constructs a new `TrackedIter<'a, T>`, which sets the tick of each value written to
+    /// `version`.
+    pub fn new(values: &'a mut [T], ticks: &'a mut [usize], version: usize) -> TrackedIter<'a, T> {
+        TrackedIter {
+            values: values.iter_mut(),
+            ticks: ticks.iter_mut(),
+            version,
+        }
+    }
+}
+
+impl<'a, T: 'a> Iterator for TrackedIter<'a, T> {
+    type Item = TrackedMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.values.next(), self.ticks.next()) {
+            (Some(value), Some(tick)) => Some(TrackedMut::new(value, tick, self.version)),
+            _ => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for TrackedIter<'a, T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,7 +1,8 @@
 use crate::{Archetype, ChunkBuilder, ComponentTypeId, Entity, EntityAllocator, TagTypeId};
 use easy_ffi::*;
 use fnv::FnvHashSet;
-use std::os::raw::c_void;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
 
 easy_ffi!(bool_ffi =>
     |err| {
@@ -43,6 +44,39 @@ easy_ffi!(void_ffi =>
     }
 );
 
+/// Defines an FFI function which dereferences its pointer arguments, and so must be declared
+/// `unsafe`. Errors and panics are reported as by the `easy_ffi` wrappers above, and the function
+/// returns `$failed`.
+macro_rules! unsafe_ffi {
+    (
+        $failed:expr;
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $arg_ty:ty),* $(,)*) -> Result<$ok_ty:ty, $err_ty:ty>
+        $body:block
+    ) => {
+        #[no_mangle]
+        $(#[$attr])*
+        pub unsafe extern "C" fn $name($($arg: $arg_ty),*) -> $ok_ty {
+            let result: std::thread::Result<Result<$ok_ty, $err_ty>> =
+                std::panic::catch_unwind(move || $body);
+            match result {
+                Ok(Ok(value)) => value,
+                Ok(Err(err)) => {
+                    println!("{}", err);
+                    $failed
+                }
+                Err(panic_val) => {
+                    match panic_val.downcast_ref::<&'static str>() {
+                        Some(s) => println!("panic: {}", s),
+                        None => println!("unknown panic!"),
+                    }
+                    $failed
+                }
+            }
+        }
+    };
+}
+
 #[repr(C)]
 pub struct Universe {
     _private: [u8; 0],
@@ -51,6 +85,33 @@ pub struct Universe {
 pub struct World {
     _private: [u8; 0],
 }
+#[repr(C)]
+pub struct TypeRegistry {
+    _private: [u8; 0],
+}
+/// A `registry::StableTypeId`, split into its high and low 64 bits.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StableTypeId {
+    pub high: u64,
+    pub low: u64,
+}
+
+impl From<crate::registry::StableTypeId> for StableTypeId {
+    fn from(id: crate::registry::StableTypeId) -> Self {
+        StableTypeId {
+            high: (id.0 >> 64) as u64,
+            low: id.0 as u64,
+        }
+    }
+}
+
+impl From<StableTypeId> for crate::registry::StableTypeId {
+    fn from(id: StableTypeId) -> Self {
+        crate::registry::StableTypeId((id.high as u128) << 64 | id.low as u128)
+    }
+}
+
 /// Component that provides TypeId for external components
 struct ExternalComponent;
 
@@ -230,16 +291,14 @@ impl crate::EntitySource for (&EntityData, &mut EntityResult) {
                             ext_type_id(),
                             *data.component_types.offset(comp_idx as isize),
                         ),
-                        0,
+                        dst_entity_start,
                     )
                     .expect("component storage did not exist when writing chunk");
                 let comp_size = (*data.component_data_sizes.offset(comp_idx as isize)) as usize;
                 let comp_data_ptr = (*data.component_data.offset(comp_idx as isize)) as *mut u8;
                 std::ptr::copy_nonoverlapping(
                     comp_data_ptr.offset((src_entity_start * comp_size) as isize),
-                    storage
-                        .as_ptr()
-                        .offset((dst_entity_start * comp_size) as isize),
+                    storage.as_ptr(),
                     comp_size as usize * count,
                 );
             }
@@ -368,6 +427,190 @@ void_ffi!(
     }
 );
 
+unsafe_ffi!(
+    false;
+    /// Derives a stable type ID from a type name.
+    ///
+    /// # Safety
+    ///
+    /// `name` must be a valid nul-terminated string, and `result` must be valid for writes.
+    fn lgn_stable_type_id_from_name(
+        name: *const c_char,
+        result: *mut StableTypeId,
+    ) -> Result<bool, &'static str> {
+        let name = CStr::from_ptr(name)
+            .to_str()
+            .map_err(|_| "invalid type name")?;
+        *result.as_mut().unwrap() = crate::registry::StableTypeId::from_name(name).into();
+        Ok(true)
+    }
+);
+ptr_ffi!(
+    fn lgn_type_registry_new() -> Result<*mut TypeRegistry, &'static str> {
+        let registry = Box::new(crate::registry::TypeRegistry::default());
+        Ok(Box::into_raw(registry) as *mut TypeRegistry)
+    }
+);
+unsafe_ffi!(
+    ();
+    /// Frees a registry created by `lgn_type_registry_new`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and must not be used afterwards.
+    fn lgn_type_registry_free(ptr: *mut TypeRegistry) -> Result<(), &'static str> {
+        let _registry = Box::from_raw(ptr as *mut crate::registry::TypeRegistry);
+        // let registry be dropped
+        Ok(())
+    }
+);
+unsafe_ffi!(
+    false;
+    /// Registers a component type under a stable ID and name.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and `name` must be a valid
+    /// nul-terminated string.
+    fn lgn_type_registry_register_component(
+        ptr: *mut TypeRegistry,
+        ty: u32,
+        id: StableTypeId,
+        name: *const c_char,
+    ) -> Result<bool, &'static str> {
+        let registry = (ptr as *mut crate::registry::TypeRegistry)
+            .as_mut()
+            .unwrap();
+        let name = CStr::from_ptr(name)
+            .to_str()
+            .map_err(|_| "invalid type name")?;
+        registry.register_component_raw(ComponentTypeId(ext_type_id(), ty), id.into(), name);
+        Ok(true)
+    }
+);
+unsafe_ffi!(
+    false;
+    /// Registers a tag type under a stable ID and name.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and `name` must be a valid
+    /// nul-terminated string.
+    fn lgn_type_registry_register_tag(
+        ptr: *mut TypeRegistry,
+        ty: u32,
+        id: StableTypeId,
+        name: *const c_char,
+    ) -> Result<bool, &'static str> {
+        let registry = (ptr as *mut crate::registry::TypeRegistry)
+            .as_mut()
+            .unwrap();
+        let name = CStr::from_ptr(name)
+            .to_str()
+            .map_err(|_| "invalid type name")?;
+        registry.register_tag_raw(TagTypeId(ext_type_id(), ty), id.into(), name);
+        Ok(true)
+    }
+);
+unsafe_ffi!(
+    false;
+    /// Finds the component type registered under a stable ID.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and `ty` must be valid for writes.
+    fn lgn_type_registry_find_component(
+        ptr: *mut TypeRegistry,
+        id: StableTypeId,
+        ty: *mut u32,
+    ) -> Result<bool, &'static str> {
+        let registry = (ptr as *mut crate::registry::TypeRegistry)
+            .as_ref()
+            .unwrap();
+        match registry.component_by_id(id.into()) {
+            Some(ComponentTypeId(type_id, external)) if type_id == ext_type_id() => {
+                *ty.as_mut().unwrap() = external;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+);
+unsafe_ffi!(
+    false;
+    /// Finds the tag type registered under a stable ID.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and `ty` must be valid for writes.
+    fn lgn_type_registry_find_tag(
+        ptr: *mut TypeRegistry,
+        id: StableTypeId,
+        ty: *mut u32,
+    ) -> Result<bool, &'static str> {
+        let registry = (ptr as *mut crate::registry::TypeRegistry)
+            .as_ref()
+            .unwrap();
+        match registry.tag_by_id(id.into()) {
+            Some(TagTypeId(type_id, external)) if type_id == ext_type_id() => {
+                *ty.as_mut().unwrap() = external;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+);
+unsafe_ffi!(
+    false;
+    /// Gets the stable ID of a registered component type.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and `result` must be valid for
+    /// writes.
+    fn lgn_type_registry_component_id(
+        ptr: *mut TypeRegistry,
+        ty: u32,
+        result: *mut StableTypeId,
+    ) -> Result<bool, &'static str> {
+        let registry = (ptr as *mut crate::registry::TypeRegistry)
+            .as_ref()
+            .unwrap();
+        match registry.component(&ComponentTypeId(ext_type_id(), ty)) {
+            Some(info) => {
+                *result.as_mut().unwrap() = info.id().into();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+);
+unsafe_ffi!(
+    false;
+    /// Gets the stable ID of a registered tag type.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `lgn_type_registry_new`, and `result` must be valid for
+    /// writes.
+    fn lgn_type_registry_tag_id(
+        ptr: *mut TypeRegistry,
+        ty: u32,
+        result: *mut StableTypeId,
+    ) -> Result<bool, &'static str> {
+        let registry = (ptr as *mut crate::registry::TypeRegistry)
+            .as_ref()
+            .unwrap();
+        match registry.tag(&TagTypeId(ext_type_id(), ty)) {
+            Some(info) => {
+                *result.as_mut().unwrap() = info.id().into();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+);
+
 #[cfg(test)]
 mod tests {
     use crate::c_api::*;
@@ -557,4 +800,51 @@ mod tests {
         lgn_world_free(world);
         lgn_universe_free(universe);
     }
+
+    #[test]
+    fn type_registry() {
+        unsafe {
+            let name = std::ffi::CString::new("pos").unwrap();
+            let mut pos_id = StableTypeId { high: 0, low: 0 };
+            assert!(lgn_stable_type_id_from_name(name.as_ptr(), &mut pos_id));
+            assert_eq!(
+                StableTypeId::from(crate::registry::StableTypeId::from_name("pos")),
+                pos_id
+            );
+
+            let registry = lgn_type_registry_new();
+            let pos = type_id_as_u32::<Pos>();
+            let model = type_id_as_u32::<Model>();
+            let model_id = StableTypeId { high: 1, low: 2 };
+            let model_name = std::ffi::CString::new("model").unwrap();
+            assert!(lgn_type_registry_register_component(
+                registry,
+                pos,
+                pos_id,
+                name.as_ptr()
+            ));
+            assert!(lgn_type_registry_register_tag(
+                registry,
+                model,
+                model_id,
+                model_name.as_ptr()
+            ));
+
+            let mut ty = 0;
+            assert!(lgn_type_registry_find_component(registry, pos_id, &mut ty));
+            assert_eq!(pos, ty);
+            assert!(lgn_type_registry_find_tag(registry, model_id, &mut ty));
+            assert_eq!(model, ty);
+            assert!(!lgn_type_registry_find_tag(registry, pos_id, &mut ty));
+
+            let mut id = StableTypeId { high: 0, low: 0 };
+            assert!(lgn_type_registry_component_id(registry, pos, &mut id));
+            assert_eq!(pos_id, id);
+            assert!(lgn_type_registry_tag_id(registry, model, &mut id));
+            assert_eq!(model_id, id);
+            assert!(!lgn_type_registry_component_id(registry, model, &mut id));
+
+            lgn_type_registry_free(registry);
+        }
+    }
 }
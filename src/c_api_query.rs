@@ -90,7 +90,7 @@ pub struct Query {
 
 struct QueryIterator {
     query: Query,
-    chunks: Vec<crate::ChunkId>,
+    chunks: Vec<(usize, usize)>,
     started: bool,
     current_index: usize,
     current: FFIQueriedChunk,
@@ -201,15 +201,15 @@ impl Iterator for QueryIterator {
         unsafe {
             world = (self.query.world as *mut crate::World).as_mut().unwrap();
         }
-        let chunk_id = &self
+        let (archetype_index, chunk_index) = *self
             .chunks
             .get(self.current_index)
-            .expect("Invalid iterator chunk_id");
+            .expect("Invalid iterator chunk index");
         let archetype = &world
             .archetypes
-            .get(chunk_id.1 as usize)
-            .expect("Iterator chunk_id pointed to invalid archetype");
-        let chunk = archetype.chunk(chunk_id.2).unwrap();
+            .get(archetype_index)
+            .expect("Iterator chunk index pointed to invalid archetype");
+        let chunk = archetype.chunk(chunk_index as crate::ChunkIndex).unwrap();
 
         for component in &self.query.accessor.components {
             unsafe {
@@ -250,9 +250,9 @@ impl Iterator for QueryIterator {
     }
 }
 
-fn filter_world(world: &crate::World, filter: &FilterData) -> Vec<crate::ChunkId> {
-    let mut matching_chunks: Vec<crate::ChunkId> = Vec::new();
-    for archetype in &world.archetypes {
+fn filter_world(world: &crate::World, filter: &FilterData) -> Vec<(usize, usize)> {
+    let mut matching_chunks: Vec<(usize, usize)> = Vec::new();
+    for (archetype_index, archetype) in world.archetypes.iter().enumerate() {
         // Inclusive Tags
         if filter
             .tags
@@ -285,8 +285,8 @@ fn filter_world(world: &crate::World, filter: &FilterData) -> Vec<crate::ChunkId
         {
             continue;
         }
-        for chunk in archetype.chunks() {
-            matching_chunks.push(chunk.id());
+        for chunk_index in 0..archetype.chunks().len() {
+            matching_chunks.push((archetype_index, chunk_index));
         }
     }
     matching_chunks
@@ -0,0 +1,799 @@
+//! Delta encoding of `World`s for network replication.
+//!
+//! A `DeltaEncoder` on the server records what it has already sent to one client. Each call to
+//! `DeltaEncoder::encode` compares the world against that record and produces a `WorldDelta`,
+//! which can be serialized with any serde format. It contains the IDs of deleted entities, and the
+//! changed component columns of each chunk. Changes are detected via the version numbers of chunk
+//! storage rather than by comparing values, so a column is sent whenever it has been borrowed
+//! mutably since the previous delta, and every column of a chunk is sent whenever entities have
+//! been added to, removed from or moved within it.
+//!
+//! A `DeltaDecoder` on the client applies each delta to a local world. Replicated entities are
+//! allocated local IDs, so the client world may also contain entities of its own. The `Entity`
+//! references of components registered in an `EntityMappers` are rewritten to the local IDs.
+//!
+//! Deltas must be applied in the order in which they were encoded, and none may be skipped. The
+//! component and tag types of replicated entities must be registered in the `SerializeRegistry`
+//! on both sides.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::delta::{DeltaDecoder, DeltaEncoder};
+//! # use legion::serialize::SerializeRegistry;
+//! # use legion::EntityMappers;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Position(f32);
+//!
+//! let mut registry = SerializeRegistry::default();
+//! registry.register_component::<Position>("position");
+//! let mappers = EntityMappers::default();
+//!
+//! let server_universe = Universe::new(None);
+//! let mut server = server_universe.create_world();
+//! let entity = server.insert_from((), vec![(Position(0.),)])[0];
+//!
+//! let client_universe = Universe::new(None);
+//! let mut client = client_universe.create_world();
+//!
+//! let mut encoder = DeltaEncoder::new();
+//! let mut decoder = DeltaDecoder::new();
+//! for _ in 0..3 {
+//!     *server.component_mut::<Position>(entity).unwrap() = Position(1.);
+//!
+//!     let delta = encoder.encode(&server, &registry).unwrap();
+//!     let json = serde_json::to_string(&delta).unwrap();
+//!
+//!     let mut deserializer = serde_json::Deserializer::from_str(&json);
+//!     decoder.apply(&mut client, &registry, &mappers, &mut deserializer).unwrap();
+//! }
+//!
+//! let replicated = decoder.entity(entity).unwrap();
+//! assert_eq!(Position(1.), *client.component::<Position>(replicated).unwrap());
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, SerializeTuple};
+use serde::{Deserializer, Serialize, Serializer};
+
+use crate::registry::StableTypeId;
+use crate::serialize::{
+    ComponentRegistration, Fields, MapFields, SeqFields, SerializeRegistry, TagRegistration,
+    TagSeed,
+};
+use crate::storage::{
+    Chunk, ChunkVersions, DynamicComponent, DynamicSingleEntitySource, DynamicTagSet,
+};
+use crate::*;
+
+/// An error returned by `DeltaEncoder::encode`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DeltaError {
+    /// An entity in the world has a component type which has not been registered.
+    UnregisteredComponent(ComponentTypeId),
+    /// An entity in the world has a tag type which has not been registered.
+    UnregisteredTag(TagTypeId),
+}
+
+impl Display for DeltaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DeltaError::UnregisteredComponent(ty) => {
+                write!(f, "component type {:?} is not registered", ty)
+            }
+            DeltaError::UnregisteredTag(ty) => write!(f, "tag type {:?} is not registered", ty),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+/// Tracks the state of a world which has been sent to one client, and encodes the changes made
+/// since then.
+#[derive(Default)]
+pub struct DeltaEncoder {
+    chunks: FnvHashMap<ChunkId, ChunkVersions>,
+    entities: FnvHashSet<Entity>,
+}
+
+impl DeltaEncoder {
+    /// Constructs a new `DeltaEncoder`, whose first delta contains the entire world.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets everything which has been sent, so that the next delta contains the entire world.
+    ///
+    /// Entities which the client has already received are replaced, rather than duplicated.
+    pub fn reset(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Encodes the changes made to `world` since the previous delta, and records them as sent.
+    ///
+    /// Nothing is recorded if an error is returned.
+    pub fn encode<'a>(
+        &mut self,
+        world: &'a World,
+        registry: &'a SerializeRegistry,
+    ) -> Result<WorldDelta<'a>, DeltaError> {
+        let mut chunks = FnvHashMap::default();
+        let mut entities = FnvHashSet::default();
+        let mut changes = Vec::new();
+
+        for archetype in world.archetypes.iter() {
+            let tags = archetype
+                .tags
+                .iter()
+                .map(|ty| {
+                    registry
+                        .tag_registration(ty)
+                        .map(|(_, r)| r)
+                        .ok_or(DeltaError::UnregisteredTag(*ty))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let components = archetype
+                .components
+                .iter()
+                .map(|ty| {
+                    registry
+                        .component_registration(ty)
+                        .map(|(_, r)| (*ty, r))
+                        .ok_or(DeltaError::UnregisteredComponent(*ty))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for chunk in archetype.chunks().iter().filter(|chunk| chunk.len() > 0) {
+                let versions = chunk.versions();
+
+                // a chunk ID may be reused by a chunk with another layout after a world is restored
+                let change = match self.chunks.get(&chunk.id()) {
+                    Some(previous)
+                        if previous.entities == versions.entities
+                            && previous.components.len() == versions.components.len()
+                            && versions
+                                .components
+                                .keys()
+                                .all(|ty| previous.components.contains_key(ty)) =>
+                    {
+                        ChunkChange {
+                            chunk,
+                            tags: None,
+                            components: components
+                                .iter()
+                                .filter(|(ty, _)| {
+                                    previous.components.get(ty) != versions.components.get(ty)
+                                })
+                                .map(|(_, r)| *r)
+                                .collect(),
+                        }
+                    }
+                    _ => ChunkChange {
+                        chunk,
+                        tags: Some(tags.clone()),
+                        components: components.iter().map(|(_, r)| *r).collect(),
+                    },
+                };
+                if change.tags.is_some() || !change.components.is_empty() {
+                    changes.push(change);
+                }
+
+                entities.extend(unsafe { chunk.entities() });
+                chunks.insert(chunk.id(), versions);
+            }
+        }
+
+        let deleted = self
+            .entities
+            .iter()
+            .filter(|entity| !entities.contains(*entity))
+            .cloned()
+            .collect();
+
+        self.chunks = chunks;
+        self.entities = entities;
+
+        Ok(WorldDelta {
+            deleted,
+            chunks: changes,
+        })
+    }
+}
+
+/// The changes made to a `World` since a previous delta, which can be serialized with serde.
+///
+/// Constructed by `DeltaEncoder::encode`.
+pub struct WorldDelta<'a> {
+    deleted: Vec<Entity>,
+    chunks: Vec<ChunkChange<'a>>,
+}
+
+impl<'a> WorldDelta<'a> {
+    /// Gets the IDs of the entities which have been deleted.
+    pub fn deleted(&self) -> &[Entity] {
+        &self.deleted
+    }
+
+    /// Determines if the delta contains no changes.
+    pub fn is_empty(&self) -> bool {
+        self.deleted.is_empty() && self.chunks.is_empty()
+    }
+}
+
+impl<'a> Serialize for WorldDelta<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("WorldDelta", 2)?;
+        state.serialize_field("deleted", &self.deleted)?;
+        state.serialize_field("chunks", &self.chunks)?;
+        state.end()
+    }
+}
+
+/// The changed columns of a chunk.
+///
+/// When the chunk's entities have changed, `tags` is `Some` and the chunk's entire contents are
+/// sent, so that the client can reconstruct each entity's layout.
+struct ChunkChange<'a> {
+    chunk: &'a Chunk,
+    tags: Option<Vec<&'a TagRegistration>>,
+    components: Vec<&'a ComponentRegistration>,
+}
+
+impl<'a> Serialize for ChunkChange<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tags = self.tags.as_ref().map(|tags| {
+            tags.iter()
+                .map(|r| TypedValue(r.id, (r.serialize_fn)(self.chunk)))
+                .collect::<Vec<_>>()
+        });
+        let components = self
+            .components
+            .iter()
+            .map(|r| TypedValue(r.id, (r.serialize_fn)(self.chunk)))
+            .collect::<Vec<_>>();
+
+        let mut state = serializer.serialize_struct("ChunkDelta", 3)?;
+        state.serialize_field("tags", &tags)?;
+        state.serialize_field("entities", unsafe { self.chunk.entities() })?;
+        state.serialize_field("components", &components)?;
+        state.end()
+    }
+}
+
+/// A value preceded by the stable ID of its type.
+struct TypedValue<V>(StableTypeId, V);
+
+impl<V: Serialize> Serialize for TypedValue<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_tuple(2)?;
+        state.serialize_element(&self.0)?;
+        state.serialize_element(&self.1)?;
+        state.end()
+    }
+}
+
+/// Applies the deltas produced by a `DeltaEncoder` to a client world.
+#[derive(Default)]
+pub struct DeltaDecoder {
+    entities: EntityMap,
+}
+
+impl DeltaDecoder {
+    /// Constructs a new `DeltaDecoder`, which has not yet replicated any entities.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the local ID of a replicated entity, given its ID on the server.
+    pub fn entity(&self, remote: Entity) -> Option<Entity> {
+        self.entities.get(&remote).cloned()
+    }
+
+    /// Gets the map from the server's entity IDs to the local IDs of all replicated entities.
+    pub fn entities(&self) -> &EntityMap {
+        &self.entities
+    }
+
+    /// Deserializes a delta and applies it to `world`.
+    ///
+    /// The delta is read in its entirety before any changes are made, so the world is left
+    /// unchanged if an error occurs.
+    pub fn apply<'de, D: Deserializer<'de>>(
+        &mut self,
+        world: &mut World,
+        registry: &SerializeRegistry,
+        mappers: &EntityMappers,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        let delta = DeltaSeed { registry }.deserialize(deserializer)?;
+
+        // check that the changed columns of existing chunks can be written before changing
+        // anything
+        for chunk in delta.chunks.iter().filter(|chunk| chunk.tags.is_none()) {
+            for entity in chunk.entities.iter() {
+                let local = self
+                    .entities
+                    .get(entity)
+                    .filter(|local| world.is_alive(local))
+                    .ok_or_else(|| {
+                        de::Error::custom(format!("entity {} has not been replicated", entity))
+                    })?;
+                let chunk_types = world.entity_chunk(*local).unwrap();
+                if let Some(ty) = chunk
+                    .types
+                    .iter()
+                    .find(|ty| chunk_types.component_version_untyped(ty).is_none())
+                {
+                    return Err(de::Error::custom(format!(
+                        "replicated entity {} does not have component type {:?}",
+                        entity, ty
+                    )));
+                }
+            }
+        }
+
+        let mut delta = delta;
+        for entity in delta.deleted.iter() {
+            if let Some(local) = self.entities.remove(entity) {
+                world.delete(local);
+            }
+        }
+
+        // allocate IDs for new entities before rewriting any references
+        let mut created = FnvHashSet::default();
+        for chunk in delta.chunks.iter().filter(|chunk| chunk.tags.is_some()) {
+            for entity in chunk.entities.iter() {
+                if !self.entities.contains_key(entity) {
+                    let local = world.allocator.create_entity();
+                    self.entities.insert(*entity, local);
+                    created.insert(local);
+                }
+            }
+        }
+
+        for chunk in delta.chunks.iter_mut() {
+            for column in chunk.columns.iter_mut() {
+                for component in column.iter_mut() {
+                    mappers.map_component(component, &self.entities);
+                }
+            }
+        }
+
+        for chunk in delta.chunks {
+            let mut columns = chunk
+                .columns
+                .into_iter()
+                .map(|column| column.into_iter())
+                .collect::<Vec<_>>();
+            for entity in chunk.entities.iter() {
+                let local = self.entities[entity];
+                let components = columns
+                    .iter_mut()
+                    .map(|column| column.next().unwrap())
+                    .collect::<Vec<_>>();
+
+                match chunk.tags {
+                    Some(ref tags) => {
+                        if !created.contains(&local) {
+                            world.remove_entity_data(local);
+                        }
+                        world.insert(
+                            tags.clone(),
+                            DynamicSingleEntitySource::new(local, components),
+                        );
+                    }
+                    None => {
+                        let (archetype, chunk, index) =
+                            world.allocator.get_location(&local.index).unwrap();
+                        let chunk = world.archetypes[archetype as usize]
+                            .chunk_mut(chunk)
+                            .unwrap();
+                        for component in components {
+                            let replaced = unsafe { component.replace(chunk, index as usize) };
+                            debug_assert!(replaced.is_ok());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A deserialized delta, whose values have not yet been written into a world.
+struct Delta {
+    deleted: Vec<Entity>,
+    chunks: Vec<ChunkDelta>,
+}
+
+/// The deserialized changes to one chunk.
+struct ChunkDelta {
+    tags: Option<DynamicTagSet>,
+    entities: Vec<Entity>,
+    types: Vec<ComponentTypeId>,
+    columns: Vec<Vec<DynamicComponent>>,
+}
+
+struct DeltaSeed<'a> {
+    registry: &'a SerializeRegistry,
+}
+
+impl<'a> DeltaSeed<'a> {
+    fn visit_fields<'de, F: Fields<'de>>(self, mut fields: F) -> Result<Delta, F::Error> {
+        let deleted = fields.next("deleted", PhantomData::<Vec<Entity>>)?;
+        let chunks = fields.next(
+            "chunks",
+            ChunkDeltasSeed {
+                registry: self.registry,
+            },
+        )?;
+
+        Ok(Delta { deleted, chunks })
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for DeltaSeed<'a> {
+    type Value = Delta;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Delta, D::Error> {
+        deserializer.deserialize_struct("WorldDelta", &["deleted", "chunks"], self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for DeltaSeed<'a> {
+    type Value = Delta;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a world delta")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Delta, A::Error> {
+        self.visit_fields(SeqFields(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Delta, A::Error> {
+        self.visit_fields(MapFields::new(map))
+    }
+}
+
+struct ChunkDeltasSeed<'a> {
+    registry: &'a SerializeRegistry,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ChunkDeltasSeed<'a> {
+    type Value = Vec<ChunkDelta>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ChunkDeltasSeed<'a> {
+    type Value = Vec<ChunkDelta>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of chunk deltas")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = seq.next_element_seed(ChunkDeltaSeed {
+            registry: self.registry,
+        })? {
+            chunks.push(chunk);
+        }
+
+        Ok(chunks)
+    }
+}
+
+struct ChunkDeltaSeed<'a> {
+    registry: &'a SerializeRegistry,
+}
+
+impl<'a> ChunkDeltaSeed<'a> {
+    fn visit_fields<'de, F: Fields<'de>>(self, mut fields: F) -> Result<ChunkDelta, F::Error> {
+        let tags = fields.next(
+            "tags",
+            OptionSeed(TagsSeed {
+                registry: self.registry,
+            }),
+        )?;
+        let entities = fields.next("entities", PhantomData::<Vec<Entity>>)?;
+        let (types, columns) = fields.next(
+            "components",
+            ColumnsSeed {
+                registry: self.registry,
+                len: entities.len(),
+            },
+        )?;
+
+        if tags.is_some() {
+            let unique = types.iter().collect::<FnvHashSet<_>>();
+            if unique.len() != types.len() {
+                return Err(de::Error::custom(
+                    "chunk contains duplicate component types",
+                ));
+            }
+        }
+
+        Ok(ChunkDelta {
+            tags,
+            entities,
+            types,
+            columns,
+        })
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ChunkDeltaSeed<'a> {
+    type Value = ChunkDelta;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<ChunkDelta, D::Error> {
+        deserializer.deserialize_struct("ChunkDelta", &["tags", "entities", "components"], self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ChunkDeltaSeed<'a> {
+    type Value = ChunkDelta;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a chunk delta")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<ChunkDelta, A::Error> {
+        self.visit_fields(SeqFields(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<ChunkDelta, A::Error> {
+        self.visit_fields(MapFields::new(map))
+    }
+}
+
+/// Deserializes an optional value with a seed.
+struct OptionSeed<T>(T);
+
+impl<'de, T: DeserializeSeed<'de>> DeserializeSeed<'de> for OptionSeed<T> {
+    type Value = Option<T::Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de, T: DeserializeSeed<'de>> Visitor<'de> for OptionSeed<T> {
+    type Value = Option<T::Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an optional value")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.0.deserialize(deserializer).map(Some)
+    }
+}
+
+/// Deserializes a sequence of tag values, each preceded by its stable type ID.
+struct TagsSeed<'a> {
+    registry: &'a SerializeRegistry,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for TagsSeed<'a> {
+    type Value = DynamicTagSet;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<DynamicTagSet, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for TagsSeed<'a> {
+    type Value = DynamicTagSet;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of tag values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DynamicTagSet, A::Error> {
+        let mut tags = DynamicTagSet::default();
+        let mut types = FnvHashSet::default();
+        while seq
+            .next_element_seed(TypedTagSeed {
+                registry: self.registry,
+                tags: &mut tags,
+                types: &mut types,
+            })?
+            .is_some()
+        {}
+
+        Ok(tags)
+    }
+}
+
+struct TypedTagSeed<'a, 'b> {
+    registry: &'a SerializeRegistry,
+    tags: &'b mut DynamicTagSet,
+    types: &'b mut FnvHashSet<TagTypeId>,
+}
+
+impl<'de, 'a, 'b> DeserializeSeed<'de> for TypedTagSeed<'a, 'b> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+impl<'de, 'a, 'b> Visitor<'de> for TypedTagSeed<'a, 'b> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a tag type ID and value")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let id = seq
+            .next_element::<StableTypeId>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let (ty, registration) = self
+            .registry
+            .tag(id)
+            .ok_or_else(|| de::Error::custom(format!("unknown tag type {}", id)))?;
+        if !self.types.insert(ty) {
+            return Err(de::Error::custom(format!("duplicate tag type {}", id)));
+        }
+
+        seq.next_element_seed(TagSeed {
+            registration,
+            tags: self.tags,
+            upgrades: &[],
+        })?
+        .ok_or_else(|| de::Error::invalid_length(1, &self))
+    }
+}
+
+/// Deserializes a sequence of component columns, each preceded by its stable type ID.
+struct ColumnsSeed<'a> {
+    registry: &'a SerializeRegistry,
+    len: usize,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ColumnsSeed<'a> {
+    type Value = (Vec<ComponentTypeId>, Vec<Vec<DynamicComponent>>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ColumnsSeed<'a> {
+    type Value = (Vec<ComponentTypeId>, Vec<Vec<DynamicComponent>>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of component columns")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut types = Vec::new();
+        let mut columns = Vec::new();
+        while let Some((ty, column)) = seq.next_element_seed(TypedColumnSeed {
+            registry: self.registry,
+            len: self.len,
+        })? {
+            types.push(ty);
+            columns.push(column);
+        }
+
+        Ok((types, columns))
+    }
+}
+
+struct TypedColumnSeed<'a> {
+    registry: &'a SerializeRegistry,
+    len: usize,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for TypedColumnSeed<'a> {
+    type Value = (ComponentTypeId, Vec<DynamicComponent>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for TypedColumnSeed<'a> {
+    type Value = (ComponentTypeId, Vec<DynamicComponent>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a component type ID and column")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let id = seq
+            .next_element::<StableTypeId>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let registration = self
+            .registry
+            .component(id)
+            .ok_or_else(|| de::Error::custom(format!("unknown component type {}", id)))?;
+
+        let column = seq
+            .next_element_seed(ColumnSeed {
+                registration,
+                len: self.len,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        Ok((registration.ty, column))
+    }
+}
+
+/// Deserializes a sequence of exactly `len` components.
+struct ColumnSeed<'a> {
+    registration: &'a ComponentRegistration,
+    len: usize,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ColumnSeed<'a> {
+    type Value = Vec<DynamicComponent>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ColumnSeed<'a> {
+    type Value = Vec<DynamicComponent>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of {} components", self.len)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut column = Vec::with_capacity(self.len);
+        while let Some(component) = seq.next_element_seed(ComponentSeed {
+            registration: self.registration,
+        })? {
+            if column.len() == self.len {
+                return Err(de::Error::invalid_length(self.len + 1, &self));
+            }
+            column.push(component);
+        }
+
+        if column.len() != self.len {
+            return Err(de::Error::invalid_length(column.len(), &self));
+        }
+
+        Ok(column)
+    }
+}
+
+struct ComponentSeed<'a> {
+    registration: &'a ComponentRegistration,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ComponentSeed<'a> {
+    type Value = DynamicComponent;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<DynamicComponent, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.registration.deserialize_one_fn)(&mut deserializer).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,438 @@
+//! Recording of the changes made to a `World`, for reproducing bugs.
+//!
+//! While a world is recording into a `Journal`, every entity inserted with `World::insert`,
+//! deleted with `World::delete` or changed with `World::mutate_entity`, and every component
+//! borrowed with `World::component_mut`, is appended to the journal as a `JournalEntry`. Bulk
+//! operations, such as `delete_where`, `add_component_where`, `merge` and `split_off`, append an
+//! entry for each entity they insert, change or remove. Entries hold clones of the affected
+//! entity's tags and components, cloned with the journal's `CloneRegistry`, so all component
+//! types which are written must be registered in it. Entities and writes with an unregistered
+//! component type are not recorded, and a warning is logged.
+//!
+//! Component writes are captured when the world is next changed, or when the journal is next
+//! accessed, so each entry holds the value the component was left with once its borrow ended.
+//! Writes made through queries are not recorded.
+//!
+//! A `Replay` applies the entries of a journal to another world, one at a time or all at once.
+//! Recorded entities are allocated new IDs in that world, and the `Entity` references of
+//! components registered in an `EntityMappers` are rewritten to match.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::journal::Journal;
+//! # use legion::storage::CloneRegistry;
+//! # use legion::EntityMappers;
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! struct Position(f32);
+//!
+//! let mut registry = CloneRegistry::default();
+//! registry.register::<Position>();
+//!
+//! let universe = Universe::new(None);
+//! let mut world = universe.create_world();
+//! world.record(Journal::new(&registry));
+//!
+//! let entity = world.insert_from((), vec![(Position(0.),)])[0];
+//! *world.component_mut::<Position>(entity).unwrap() = Position(1.);
+//!
+//! let journal = world.take_journal().unwrap();
+//! assert_eq!(2, journal.len());
+//!
+//! // reproduce the recorded changes step by step
+//! let mappers = EntityMappers::default();
+//! let mut replay = journal.replay(&mappers);
+//! let mut repro = universe.create_world();
+//! while replay.step(&mut repro).is_some() {}
+//!
+//! let replayed = replay.entity(entity).unwrap();
+//! assert_eq!(Position(1.), *repro.component::<Position>(replayed).unwrap());
+//! ```
+
+use slog::warn;
+
+use crate::storage::{CloneRegistry, DynamicComponent, DynamicSingleEntitySource, DynamicTagSet};
+use crate::*;
+
+/// A change to an entity, recorded in a `Journal`.
+pub enum JournalEntry {
+    /// An entity was inserted with the given tags and components.
+    Insert {
+        entity: Entity,
+        tags: DynamicTagSet,
+        components: Vec<DynamicComponent>,
+    },
+    /// An entity was changed by `World::mutate_entity`, and was left with the given tags and
+    /// components.
+    Mutate {
+        entity: Entity,
+        tags: DynamicTagSet,
+        components: Vec<DynamicComponent>,
+    },
+    /// An entity was deleted.
+    Delete { entity: Entity },
+    /// A component of an entity was written.
+    Write {
+        entity: Entity,
+        component: DynamicComponent,
+    },
+}
+
+impl JournalEntry {
+    /// Gets the ID of the entity which was changed, as it was in the recorded world.
+    pub fn entity(&self) -> Entity {
+        match self {
+            JournalEntry::Insert { entity, .. }
+            | JournalEntry::Mutate { entity, .. }
+            | JournalEntry::Delete { entity }
+            | JournalEntry::Write { entity, .. } => *entity,
+        }
+    }
+}
+
+/// An appendable log of the changes made to a `World`.
+///
+/// Constructed with `Journal::new`, and recorded into with `World::record`.
+pub struct Journal {
+    registry: CloneRegistry,
+    entries: Vec<JournalEntry>,
+    write: Option<(Entity, ComponentTypeId)>,
+}
+
+impl Journal {
+    /// Constructs a new, empty `Journal`, which clones component values with `registry`.
+    pub fn new(registry: &CloneRegistry) -> Self {
+        Journal {
+            registry: registry.clone(),
+            entries: Vec::new(),
+            write: None,
+        }
+    }
+
+    /// Gets the recorded entries, in the order in which they were recorded.
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Gets the number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determines if the journal contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Appends all entries of `other` to this journal.
+    pub fn append(&mut self, mut other: Journal) {
+        self.entries.append(&mut other.entries);
+    }
+
+    /// Constructs a `Replay` which applies this journal's entries to a world, from the start.
+    pub fn replay<'a>(&'a self, mappers: &'a EntityMappers) -> Replay<'a> {
+        Replay {
+            journal: self,
+            mappers,
+            position: 0,
+            entities: EntityMap::default(),
+        }
+    }
+
+    /// Clones the tags and components of an entity, or returns `None` if any of its component
+    /// types is not registered.
+    fn record_entity(
+        &self,
+        world: &World,
+        entity: Entity,
+    ) -> Option<(DynamicTagSet, Vec<DynamicComponent>)> {
+        let (archetype, chunk, index) = match world.entity_location(entity) {
+            Some(location) => location,
+            // an entity whose components have all been removed has no data to record
+            None => return Some((DynamicTagSet::default(), Vec::new())),
+        };
+        let chunk = world.archetypes[archetype as usize].chunk(chunk).unwrap();
+        match chunk.clone_entity(index, &self.registry) {
+            Ok(state) => Some(state),
+            Err(ty) => {
+                Journal::skip_unregistered(&world.logger, entity, ty);
+                None
+            }
+        }
+    }
+
+    fn skip_unregistered(logger: &slog::Logger, entity: Entity, ty: ComponentTypeId) {
+        warn!(logger, "not recording change to entity with unregistered component type";
+            "entity" => %entity, "component_type" => ?ty);
+    }
+
+    fn unregistered(ty: ComponentTypeId) -> ! {
+        panic!(
+            "component type {:?} has no clone function registered in the journal's CloneRegistry",
+            ty
+        )
+    }
+}
+
+/// Applies the entries of a `Journal` to a world, in order.
+pub struct Replay<'a> {
+    journal: &'a Journal,
+    mappers: &'a EntityMappers,
+    position: usize,
+    entities: EntityMap,
+}
+
+impl<'a> Replay<'a> {
+    /// Gets the index of the next entry to be applied.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Gets the number of entries which have not yet been applied.
+    pub fn remaining(&self) -> usize {
+        self.journal.len() - self.position
+    }
+
+    /// Gets the ID given to a recorded entity in the world being replayed into.
+    pub fn entity(&self, recorded: Entity) -> Option<Entity> {
+        self.entities.get(&recorded).cloned()
+    }
+
+    /// Gets the map from recorded entity IDs to the IDs given to them in the world being
+    /// replayed into.
+    pub fn entities(&self) -> &EntityMap {
+        &self.entities
+    }
+
+    /// Applies the next entry to `world`, and returns it.
+    ///
+    /// Returns `None` once every entry has been applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any component type in the entry has no clone function registered in the
+    /// journal's `CloneRegistry`.
+    pub fn step(&mut self, world: &mut World) -> Option<&'a JournalEntry> {
+        let entry = self.journal.entries.get(self.position)?;
+        self.position += 1;
+
+        match entry {
+            JournalEntry::Insert {
+                entity,
+                tags,
+                components,
+            } => {
+                let local = world.allocator.create_entity();
+                self.entities.insert(*entity, local);
+                let components = self.clone_components(components);
+                world.insert_entities(
+                    tags.clone(),
+                    DynamicSingleEntitySource::new(local, components),
+                );
+            }
+            JournalEntry::Mutate {
+                entity,
+                tags,
+                components,
+            } => {
+                if let Some(local) = self.live_entity(world, *entity) {
+                    let components = self.clone_components(components);
+                    world.remove_entity_data(local);
+                    world.insert_entities(
+                        tags.clone(),
+                        DynamicSingleEntitySource::new(local, components),
+                    );
+                }
+            }
+            JournalEntry::Delete { entity } => {
+                if let Some(local) = self.entities.remove(entity) {
+                    world.delete(local);
+                }
+            }
+            JournalEntry::Write { entity, component } => {
+                if let Some(local) = self.live_entity(world, *entity) {
+                    let mut component = self.clone_component(component);
+                    self.mappers.map_component(&mut component, &self.entities);
+
+                    let chunk = world.entity_chunk(local).unwrap();
+                    let (_, _, index) = world.allocator.get_location(&local.index).unwrap();
+                    let replaced = unsafe { component.replace(chunk, index as usize) };
+                    debug_assert!(replaced.is_ok());
+                }
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Applies all remaining entries to `world`.
+    pub fn run(&mut self, world: &mut World) {
+        while self.step(world).is_some() {}
+    }
+
+    fn live_entity(&self, world: &World, recorded: Entity) -> Option<Entity> {
+        self.entity(recorded).filter(|local| world.is_alive(local))
+    }
+
+    fn clone_component(&self, component: &DynamicComponent) -> DynamicComponent {
+        component
+            .try_clone(&self.journal.registry)
+            .unwrap_or_else(|| Journal::unregistered(component.type_id()))
+    }
+
+    fn clone_components(&self, components: &[DynamicComponent]) -> Vec<DynamicComponent> {
+        components
+            .iter()
+            .map(|component| {
+                let mut component = self.clone_component(component);
+                self.mappers.map_component(&mut component, &self.entities);
+                component
+            })
+            .collect()
+    }
+}
+
+impl World {
+    /// Starts recording changes to this world into `journal`.
+    ///
+    /// Any journal which was already being recorded into is dropped.
+    ///
+    /// While recording, entities and writes with a component type which has no clone function
+    /// registered in the journal's `CloneRegistry` are not recorded.
+    pub fn record(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    /// Gets the journal being recorded into, if any.
+    pub fn journal(&mut self) -> Option<&Journal> {
+        self.flush_journal();
+        self.journal.as_ref()
+    }
+
+    /// Stops recording, and returns the journal which was being recorded into.
+    pub fn take_journal(&mut self) -> Option<Journal> {
+        self.flush_journal();
+        self.journal.take()
+    }
+
+    /// Records the current value of the component which was last borrowed by `component_mut`,
+    /// if it has not yet been recorded.
+    pub(crate) fn flush_journal(&mut self) {
+        let World {
+            journal,
+            allocator,
+            archetypes,
+            logger,
+            ..
+        } = self;
+        if let Some(journal) = journal.as_mut() {
+            if let Some((entity, ty)) = journal.write.take() {
+                let location = allocator
+                    .get_location(&entity.index)
+                    .filter(|_| allocator.is_alive(&entity));
+                let component = location.and_then(|(archetype, chunk, index)| {
+                    archetypes[archetype as usize]
+                        .chunk(chunk)
+                        .and_then(|c| c.clone_component(&ty, index, &journal.registry))
+                });
+                match component {
+                    Some(Ok(component)) => journal
+                        .entries
+                        .push(JournalEntry::Write { entity, component }),
+                    Some(Err(ty)) => Journal::skip_unregistered(logger, entity, ty),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Records the entities which were inserted by the last call to `insert`.
+    pub(crate) fn record_inserted(&mut self) {
+        if self.journal.is_some() {
+            let entities = self.allocator.allocation_buffer().to_vec();
+            self.record_insertions(&entities);
+        }
+    }
+
+    /// Records the insertion of each of the given entities, such as those merged in from another
+    /// world.
+    pub(crate) fn record_insertions(&mut self, entities: &[Entity]) {
+        if let Some(journal) = self.journal.as_ref() {
+            let entries = entities
+                .iter()
+                .filter_map(|entity| {
+                    let (tags, components) = journal.record_entity(self, *entity)?;
+                    Some(JournalEntry::Insert {
+                        entity: *entity,
+                        tags,
+                        components,
+                    })
+                })
+                .collect::<Vec<_>>();
+            self.journal.as_mut().unwrap().entries.extend(entries);
+        }
+    }
+
+    /// Records the state of an entity after it was changed by `mutate_entity`.
+    pub(crate) fn record_mutated(&mut self, entity: Entity) {
+        if let Some((tags, components)) = self
+            .journal
+            .as_ref()
+            .and_then(|journal| journal.record_entity(self, entity))
+        {
+            self.journal
+                .as_mut()
+                .unwrap()
+                .entries
+                .push(JournalEntry::Mutate {
+                    entity,
+                    tags,
+                    components,
+                });
+        }
+    }
+
+    /// Records the deletion of an entity.
+    pub(crate) fn record_deleted(&mut self, entity: Entity) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.entries.push(JournalEntry::Delete { entity });
+        }
+    }
+
+    /// Records the deletion of each of the given entities.
+    pub(crate) fn record_deletions(&mut self, entities: &[Entity]) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.entries.extend(
+                entities
+                    .iter()
+                    .map(|entity| JournalEntry::Delete { entity: *entity }),
+            );
+        }
+    }
+
+    /// Gets the entities in the given chunks, or nothing if this world is not recording a
+    /// journal.
+    pub(crate) fn entities_to_journal<'a, I: IntoIterator<Item = &'a Chunk>>(
+        &self,
+        chunks: I,
+    ) -> Vec<Entity> {
+        match self.journal {
+            Some(_) => chunks
+                .into_iter()
+                .flat_map(|chunk| unsafe { chunk.entities() }.iter().copied())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Records that a component has been borrowed by `component_mut`.
+    ///
+    /// Its value is recorded when the journal is next flushed, once the borrow has ended.
+    pub(crate) fn record_write(&mut self, entity: Entity, ty: ComponentTypeId) {
+        self.flush_journal();
+        if let Some(journal) = self.journal.as_mut() {
+            journal.write = Some((entity, ty));
+        }
+    }
+}
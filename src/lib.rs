@@ -167,13 +167,29 @@
 //! # struct Static;
 //! # let universe = Universe::new(None);
 //! # let mut world = universe.create_world();
-//! // Queries can perform coarse-grained change detection, rejecting entities who's data
+//! // Queries can perform change detection, rejecting entities who's data
 //! // has not changed since the last time the query was iterated.
 //! let mut query = <(Read<Position>, Tagged<Model>)>::query()
 //!     .filter(changed::<Position>());
 //! for (pos, model) in query.iter(&world) {
 //!     // entities who have changed position
 //! }
+//!
+//! // `added` filters match the entities which have gained a component since the query
+//! // was last iterated.
+//! let mut query = <Read<Position>>::query().filter(added::<Position>());
+//! for pos in query.iter(&world) {
+//!     // entities who have been given a position
+//! }
+//!
+//! // `Write` marks every component it yields as changed, while `Tracked` only marks the
+//! // components which are actually written.
+//! let mut query = <(Tracked<Position>, Read<Velocity>)>::query();
+//! for (mut pos, vel) in query.iter(&world) {
+//!     if vel.dx != 0.0 {
+//!         pos.x += vel.dx;
+//!     }
+//! }
 //! ```
 //!
 //! ### Content Streaming
@@ -193,6 +209,50 @@
 //! world_a.merge(world_b);
 //! ```
 //!
+//! The `streaming::StreamingLoader` runs loader closures on a pool of worker threads, and merges
+//! the worlds they produce into the main world incrementally under a per-frame budget.
+//!
+//! ### Snapshots
+//!
+//! `World::snapshot` saves the state of a world, and `World::restore` rewinds the world to it, as
+//! needed for rollback networking. Snapshots share the copies of chunks which have not changed
+//! between them, so their cost scales with what has changed. See the `snapshot` module.
+//!
+//! For reproducing bugs, `World::record` records inserts, deletes, `mutate_entity` calls,
+//! `component_mut` writes and the entities changed by bulk operations such as `merge` into a
+//! `journal::Journal`, which can be replayed on another world one entry at a time.
+//!
+//! Component types registered with `World::buffer` are double-buffered: each call to
+//! `World::advance` saves their values, which can then be read with `Read<Prev<T>>` alongside the
+//! current values, as needed for interpolation.
+//!
+//! `World::freeze` produces a `view::WorldView` of the world's current contents, which can be read
+//! from other threads while the world continues to change. The view shares the world's chunk
+//! memory, and each component column is only copied when it is next written.
+//!
+//! ### Serialization
+//!
+//! With the `serde` feature enabled, worlds can be saved and loaded with any serde format via the
+//! `serialize` module. The component and tag types to be persisted are registered by name in a
+//! `serialize::SerializeRegistry`.
+//!
+//! The `binary` module provides a much faster format for worlds whose component and tag types are
+//! plain-old-data, which copies entire component columns to and from chunks.
+//!
+//! Both formats identify types by a `registry::StableTypeId`, which is derived from the registered
+//! name or assigned explicitly, rather than by `std::any::TypeId`, which may change between builds.
+//! The serde format also records a schema version for each type, so that data saved by older
+//! builds can be upgraded as it is loaded, and types which are no longer registered can be dropped
+//! or preserved. See the `schema` module.
+//!
+//! Loaded entities normally keep their saved IDs. `World::deserialize_into` and
+//! `World::read_binary_into` instead load into an existing world, allocating new IDs and rewriting
+//! the `Entity` references of components which implement `MapEntities`.
+//!
+//! For network replication, the `delta` module encodes only the entities and component columns
+//! which have changed since the previous delta, using the version numbers of chunk storage, and
+//! applies each delta to a client world.
+//!
 //! ### Chunk Iteration
 //!
 //! Entity data is allocated in blocks called "chunks", each approximately containing 64KiB of data. The query API exposes each chunk via 'iter_chunk'. As all entities in a chunk are guarenteed to contain the same set of entity data and shared data values, it is possible to do batch processing via the chunk API.
@@ -225,22 +285,37 @@
 //! }
 //! ```
 
+pub mod binary;
 pub mod borrows;
 #[cfg(feature = "c-api")]
 pub mod c_api;
 #[cfg(feature = "c-api")]
 pub mod c_api_query;
+#[cfg(feature = "serde")]
+pub mod delta;
+pub mod journal;
 pub mod query;
+pub mod registry;
+#[cfg(feature = "serde")]
+pub mod scene;
 #[cfg(feature = "schedule")]
 pub mod schedule;
+#[cfg(feature = "serde")]
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod snapshot;
 pub mod storage;
+pub mod streaming;
+pub mod view;
 
 use crate::borrows::*;
+use crate::query::{EntityMask, Filter, FilterResult};
 use crate::storage::*;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
-use fnv::FnvHashSet;
+use fnv::{FnvHashMap, FnvHashSet};
 use parking_lot::Mutex;
 use slog::{debug, info, o, trace, Drain};
 use std::any::TypeId;
@@ -252,7 +327,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 pub mod prelude {
-    pub use crate::query::{filter::*, IntoQuery, Query, Read, Tagged, Write};
+    pub use crate::query::{filter::*, IntoQuery, Prev, Query, Read, Tagged, Tracked, Write};
     pub use crate::{DataTypeId, DefaultComponentImpl, Entity, IntoTagSet, Universe, World};
 }
 
@@ -271,7 +346,7 @@ impl WorldId {
 pub struct ArchetypeId(u16, u16);
 
 impl ArchetypeId {
-    fn chunk(&self, id: u16) -> ChunkId {
+    fn chunk(&self, id: u64) -> ChunkId {
         ChunkId(self.0, self.1, id)
     }
 }
@@ -285,8 +360,10 @@ pub struct ComponentTypeId(pub TypeId, pub u32);
 pub struct TagTypeId(pub TypeId, pub u32);
 
 /// Unique Chunk ID.
+///
+/// Chunk IDs are never re-used within an archetype, even once their chunk has been freed.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct ChunkId(u16, u16, u16);
+pub struct ChunkId(u16, u16, u64);
 
 pub(crate) type EntityIndex = u32;
 pub(crate) type EntityVersion = Wrapping<u32>;
@@ -294,6 +371,7 @@ pub(crate) type EntityVersion = Wrapping<u32>;
 /// A handle to an entity.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "c-api", repr(C))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entity {
     index: EntityIndex,
     version: EntityVersion,
@@ -327,7 +405,8 @@ pub struct Universe {
     name: String,
     logger: slog::Logger,
     allocator: Arc<Mutex<BlockAllocator>>,
-    next_id: AtomicUsize,
+    chunk_pool: Arc<ChunkPool>,
+    next_id: Arc<AtomicUsize>,
 }
 
 impl Universe {
@@ -361,7 +440,8 @@ impl Universe {
             name,
             logger,
             allocator: Arc::from(Mutex::new(BlockAllocator::new())),
-            next_id: AtomicUsize::new(0),
+            chunk_pool: Arc::new(ChunkPool::new(0)),
+            next_id: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -372,10 +452,58 @@ impl Universe {
     ///
     /// Worlds belonging to the same universe can be safely merged via `World.merge`.
     pub fn create_world(&self) -> World {
+        self.world_factory().create_world()
+    }
+
+    /// Gets a handle which can create worlds within this `Universe` from any thread.
+    pub(crate) fn world_factory(&self) -> WorldFactory {
+        WorldFactory {
+            logger: self.logger.clone(),
+            allocator: self.allocator.clone(),
+            chunk_pool: self.chunk_pool.clone(),
+            next_id: self.next_id.clone(),
+        }
+    }
+
+    /// Gets the pool which chunks in all worlds in this universe allocate their memory from.
+    ///
+    /// The pool has a capacity of zero by default, in which case the memory of released chunks
+    /// is returned to the allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// let universe = Universe::new(None);
+    ///
+    /// // retain the memory of up to 64 released chunks for re-use by new chunks
+    /// universe.chunk_pool().set_capacity(64);
+    /// ```
+    pub fn chunk_pool(&self) -> &Arc<ChunkPool> {
+        &self.chunk_pool
+    }
+}
+
+/// Creates worlds within a `Universe`.
+#[derive(Clone)]
+pub(crate) struct WorldFactory {
+    logger: slog::Logger,
+    allocator: Arc<Mutex<BlockAllocator>>,
+    chunk_pool: Arc<ChunkPool>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl WorldFactory {
+    pub(crate) fn logger(&self) -> &slog::Logger {
+        &self.logger
+    }
+
+    pub(crate) fn create_world(&self) -> World {
         World::new(
-            WorldId(self.next_id.fetch_add(1, Ordering::SeqCst) as u16),
             self.logger.clone(),
             EntityAllocator::new(self.allocator.clone()),
+            self.chunk_pool.clone(),
+            self.next_id.clone(),
         )
     }
 }
@@ -388,6 +516,9 @@ pub(crate) type ArchetypeIndex = u16;
 struct BlockAllocator {
     allocated: usize,
     free: Vec<EntityBlock>,
+    /// Released entity indices which are no longer in use, waiting to be reclaimed by the
+    /// allocator which owns their block.
+    returned: Vec<Entity>,
 }
 
 impl BlockAllocator {
@@ -397,6 +528,7 @@ impl BlockAllocator {
         BlockAllocator {
             allocated: 0,
             free: Vec::new(),
+            returned: Vec::new(),
         }
     }
 
@@ -410,17 +542,79 @@ impl BlockAllocator {
         }
     }
 
-    pub fn free(&mut self, block: EntityBlock) {
+    pub fn free(&mut self, mut block: EntityBlock) {
+        self.returned.retain(|entity| {
+            if block.in_range(entity.index) {
+                if block.is_released(entity.index) {
+                    block.reclaim(*entity, false);
+                }
+                false
+            } else {
+                true
+            }
+        });
         self.free.push(block);
     }
+
+    /// Returns entity indices which were released by their owning allocator, and are no longer
+    /// in use by the allocator which adopted them, so that their block may re-use them.
+    pub fn return_entities<I: IntoIterator<Item = Entity>>(&mut self, entities: I) {
+        for entity in entities {
+            match self.free.iter_mut().find(|b| b.in_range(entity.index)) {
+                Some(block) if block.is_released(entity.index) => block.reclaim(entity, false),
+                Some(_) => {}
+                None => self.returned.push(entity),
+            }
+        }
+    }
+
+    /// Takes the returned entity indices for which `owned` returns `true`.
+    pub fn take_returned<F: Fn(EntityIndex) -> bool>(&mut self, owned: F) -> Vec<Entity> {
+        let (taken, kept) = std::mem::take(&mut self.returned)
+            .into_iter()
+            .partition(|entity| owned(entity.index));
+        self.returned = kept;
+        taken
+    }
+
+    /// Reserves the block of entity indices beginning at `start`, so that it will not be
+    /// allocated to any allocator.
+    ///
+    /// Returns `false` if the block has already been allocated.
+    pub fn reserve(&mut self, start: EntityIndex) -> bool {
+        if let Some(i) = self.free.iter().position(|b| b.start == start) {
+            self.free.swap_remove(i);
+            return true;
+        }
+
+        if (start as usize) < self.allocated {
+            return false;
+        }
+
+        // release any skipped blocks for use by other allocators
+        while self.allocated < start as usize {
+            let block = EntityBlock::new(self.allocated as EntityIndex, BlockAllocator::BLOCK_SIZE);
+            self.free.push(block);
+            self.allocated += BlockAllocator::BLOCK_SIZE;
+        }
+
+        self.allocated += BlockAllocator::BLOCK_SIZE;
+        true
+    }
+
+    /// Gets the start of the block which contains the given entity index.
+    fn block_start(index: EntityIndex) -> EntityIndex {
+        index - index % BlockAllocator::BLOCK_SIZE as EntityIndex
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct EntityBlock {
     start: EntityIndex,
     len: usize,
     versions: Vec<EntityVersion>,
     free: Vec<EntityIndex>,
+    released: FnvHashSet<EntityIndex>,
     locations: Vec<(ArchetypeIndex, ChunkIndex, ComponentIndex)>,
 }
 
@@ -431,6 +625,7 @@ impl EntityBlock {
             len: len,
             versions: Vec::with_capacity(len),
             free: Vec::new(),
+            released: FnvHashSet::default(),
             locations: std::iter::repeat((
                 0 as ArchetypeIndex,
                 0 as ChunkIndex,
@@ -441,6 +636,11 @@ impl EntityBlock {
         }
     }
 
+    /// Takes the block out of an `Arc`, copying it if it is still shared.
+    fn unshare(block: Arc<EntityBlock>) -> EntityBlock {
+        Arc::try_unwrap(block).unwrap_or_else(|block| (*block).clone())
+    }
+
     fn index(&self, index: EntityIndex) -> usize {
         (index - self.start) as usize
     }
@@ -449,10 +649,21 @@ impl EntityBlock {
         index >= self.start && index < (self.start + self.len as u32)
     }
 
+    pub fn is_released(&self, index: EntityIndex) -> bool {
+        self.released.contains(&index)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free.is_empty() && self.versions.len() >= self.len
+    }
+
     pub fn is_alive(&self, entity: &Entity) -> Option<bool> {
         if entity.index >= self.start {
             let i = self.index(entity.index);
-            self.versions.get(i).map(|v| *v == entity.version)
+            self.versions.get(i).map(|v| {
+                *v == entity.version
+                    && (self.released.is_empty() || !self.released.contains(&entity.index))
+            })
         } else {
             None
         }
@@ -473,15 +684,62 @@ impl EntityBlock {
 
     pub fn free(&mut self, entity: Entity) -> Option<bool> {
         if let Some(alive) = self.is_alive(&entity) {
-            let i = self.index(entity.index);
-            self.versions[i] += Wrapping(1);
-            self.free.push(entity.index);
+            if alive {
+                let i = self.index(entity.index);
+                self.versions[i] += Wrapping(1);
+                self.free.push(entity.index);
+            }
+            Some(alive)
+        } else {
+            None
+        }
+    }
+
+    /// Marks the given entity as alive in this block with its exact index and version.
+    ///
+    /// Any indices before it which have not yet been allocated are added to the free list.
+    /// They are given version 0, so that they are not considered alive for any handle
+    /// which may have been issued for them elsewhere.
+    pub fn insert(&mut self, entity: Entity) {
+        let i = self.index(entity.index);
+        while self.versions.len() <= i {
+            self.free
+                .push(self.start + self.versions.len() as EntityIndex);
+            self.versions.push(Wrapping(0));
+        }
+
+        self.free.retain(|index| *index != entity.index);
+        self.released.remove(&entity.index);
+        self.versions[i] = entity.version;
+    }
+
+    /// Marks a live entity as owned by another allocator.
+    ///
+    /// The entity is no longer alive in this block, but its index is not made available for
+    /// re-use until it is reclaimed.
+    pub fn release(&mut self, entity: Entity) -> Option<bool> {
+        if let Some(alive) = self.is_alive(&entity) {
+            if alive {
+                self.released.insert(entity.index);
+            }
             Some(alive)
         } else {
             None
         }
     }
 
+    /// Takes back ownership of an entity index which was previously released.
+    ///
+    /// If `alive` is `false`, the index is made available for re-use with the entity's version.
+    pub fn reclaim(&mut self, entity: Entity, alive: bool) {
+        let i = self.index(entity.index);
+        self.versions[i] = entity.version;
+        self.released.remove(&entity.index);
+        if !alive {
+            self.free.push(entity.index);
+        }
+    }
+
     pub fn set_location(
         &mut self,
         entity: &EntityIndex,
@@ -506,10 +764,20 @@ impl EntityBlock {
 }
 
 /// Manages the allocation and deletion of `Entity` IDs within a world.
+///
+/// Entity IDs are allocated from blocks which are owned by a single allocator. Entities which
+/// have been moved in from another world are "adopted": their index remains within a block
+/// owned by another allocator, and so is tracked individually. Adopted indices are returned
+/// to the `Universe` when the adopting allocator is dropped, and are reclaimed by the owner of
+/// their block once it runs out of other indices.
+///
+/// Blocks are shared with saved allocator states, and are copied when they are next changed.
 #[derive(Debug)]
 pub struct EntityAllocator {
     allocator: Arc<Mutex<BlockAllocator>>,
-    blocks: Vec<EntityBlock>,
+    blocks: Vec<Arc<EntityBlock>>,
+    adopted: FnvHashMap<EntityIndex, (EntityVersion, (ArchetypeIndex, ChunkIndex, ComponentIndex))>,
+    adopted_free: Vec<Entity>,
     entity_buffer: Vec<Entity>,
 }
 
@@ -518,33 +786,38 @@ impl EntityAllocator {
         EntityAllocator {
             allocator: allocator,
             blocks: Vec::new(),
+            adopted: FnvHashMap::default(),
+            adopted_free: Vec::new(),
             entity_buffer: Vec::new(),
         }
     }
 
     /// Determines if the given `Entity` is considered alive.
     pub fn is_alive(&self, entity: &Entity) -> bool {
-        self.blocks
-            .iter()
-            .filter_map(|b| b.is_alive(entity))
-            .nth(0)
-            .unwrap_or(false)
+        match self.blocks.iter().filter_map(|b| b.is_alive(entity)).nth(0) {
+            Some(alive) => alive,
+            None => self
+                .adopted
+                .get(&entity.index)
+                .map(|(version, _)| *version == entity.version)
+                .unwrap_or(false),
+        }
     }
 
     /// Allocates a new unused `Entity` ID.
     pub fn create_entity(&mut self) -> Entity {
-        let entity = if let Some(entity) = self
-            .blocks
-            .iter_mut()
-            .rev()
-            .filter_map(|b| b.allocate())
-            .nth(0)
-        {
+        let entity = if let Some(block) = self.blocks.iter_mut().rev().find(|b| !b.is_full()) {
+            Arc::make_mut(block).allocate().unwrap()
+        } else if let Some(entity) = self.adopted_free.pop() {
+            self.adopted
+                .insert(entity.index, (entity.version, (0, 0, 0)));
+            entity
+        } else if let Some(entity) = self.reclaim_returned() {
             entity
         } else {
             let mut block = self.allocator.lock().allocate();
             let entity = block.allocate().unwrap();
-            self.blocks.push(block);
+            self.blocks.push(Arc::new(block));
             entity
         };
 
@@ -552,12 +825,97 @@ impl EntityAllocator {
         entity
     }
 
+    /// Reclaims the indices in this allocator's blocks which were released to other allocators
+    /// and have since been returned, and allocates one of them.
+    fn reclaim_returned(&mut self) -> Option<Entity> {
+        let blocks = &self.blocks;
+        let returned = self
+            .allocator
+            .lock()
+            .take_returned(|index| blocks.iter().any(|b| b.in_range(index)));
+        for entity in returned {
+            if self.blocks.iter().any(|b| b.is_released(entity.index)) {
+                self.block_mut(entity.index).unwrap().reclaim(entity, false);
+            }
+        }
+
+        self.blocks
+            .iter_mut()
+            .rev()
+            .find(|b| !b.is_full())
+            .map(|block| Arc::make_mut(block).allocate().unwrap())
+    }
+
     pub(crate) fn delete_entity(&mut self, entity: Entity) -> bool {
+        match self.owning_block(entity) {
+            Some((block, alive)) => {
+                if alive {
+                    Arc::make_mut(block).free(entity);
+                }
+                alive
+            }
+            None => match self.adopted.get(&entity.index) {
+                Some((version, _)) if *version == entity.version => {
+                    self.adopted.remove(&entity.index);
+                    self.adopted_free
+                        .push(Entity::new(entity.index, entity.version + Wrapping(1)));
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Gives up ownership of a live entity, so that it may be adopted by another allocator
+    /// from the same `Universe`.
+    ///
+    /// Returns `false` if the entity was not alive.
+    pub(crate) fn release_entity(&mut self, entity: Entity) -> bool {
+        match self.owning_block(entity) {
+            Some((block, alive)) => {
+                if alive {
+                    Arc::make_mut(block).release(entity);
+                }
+                alive
+            }
+            None => match self.adopted.get(&entity.index) {
+                Some((version, _)) if *version == entity.version => {
+                    self.adopted.remove(&entity.index);
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Finds the block which has allocated the entity's index, and determines if the entity is
+    /// alive in it.
+    fn owning_block(&mut self, entity: Entity) -> Option<(&mut Arc<EntityBlock>, bool)> {
+        self.blocks
+            .iter_mut()
+            .find_map(|b| b.is_alive(&entity).map(|alive| (b, alive)))
+    }
+
+    /// Gets the block containing the given index for writing, copying it first if it is shared
+    /// with a saved state.
+    fn block_mut(&mut self, index: EntityIndex) -> Option<&mut EntityBlock> {
         self.blocks
             .iter_mut()
-            .filter_map(|b| b.free(entity))
-            .nth(0)
-            .unwrap_or(false)
+            .rev()
+            .find(|b| b.in_range(index))
+            .map(Arc::make_mut)
+    }
+
+    /// Takes ownership of a live entity which was released by another allocator from the same
+    /// `Universe`.
+    pub(crate) fn adopt_entity(&mut self, entity: Entity) {
+        match self.block_mut(entity.index) {
+            Some(block) => block.reclaim(entity, true),
+            None => {
+                self.adopted
+                    .insert(entity.index, (entity.version, (0, 0, 0)));
+            }
+        }
     }
 
     pub(crate) fn set_location(
@@ -565,24 +923,22 @@ impl EntityAllocator {
         entity: &EntityIndex,
         location: (ArchetypeIndex, ChunkIndex, ComponentIndex),
     ) {
-        self.blocks
-            .iter_mut()
-            .rev()
-            .filter(|b| b.in_range(*entity))
-            .next()
-            .unwrap()
-            .set_location(entity, location);
+        match self.block_mut(*entity) {
+            Some(block) => block.set_location(entity, location),
+            None => {
+                self.adopted.get_mut(entity).unwrap().1 = location;
+            }
+        }
     }
 
     pub(crate) fn get_location(
         &self,
         entity: &EntityIndex,
     ) -> Option<(ArchetypeIndex, ChunkIndex, ComponentIndex)> {
-        self.blocks
-            .iter()
-            .filter(|b| b.in_range(*entity))
-            .next()
-            .and_then(|b| b.get_location(entity))
+        match self.blocks.iter().filter(|b| b.in_range(*entity)).next() {
+            Some(block) => block.get_location(entity),
+            None => self.adopted.get(entity).map(|(_, location)| *location),
+        }
     }
 
     pub(crate) fn allocation_buffer(&self) -> &[Entity] {
@@ -594,268 +950,1822 @@ impl EntityAllocator {
     }
 
     pub(crate) fn merge(&mut self, mut other: EntityAllocator) {
-        assert!(Arc::ptr_eq(&self.allocator, &other.allocator));
+        assert!(self.shares_universe(&other));
         self.blocks.append(&mut other.blocks);
-    }
-}
 
-impl Drop for EntityAllocator {
-    fn drop(&mut self) {
-        for block in self.blocks.drain(..) {
-            self.allocator.lock().free(block);
+        let adopted = std::mem::take(&mut self.adopted)
+            .into_iter()
+            .chain(other.adopted.drain());
+        let adopted_free = std::mem::take(&mut self.adopted_free)
+            .into_iter()
+            .chain(other.adopted_free.drain(..))
+            .collect::<Vec<_>>();
+
+        // return adopted entities to their block, if we now own it
+        for (index, (version, location)) in adopted {
+            let entity = Entity::new(index, version);
+            match self.block_mut(index) {
+                Some(block) => {
+                    block.reclaim(entity, true);
+                    block.set_location(&index, location);
+                }
+                None => {
+                    self.adopted.insert(index, (version, location));
+                }
+            }
+        }
+
+        for entity in adopted_free {
+            match self.block_mut(entity.index) {
+                Some(block) => block.reclaim(entity, false),
+                None => self.adopted_free.push(entity),
+            }
         }
     }
-}
 
-/// Contains queryable collections of data associated with `Entity`s.
-pub struct World {
-    id: WorldId,
-    logger: slog::Logger,
-    allocator: EntityAllocator,
-    archetypes: Vec<Archetype>,
-    next_arch_id: u16,
-}
+    /// Copies the entity IDs which are alive in this allocator into `target`, which belongs to a
+    /// different `Universe`.
+    ///
+    /// The blocks containing the IDs are reserved in the target universe, so that they will not
+    /// be allocated by any other world within it. Locations are not copied.
+    ///
+    /// Returns `false` if any of the blocks have already been allocated in the target universe.
+    pub(crate) fn clone_ids_into(&self, target: &mut EntityAllocator) -> bool {
+        let mut blocks = self
+            .blocks
+            .iter()
+            .map(|b| b.start)
+            .chain(
+                self.adopted
+                    .keys()
+                    .map(|index| BlockAllocator::block_start(*index)),
+            )
+            .collect::<Vec<_>>();
+        blocks.sort();
+        blocks.dedup();
 
-impl World {
-    fn new(id: WorldId, logger: slog::Logger, allocator: EntityAllocator) -> Self {
-        let logger = logger.new(o!("world_id" => id.0));
+        {
+            let mut allocator = target.allocator.lock();
+            if blocks.iter().any(|start| {
+                (*start as usize) < allocator.allocated
+                    && !allocator.free.iter().any(|b| b.start == *start)
+            }) {
+                return false;
+            }
 
-        info!(logger, "starting world");
-        World {
-            id,
-            logger,
-            allocator: allocator,
-            archetypes: Vec::new(),
-            next_arch_id: 0,
+            for start in blocks.iter() {
+                assert!(allocator.reserve(*start));
+            }
+        }
+
+        for block in self.blocks.iter() {
+            let mut clone = EntityBlock::new(block.start, block.len);
+            for (i, version) in block.versions.iter().enumerate() {
+                let index = block.start + i as EntityIndex;
+                if block.is_alive(&Entity::new(index, *version)) == Some(true) {
+                    clone.insert(Entity::new(index, *version));
+                }
+            }
+            target.blocks.push(Arc::new(clone));
+        }
+
+        for (index, (version, _)) in self.adopted.iter() {
+            let start = BlockAllocator::block_start(*index);
+            let block = match target.blocks.iter().position(|b| b.start == start) {
+                Some(i) => &mut target.blocks[i],
+                None => {
+                    target.blocks.push(Arc::new(EntityBlock::new(
+                        start,
+                        BlockAllocator::BLOCK_SIZE,
+                    )));
+                    target.blocks.last_mut().unwrap()
+                }
+            };
+            Arc::make_mut(block).insert(Entity::new(*index, *version));
         }
+
+        true
     }
 
-    /// Merges two worlds together.
+    /// Marks the given entity IDs as alive in this allocator, preserving their exact index and
+    /// version.
     ///
-    /// This function moves all chunks from `other` into `self`. This operation is very fast,
-    /// however the resulting memory layout may be inefficient if `other` contains a very small
-    /// number of entities.
+    /// The blocks containing the IDs are reserved in this allocator's universe, so that they will
+    /// not be allocated by any other world within it. Locations are not set.
     ///
-    /// Merge is most effectively used to allow large numbers of entities to be loaded and
-    /// initialized in the background, and then shunted into the "main" world all at once, once ready.
-    ///
-    /// # Safety
-    ///
-    /// It is only safe to merge worlds which belong to the same `Universe`. This is currently not
-    /// validated by the API.
-    pub fn merge(&mut self, mut other: World) {
-        self.allocator.merge(other.allocator);
+    /// Returns `false` if any of the blocks have already been allocated in the universe.
+    pub(crate) fn restore_ids<I: IntoIterator<Item = Entity>>(&mut self, entities: I) -> bool {
+        let entities = entities.into_iter().collect::<Vec<_>>();
+        let mut blocks = entities
+            .iter()
+            .map(|entity| BlockAllocator::block_start(entity.index))
+            .collect::<Vec<_>>();
+        blocks.sort();
+        blocks.dedup();
 
-        let first_new_index = self.archetypes.len();
-        self.archetypes.append(&mut other.archetypes);
+        {
+            let mut allocator = self.allocator.lock();
+            if blocks.iter().any(|start| {
+                (*start as usize) < allocator.allocated
+                    && !allocator.free.iter().any(|b| b.start == *start)
+            }) {
+                return false;
+            }
 
-        for archetype_index in first_new_index..self.archetypes.len() {
-            let archetype = self.archetypes.get(archetype_index).unwrap();
-            for (chunk_index, chunk) in archetype.chunks().iter().enumerate() {
-                for (entity_index, entity) in unsafe { chunk.entities().iter().enumerate() } {
-                    self.allocator.set_location(
-                        &entity.index,
-                        (
-                            archetype_index as ArchetypeIndex,
-                            chunk_index as ChunkIndex,
-                            entity_index as ComponentIndex,
-                        ),
-                    );
-                }
+            for start in blocks.iter() {
+                assert!(allocator.reserve(*start));
             }
         }
+
+        for start in blocks {
+            self.blocks.push(Arc::new(EntityBlock::new(
+                start,
+                BlockAllocator::BLOCK_SIZE,
+            )));
+        }
+
+        for entity in entities {
+            self.block_mut(entity.index).unwrap().insert(entity);
+        }
+
+        true
     }
 
-    /// Determines if the given `Entity` is alive within this `World`.
-    pub fn is_alive(&self, entity: &Entity) -> bool {
-        self.allocator.is_alive(entity)
+    /// Determines if both allocators allocate entity IDs from the same `Universe`.
+    pub(crate) fn shares_universe(&self, other: &EntityAllocator) -> bool {
+        Arc::ptr_eq(&self.allocator, &other.allocator)
     }
 
-    /// Inserts entities from an iterator of component tuples.
+    /// Captures the allocation state and entity locations of this allocator.
     ///
-    /// # Examples
+    /// The state shares this allocator's blocks until either is changed.
+    pub(crate) fn save(&self) -> EntityAllocatorState {
+        EntityAllocatorState {
+            blocks: self.blocks.clone(),
+            adopted: self.adopted.clone(),
+            adopted_free: self.adopted_free.clone(),
+        }
+    }
+
+    /// Rewinds this allocator to a previously saved state.
     ///
-    /// Inserting entity tuples:
+    /// Blocks which have been acquired since the state was saved are returned to the `Universe`,
+    /// and blocks which have since been returned are reserved again.
     ///
-    /// ```
-    /// # use legion::prelude::*;
-    /// # #[derive(Copy, Clone, Debug, PartialEq)]
-    /// # struct Position(f32);
-    /// # #[derive(Copy, Clone, Debug, PartialEq)]
-    /// # struct Rotation(f32);
-    /// # let universe = Universe::new(None);
-    /// # let mut world = universe.create_world();
-    /// # let model = 0u8;
-    /// # let color = 0u16;
-    /// let tags = (model, color).as_tags();
-    /// let data = vec![
-    ///     (Position(0.0), Rotation(0.0)),
-    ///     (Position(1.0), Rotation(1.0)),
-    ///     (Position(2.0), Rotation(2.0)),
-    /// ];
-    /// world.insert_from(tags, data);
-    /// ```
-    pub fn insert_from<T, C>(&mut self, tags: T, components: C) -> &[Entity]
-    where
-        T: TagSet,
-        C: IntoIterator,
-        C::Item: ComponentSet,
-        IterEntitySource<C::IntoIter, C::Item>: EntitySource,
-    {
-        let source = C::Item::component_source(components.into_iter());
-        self.insert(tags, source)
-    }
+    /// Returns `false`, leaving the allocator unchanged, if any of the saved blocks have since
+    /// been allocated by another allocator.
+    pub(crate) fn restore(&mut self, state: &EntityAllocatorState) -> bool {
+        let owned = |blocks: &[Arc<EntityBlock>], start: EntityIndex| {
+            blocks.iter().any(|b| b.start == start)
+        };
+        let reserved = state
+            .blocks
+            .iter()
+            .map(|b| b.start)
+            .filter(|start| !owned(&self.blocks, *start))
+            .collect::<Vec<_>>();
 
-    /// Inserts entities from an `EntitySource`.
-    pub fn insert<T, C>(&mut self, mut tags: T, mut components: C) -> &[Entity]
-    where
-        T: TagSet,
-        C: EntitySource,
-    {
-        // find or create archetype
-        let (arch_index, archetype) = World::prep_archetype(
-            &self.id,
-            &mut self.archetypes,
-            &mut self.next_arch_id,
-            &mut self.logger,
-            &tags,
-            &components,
+        {
+            let mut allocator = self.allocator.lock();
+            if reserved.iter().any(|start| {
+                (*start as usize) < allocator.allocated
+                    && !allocator.free.iter().any(|b| b.start == *start)
+            }) {
+                return false;
+            }
+
+            for start in reserved {
+                assert!(allocator.reserve(start));
+            }
+
+            for block in std::mem::take(&mut self.blocks) {
+                if !owned(&state.blocks, block.start) {
+                    allocator.free(EntityBlock::unshare(block));
+                }
+            }
+        }
+
+        self.blocks = state.blocks.clone();
+        self.adopted = state.adopted.clone();
+        self.adopted_free = state.adopted_free.clone();
+        self.entity_buffer.clear();
+        true
+    }
+}
+
+/// The saved state of an `EntityAllocator`.
+#[derive(Debug)]
+pub(crate) struct EntityAllocatorState {
+    blocks: Vec<Arc<EntityBlock>>,
+    adopted: FnvHashMap<EntityIndex, (EntityVersion, (ArchetypeIndex, ChunkIndex, ComponentIndex))>,
+    adopted_free: Vec<Entity>,
+}
+
+impl Drop for EntityAllocator {
+    fn drop(&mut self) {
+        // adopted entity indices belong to blocks owned by other allocators, so are returned for
+        // those allocators to reclaim
+        let mut allocator = self.allocator.lock();
+        allocator.return_entities(
+            self.adopted
+                .drain()
+                .map(|(index, (version, _))| Entity::new(index, version + Wrapping(1)))
+                .chain(self.adopted_free.drain(..)),
+        );
+        for block in self.blocks.drain(..) {
+            allocator.free(EntityBlock::unshare(block));
+        }
+    }
+}
+
+/// Maps entity IDs from one world onto the IDs they were assigned in another.
+pub type EntityMap = FnvHashMap<Entity, Entity>;
+
+/// Implemented by components which hold references to other entities.
+///
+/// When entities are assigned new IDs, such as when merging a world from a foreign `Universe`,
+/// registered components are given the chance to rewrite their `Entity` fields.
+pub trait MapEntities {
+    /// Replaces any `Entity` references held by `self` with their mapped values.
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+/// A registry of the component types which implement `MapEntities`.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::*;
+/// #[derive(Debug)]
+/// struct Parent(Entity);
+///
+/// impl MapEntities for Parent {
+///     fn map_entities(&mut self, map: &EntityMap) {
+///         if let Some(entity) = map.get(&self.0) {
+///             self.0 = *entity;
+///         }
+///     }
+/// }
+///
+/// let mut mappers = EntityMappers::default();
+/// mappers.register::<Parent>();
+/// ```
+#[derive(Default, Clone)]
+pub struct EntityMappers {
+    mappers: FnvHashMap<ComponentTypeId, (MapChunkFn, MapValueFn)>,
+}
+
+type MapChunkFn = fn(&Chunk, &EntityMap);
+type MapValueFn = fn(*mut u8, &EntityMap);
+
+impl EntityMappers {
+    /// Registers a component type whose `Entity` references should be rewritten.
+    pub fn register<T: Component + MapEntities>(&mut self) -> &mut Self {
+        self.mappers.insert(
+            T::type_id(),
+            (EntityMappers::map_chunk::<T>, EntityMappers::map_value::<T>),
         );
+        self
+    }
+
+    fn map_chunk<T: Component + MapEntities>(chunk: &Chunk, map: &EntityMap) {
+        if let Some(components) = unsafe { chunk.components_mut_unchecked::<T>() } {
+            for component in components {
+                component.map_entities(map);
+            }
+        }
+    }
+
+    fn map_value<T: Component + MapEntities>(ptr: *mut u8, map: &EntityMap) {
+        unsafe { (*(ptr as *mut T)).map_entities(map) };
+    }
+
+    /// Rewrites the `Entity` references of all registered component types in the chunk.
+    fn map_entities(&self, chunk: &mut Chunk, map: &EntityMap) {
+        for (map_chunk, _) in self.mappers.values() {
+            map_chunk(chunk, map);
+        }
+    }
+
+    /// Rewrites the `Entity` references of a single component, if its type is registered.
+    pub(crate) fn map_component(&self, component: &mut DynamicComponent, map: &EntityMap) {
+        if let Some((_, map_value)) = self.mappers.get(&component.type_id()) {
+            map_value(component.as_mut_ptr(), map);
+        }
+    }
+}
+
+/// An error returned by `World::try_merge`.
+pub enum MergeError {
+    /// The world belongs to a different `Universe`. Contains the world which could not be merged.
+    ForeignUniverse(Box<World>),
+}
+
+impl MergeError {
+    /// Recovers the world which could not be merged.
+    pub fn into_world(self) -> World {
+        match self {
+            MergeError::ForeignUniverse(world) => *world,
+        }
+    }
+}
+
+impl Debug for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MergeError::ForeignUniverse(world) => {
+                f.debug_tuple("ForeignUniverse").field(&world.id).finish()
+            }
+        }
+    }
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MergeError::ForeignUniverse(_) => {
+                write!(
+                    f,
+                    "cannot merge a world which belongs to a different universe"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// An error returned when cloning entities.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CloneError {
+    /// A component type has no clone function registered in the `CloneRegistry`.
+    UnregisteredComponent(ComponentTypeId),
+    /// The entity IDs to be preserved have already been allocated in the target `Universe`.
+    EntityIdsInUse,
+}
+
+impl Display for CloneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CloneError::UnregisteredComponent(ty) => {
+                write!(
+                    f,
+                    "no clone function registered for component type {:?}",
+                    ty
+                )
+            }
+            CloneError::EntityIdsInUse => write!(
+                f,
+                "entity IDs have already been allocated in the target universe"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CloneError {}
+
+/// Contains queryable collections of data associated with `Entity`s.
+pub struct World {
+    id: WorldId,
+    logger: slog::Logger,
+    universe_logger: slog::Logger,
+    next_world_id: Arc<AtomicUsize>,
+    allocator: EntityAllocator,
+    chunk_pool: Arc<ChunkPool>,
+    archetypes: Vec<Archetype>,
+    next_arch_id: u16,
+    defrag_progress: usize,
+    snapshot_cache: snapshot::SnapshotCache,
+    journal: Option<journal::Journal>,
+    buffers: Vec<ComponentBuffer>,
+    change_tick: Arc<AtomicUsize>,
+    removed: FnvHashMap<ComponentTypeId, Vec<Entity>>,
+}
+
+impl World {
+    fn new(
+        universe_logger: slog::Logger,
+        allocator: EntityAllocator,
+        chunk_pool: Arc<ChunkPool>,
+        next_world_id: Arc<AtomicUsize>,
+    ) -> Self {
+        let id = WorldId(next_world_id.fetch_add(1, Ordering::SeqCst) as u16);
+        let logger = universe_logger.new(o!("world_id" => id.0));
+
+        info!(logger, "starting world");
+        World {
+            id,
+            logger,
+            universe_logger,
+            next_world_id,
+            allocator: allocator,
+            chunk_pool,
+            archetypes: Vec::new(),
+            next_arch_id: 0,
+            defrag_progress: 0,
+            snapshot_cache: snapshot::SnapshotCache::default(),
+            journal: None,
+            buffers: Vec::new(),
+            change_tick: Arc::new(AtomicUsize::new(0)),
+            removed: FnvHashMap::default(),
+        }
+    }
+
+    /// Creates a new, empty world within the same `Universe` as this world.
+    fn create_sibling(&self) -> World {
+        World::new(
+            self.universe_logger.clone(),
+            EntityAllocator::new(self.allocator.allocator.clone()),
+            self.chunk_pool.clone(),
+            self.next_world_id.clone(),
+        )
+    }
+
+    /// Merges two worlds together.
+    ///
+    /// This function moves all chunks from `other` into `self`. Chunks whose archetype does not
+    /// yet exist in `self` are moved wholesale, which is very fast. Chunks belonging to an
+    /// archetype which already exists in `self` are folded into that archetype; partially full
+    /// chunks are first used to top up existing chunks with the same tag values, and any remaining
+    /// entities keep their original chunk.
+    ///
+    /// Merge is most effectively used to allow large numbers of entities to be loaded and
+    /// initialized in the background, and then shunted into the "main" world all at once, once ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` belongs to a different `Universe`. Use `try_merge` to handle this case,
+    /// or `merge_foreign` to merge such worlds by assigning their entities new IDs.
+    pub fn merge(&mut self, other: World) {
+        if let Err(err) = self.try_merge(other) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Merges two worlds together, as per `merge`.
+    ///
+    /// Returns `Err` containing `other` if it belongs to a different `Universe`.
+    pub fn try_merge(&mut self, mut other: World) -> Result<(), MergeError> {
+        if !self.allocator.shares_universe(&other.allocator) {
+            return Err(MergeError::ForeignUniverse(Box::new(other)));
+        }
+
+        self.flush_journal();
+        let archetypes = std::mem::take(&mut other.archetypes);
+        let merged = self.entities_to_journal(archetypes.iter().flat_map(|a| a.chunks()));
+        self.allocator.merge(other.allocator);
+        self.merge_archetypes(archetypes);
+        self.record_insertions(&merged);
+
+        Ok(())
+    }
 
+    /// Merges a world which may belong to a different `Universe` into this world.
+    ///
+    /// Each entity in `other` is allocated a new ID from this world's universe. Components
+    /// registered in `mappers` have their `Entity` references rewritten to the new IDs.
+    ///
+    /// Returns a map from each entity's ID in `other` to its new ID in `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # use legion::EntityMappers;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// let universe_a = Universe::new(None);
+    /// let universe_b = Universe::new(None);
+    /// let mut world_a = universe_a.create_world();
+    /// let mut world_b = universe_b.create_world();
+    ///
+    /// let entity = world_b.insert_from((), vec![(Position(1.),)])[0];
+    /// let map = world_a.merge_foreign(world_b, &EntityMappers::default());
+    ///
+    /// let merged = map[&entity];
+    /// assert_eq!(Position(1.), *world_a.component::<Position>(merged).unwrap());
+    /// ```
+    pub fn merge_foreign(&mut self, mut other: World, mappers: &EntityMappers) -> EntityMap {
+        self.flush_journal();
+        let mut archetypes = std::mem::take(&mut other.archetypes);
+
+        // allocate new IDs for every entity before rewriting any references
+        let mut map = EntityMap::default();
+        for archetype in archetypes.iter() {
+            for chunk in archetype.chunks() {
+                for entity in unsafe { chunk.entities() } {
+                    map.insert(*entity, self.allocator.create_entity());
+                }
+            }
+        }
         self.allocator.clear_allocation_buffer();
 
-        // insert components into chunks
-        while !components.is_empty() {
-            // find or create chunk
-            let (chunk_index, chunk) = archetype.get_or_create_chunk(&tags, &components);
+        for archetype in archetypes.iter_mut() {
+            for chunk in archetype.chunks.iter_mut() {
+                for entity in unsafe { chunk.entities_unchecked() }.iter_mut() {
+                    *entity = map[entity];
+                }
 
-            // write tags
-            tags.write(chunk);
+                mappers.map_entities(chunk, &map);
+            }
+        }
 
-            // insert as many components as we can into the chunk
-            let allocated = components.write(chunk, &mut self.allocator);
-            // chunk.validate();
+        debug!(
+            self.logger,
+            "remapped {entity_count} foreign entities",
+            entity_count = map.len()
+        );
 
-            // record new entity locations
-            let start = unsafe { chunk.entities().len() - allocated };
-            let added = unsafe { chunk.entities().iter().enumerate().skip(start) };
-            for (i, e) in added {
-                let comp_id = i as ComponentIndex;
-                self.allocator
-                    .set_location(&e.index, (arch_index, chunk_index, comp_id));
+        let merged = self.entities_to_journal(archetypes.iter().flat_map(|a| a.chunks()));
+        self.merge_archetypes(archetypes);
+        self.record_insertions(&merged);
+
+        map
+    }
+
+    /// Moves the chunks of the given archetypes into this world, folding them into existing
+    /// archetypes with the same layout where possible.
+    ///
+    /// All entities within the archetypes must already be allocated by this world's allocator.
+    fn merge_archetypes(&mut self, archetypes: Vec<Archetype>) {
+        for mut archetype in archetypes {
+            let target = match self
+                .archetypes
+                .iter()
+                .position(|a| a.components == archetype.components && a.tags == archetype.tags)
+            {
+                Some(i) => i,
+                None => {
+                    archetype.set_chunk_pool(self.chunk_pool.clone());
+                    archetype.set_change_tick(self.change_tick.clone());
+                    archetype.set_buffers(&self.buffers);
+                    self.archetypes.push(archetype);
+
+                    let archetype_index = self.archetypes.len() - 1;
+                    let archetype = &self.archetypes[archetype_index];
+                    for (chunk_index, chunk) in archetype.chunks().iter().enumerate() {
+                        for (i, entity) in unsafe { chunk.entities() }.iter().enumerate() {
+                            self.allocator.set_location(
+                                &entity.index,
+                                (
+                                    archetype_index as ArchetypeIndex,
+                                    chunk_index as ChunkIndex,
+                                    i as ComponentIndex,
+                                ),
+                            );
+                        }
+                    }
+
+                    continue;
+                }
+            };
+
+            for chunk in archetype.chunks.drain(..) {
+                self.merge_chunk(target as ArchetypeIndex, chunk);
             }
+        }
+    }
 
-            trace!(
-                self.logger,
-                "appended {entity_count} entities into chunk",
-                entity_count = allocated;
-                "archetype_id" => arch_index,
-                "chunk_id" => chunk_index
-            );
+    /// Moves a chunk into the given archetype, first topping up any partially full chunks with
+    /// the same tag values.
+    ///
+    /// All entities within the chunk must already be allocated by this world's allocator.
+    fn merge_chunk(&mut self, target: ArchetypeIndex, mut chunk: Chunk) {
+        let dst = &mut self.archetypes[target as usize];
+        if !chunk.is_full() {
+            for (chunk_index, dst_chunk) in dst.chunks.iter_mut().enumerate() {
+                if chunk.len() == 0 {
+                    break;
+                }
+
+                if dst_chunk.is_full() || !dst_chunk.tags_match(&chunk) {
+                    continue;
+                }
+
+                let remaining = chunk.len();
+                let count = unsafe { dst_chunk.move_from(&mut chunk, remaining) };
+                let start = dst_chunk.len() - count;
+                for (i, entity) in unsafe { dst_chunk.entities() }
+                    .iter()
+                    .enumerate()
+                    .skip(start)
+                {
+                    self.allocator.set_location(
+                        &entity.index,
+                        (target, chunk_index as ChunkIndex, i as ComponentIndex),
+                    );
+                }
+            }
+        }
+
+        if chunk.len() == 0 {
+            return;
+        }
+
+        // move the remainder of the chunk over wholesale
+        let chunk_index = dst.push_chunk(chunk);
+        self.relocate_chunk(target, chunk_index);
+    }
+
+    /// Moves entities from `other` into this world a chunk at a time, until either `other` is
+    /// empty or `budget` entities have been moved.
+    ///
+    /// At least one chunk is moved per call, even if it contains more entities than the remaining
+    /// budget. The number of entities moved is deducted from `budget`. Entities retain their
+    /// `Entity` IDs.
+    ///
+    /// Returns `true` once all entities in `other` have been merged, at which point this is
+    /// equivalent to having called `merge`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` belongs to a different `Universe`.
+    pub fn merge_incremental(&mut self, other: &mut World, budget: &mut usize) -> bool {
+        self.merge_incremental_within(other, budget, &mut 0)
+    }
+
+    /// Performs `merge_incremental` with a budget which may be shared with earlier merges,
+    /// adding the number of entities moved to `moved`.
+    ///
+    /// A chunk which contains more entities than the remaining budget is only moved if `moved` is
+    /// zero, so that the budget is only exceeded when nothing else has been merged.
+    pub(crate) fn merge_incremental_within(
+        &mut self,
+        other: &mut World,
+        budget: &mut usize,
+        moved: &mut usize,
+    ) -> bool {
+        assert!(
+            self.allocator.shares_universe(&other.allocator),
+            "cannot merge a world which belongs to a different universe"
+        );
+
+        self.flush_journal();
+        let merged_before = *moved;
+        while let Some(archetype) = other.archetypes.last_mut() {
+            let chunk_index = match archetype.chunks().len() {
+                0 => {
+                    other.archetypes.pop();
+                    continue;
+                }
+                len => (len - 1) as ChunkIndex,
+            };
+
+            let len = archetype.chunk(chunk_index).unwrap().len();
+            if *moved > 0 && len > *budget {
+                break;
+            }
+
+            let chunk = archetype.take_chunk(chunk_index);
+            let (components, tags) = (archetype.components.clone(), archetype.tags.clone());
+            for entity in unsafe { chunk.entities() } {
+                other.allocator.release_entity(*entity);
+                self.allocator.adopt_entity(*entity);
+            }
+
+            let merged = self.entities_to_journal(std::iter::once(&chunk));
+            let target = self.find_or_create_archetype(components, tags);
+            self.merge_chunk(target, chunk);
+            self.record_insertions(&merged);
+
+            *budget = budget.saturating_sub(len);
+            *moved += len;
         }
 
         trace!(
             self.logger,
-            "inserted {entity_count_added} entities",
-            entity_count_added = self.allocator.allocation_buffer().len();
-            "archetype_id" => arch_index
+            "merged {entity_count} entities",
+            entity_count = *moved - merged_before;
+            "source_world_id" => other.id.0
         );
 
-        self.allocator.allocation_buffer()
+        if other.archetypes.is_empty() {
+            // take ownership of the remaining entity blocks
+            let allocator = EntityAllocator::new(other.allocator.allocator.clone());
+            self.allocator
+                .merge(std::mem::replace(&mut other.allocator, allocator));
+            true
+        } else {
+            false
+        }
     }
 
-    /// Removes the given `Entity` from the `World`.
+    /// Moves all entities which match the given filter out of this world and into a new `World`
+    /// within the same `Universe`.
     ///
-    /// Returns `true` if the entity was deleted; else `false`.
-    pub fn delete(&mut self, entity: Entity) -> bool {
-        let deleted = self.allocator.delete_entity(entity);
+    /// Chunks in which every entity matches are moved wholesale, while the matching entities of
+    /// other chunks are moved one at a time. Entities retain their `Entity` IDs. This is the
+    /// inverse of `merge`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Section(u32);
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// let entity = world.insert_from((Section(3),).as_tags(), vec![(Position(0.),)])[0];
+    ///
+    /// // stream section 3 out of the main world
+    /// let section = world.split_off(tag_value(&Section(3)));
+    /// assert!(!world.is_alive(&entity));
+    /// assert!(section.is_alive(&entity));
+    /// ```
+    pub fn split_off<F: Filter>(&mut self, mut filter: F) -> World {
+        self.flush_journal();
+        let mut other = self.create_sibling();
+
+        let mut entity_count = 0;
+        for archetype_index in 0..self.archetypes.len() {
+            let archetype = &self.archetypes[archetype_index];
+            let chunks = World::filter_chunks(archetype, &mut filter);
+            if chunks.is_empty() {
+                continue;
+            }
+
+            let (chunks, entities) = World::split_matches(archetype, chunks);
+            let target = other
+                .find_or_create_archetype(archetype.components.clone(), archetype.tags.clone());
+
+            // chunks are swap removed, so take them in reverse order
+            for chunk_index in chunks.into_iter().rev() {
+                let chunk = self.archetypes[archetype_index].take_chunk(chunk_index);
+                self.relocate_chunk(archetype_index as ArchetypeIndex, chunk_index);
+
+                let components = &self.archetypes[archetype_index].components;
+                World::log_removed(&mut self.removed, |ty| components.contains(ty), unsafe {
+                    chunk.entities()
+                });
+                self.record_deletions(unsafe { chunk.entities() });
+
+                for entity in unsafe { chunk.entities() } {
+                    self.allocator.release_entity(*entity);
+                    other.allocator.adopt_entity(*entity);
+                }
+                entity_count += chunk.len();
+
+                let target_chunk = other.archetypes[target as usize].push_chunk(chunk);
+                other.relocate_chunk(target, target_chunk);
+            }
+
+            for entity in entities {
+                self.move_entity(entity, &mut other);
+                entity_count += 1;
+            }
+        }
+
+        debug!(
+            self.logger,
+            "split off {entity_count} entities",
+            entity_count = entity_count;
+            "target_world_id" => other.id.0
+        );
+
+        other
+    }
+
+    /// Moves a single entity out of this world and into `target`, retaining its `Entity` ID.
+    ///
+    /// Returns `false` if the entity was not alive in this world.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` belongs to a different `Universe`.
+    pub fn move_entity(&mut self, entity: Entity, target: &mut World) -> bool {
+        assert!(
+            self.allocator.shares_universe(&target.allocator),
+            "cannot move an entity into a world which belongs to a different universe"
+        );
+
+        if !self.is_alive(&entity) {
+            return false;
+        }
+
+        self.flush_journal();
+        let (arch_id, chunk_id, comp_id) = self.allocator.get_location(&entity.index).unwrap();
+        let components = &self.archetypes[arch_id as usize].components;
+        World::log_removed(&mut self.removed, |ty| components.contains(ty), &[entity]);
+        let (swapped, tags, mut components) = self.archetypes[arch_id as usize]
+            .chunk_mut(chunk_id)
+            .unwrap()
+            .fetch_remove(comp_id);
+
+        // record swapped entity's new location
+        if let Some(swapped) = swapped {
+            self.allocator
+                .set_location(&swapped.index, (arch_id, chunk_id, comp_id));
+        }
+
+        // release the chunk if it is now empty
+        self.free_chunk_if_empty(arch_id, chunk_id);
+
+        self.allocator.release_entity(entity);
+        self.record_deleted(entity);
+        target.allocator.adopt_entity(entity);
+        let prev = components.take_prev();
+        let reinserted = !components.is_empty();
+        target.insert(tags, components);
+        if reinserted {
+            target.restore_prev(entity, prev);
+        }
+
+        true
+    }
+
+    /// Deep-clones this world into a new `World` within `universe`, preserving all `Entity` IDs.
+    ///
+    /// All component types in the world must have a clone function registered in `registry`.
+    /// The entity IDs in use by this world are reserved in `universe`, which must therefore be a
+    /// different universe which has not already allocated those IDs, such as a newly created one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # use legion::storage::CloneRegistry;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// let entity = world.insert_from((), vec![(Position(1.),)])[0];
+    ///
+    /// let mut registry = CloneRegistry::default();
+    /// registry.register::<Position>();
+    ///
+    /// // simulate ahead in a separate universe
+    /// let lookahead = Universe::new(None);
+    /// let mut clone = world.clone_into(&lookahead, &registry).unwrap();
+    /// *clone.component_mut::<Position>(entity).unwrap() = Position(2.);
+    ///
+    /// assert_eq!(Position(1.), *world.component::<Position>(entity).unwrap());
+    /// ```
+    pub fn clone_into(
+        &self,
+        universe: &Universe,
+        registry: &CloneRegistry,
+    ) -> Result<World, CloneError> {
+        let mut clone = universe.create_world();
+        if !self.allocator.clone_ids_into(&mut clone.allocator) {
+            return Err(CloneError::EntityIdsInUse);
+        }
+
+        self.clone_chunks_into(&mut clone, query::Passthrough, registry)?;
+        clone.relocate_all();
+        Ok(clone)
+    }
+
+    /// Deep-clones all entities which match the given filter into a new `World` within the same
+    /// `Universe`.
+    ///
+    /// The cloned entities are allocated fresh `Entity` IDs. Returns the new world, along with a
+    /// map from the ID of each original entity to that of its clone. The map can be used with
+    /// `map_entities` to rewrite references between the cloned entities.
+    ///
+    /// All matching component types must have a clone function registered in `registry`.
+    pub fn clone_entities<F: Filter>(
+        &self,
+        filter: F,
+        registry: &CloneRegistry,
+    ) -> Result<(World, EntityMap), CloneError> {
+        let mut clone = self.create_sibling();
+        self.clone_chunks_into(&mut clone, filter, registry)?;
+
+        let mut map = EntityMap::default();
+        for archetype in clone.archetypes.iter_mut() {
+            for chunk in archetype.chunks.iter_mut() {
+                for entity in unsafe { chunk.entities_unchecked() }.iter_mut() {
+                    let new = clone.allocator.create_entity();
+                    map.insert(*entity, new);
+                    *entity = new;
+                }
+            }
+        }
+
+        clone.relocate_all();
+        Ok((clone, map))
+    }
+
+    /// Rewrites the `Entity` references held by components registered in `mappers`.
+    pub fn map_entities(&mut self, mappers: &EntityMappers, map: &EntityMap) {
+        for archetype in self.archetypes.iter_mut() {
+            for chunk in archetype.chunks.iter_mut() {
+                mappers.map_entities(chunk, map);
+            }
+        }
+    }
+
+    /// Deep-clones all chunks which match the filter into `target`, without allocating IDs for
+    /// the cloned entities.
+    fn clone_chunks_into<F: Filter>(
+        &self,
+        target: &mut World,
+        mut filter: F,
+        registry: &CloneRegistry,
+    ) -> Result<(), CloneError> {
+        for archetype in self.archetypes.iter() {
+            let chunks = World::filter_chunks(archetype, &mut filter);
+            if chunks.is_empty() {
+                continue;
+            }
+
+            let target_index = target
+                .find_or_create_archetype(archetype.components.clone(), archetype.tags.clone());
+            for (chunk_index, matches) in chunks {
+                let chunk = archetype.chunk(chunk_index).unwrap();
+                let mut builder = chunk.builder();
+                builder.set_pool(target.chunk_pool.clone());
+                builder.set_change_tick(target.change_tick.clone());
+
+                let target_archetype = &mut target.archetypes[target_index as usize];
+                let id = target_archetype.allocate_chunk_id();
+                let mut clone = chunk
+                    .try_clone(builder, id, registry)
+                    .map_err(CloneError::UnregisteredComponent)?;
+
+                // drop the clones of the entities which do not match, in reverse order so that
+                // only matching entities are swapped into their place
+                if let Some(matches) = matches {
+                    for index in (0..matches.len()).rev().filter(|i| !matches.get(*i)) {
+                        clone.remove(index as ComponentIndex);
+                    }
+                }
+
+                target_archetype.push_chunk(clone);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the location of every entity in the world.
+    fn relocate_all(&mut self) {
+        for archetype_index in 0..self.archetypes.len() {
+            for chunk_index in 0..self.archetypes[archetype_index].chunks().len() {
+                self.relocate_chunk(archetype_index as ArchetypeIndex, chunk_index as ChunkIndex);
+            }
+        }
+    }
+
+    /// Determines if the given `Entity` is alive within this `World`.
+    pub fn is_alive(&self, entity: &Entity) -> bool {
+        self.allocator.is_alive(entity)
+    }
+
+    /// Inserts entities from an iterator of component tuples.
+    ///
+    /// # Examples
+    ///
+    /// Inserting entity tuples:
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Rotation(f32);
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// # let model = 0u8;
+    /// # let color = 0u16;
+    /// let tags = (model, color).as_tags();
+    /// let data = vec![
+    ///     (Position(0.0), Rotation(0.0)),
+    ///     (Position(1.0), Rotation(1.0)),
+    ///     (Position(2.0), Rotation(2.0)),
+    /// ];
+    /// world.insert_from(tags, data);
+    /// ```
+    pub fn insert_from<T, C>(&mut self, tags: T, components: C) -> &[Entity]
+    where
+        T: TagSet,
+        C: IntoIterator,
+        C::Item: ComponentSet,
+        IterEntitySource<C::IntoIter, C::Item>: EntitySource,
+    {
+        let source = C::Item::component_source(components.into_iter());
+        self.insert(tags, source)
+    }
+
+    /// Inserts `count` new entities with clones of the template's tags and component values.
+    ///
+    /// Component values are cloned directly into chunk storage.
+    pub fn instantiate<'a, T, C>(&mut self, template: &'a Template<T, C>, count: usize) -> &[Entity]
+    where
+        T: TagSet + Clone,
+        TemplateEntitySource<'a, C, fn(usize, &mut C)>: EntitySource,
+    {
+        let source =
+            TemplateEntitySource::<C, fn(usize, &mut C)>::new(&template.components, count, None);
+        self.insert(template.tags.clone(), source)
+    }
+
+    /// Inserts `count` new entities from a template, allowing each instance's component values to
+    /// be overridden.
+    ///
+    /// `override_fn` is called with the index of each instance and a clone of the template's
+    /// component values, which it may modify before they are written into the world.
+    pub fn instantiate_with<'a, T, C, F>(
+        &mut self,
+        template: &'a Template<T, C>,
+        count: usize,
+        override_fn: F,
+    ) -> &[Entity]
+    where
+        T: TagSet + Clone,
+        F: FnMut(usize, &mut C),
+        TemplateEntitySource<'a, C, F>: EntitySource,
+    {
+        let source = TemplateEntitySource::new(&template.components, count, Some(override_fn));
+        self.insert(template.tags.clone(), source)
+    }
+
+    /// Inserts entities from an `EntitySource`.
+    pub fn insert<T, C>(&mut self, tags: T, components: C) -> &[Entity]
+    where
+        T: TagSet,
+        C: EntitySource,
+    {
+        self.flush_journal();
+        self.insert_entities(tags, components);
+        self.record_inserted();
+        self.allocator.allocation_buffer()
+    }
+
+    /// Inserts entities without recording them in the world's journal.
+    fn insert_entities<T, C>(&mut self, mut tags: T, mut components: C) -> &[Entity]
+    where
+        T: TagSet,
+        C: EntitySource,
+    {
+        // find or create archetype
+        let chunk_pool = &self.chunk_pool;
+        let buffers = &self.buffers;
+        let change_tick = &self.change_tick;
+        let (arch_index, archetype) = World::prep_archetype(
+            &self.id,
+            &mut self.archetypes,
+            &mut self.next_arch_id,
+            &mut self.logger,
+            |archetype| {
+                archetype.set_chunk_pool(chunk_pool.clone());
+                archetype.set_change_tick(change_tick.clone());
+                archetype.set_buffers(buffers);
+            },
+            &tags,
+            &components,
+        );
+
+        self.allocator.clear_allocation_buffer();
+
+        // insert components into chunks
+        while !components.is_empty() {
+            // find or create chunk
+            let (chunk_index, chunk) = archetype.get_or_create_chunk(&tags, &components);
+
+            // write tags
+            tags.write(chunk);
+
+            // insert as many components as we can into the chunk
+            let allocated = components.write(chunk, &mut self.allocator);
+            // chunk.validate();
+
+            // record new entity locations
+            let start = unsafe { chunk.entities().len() - allocated };
+            unsafe { chunk.init_buffers(start, allocated) };
+            chunk.mark_added(start, allocated);
+            let added = unsafe { chunk.entities().iter().enumerate().skip(start) };
+            for (i, e) in added {
+                let comp_id = i as ComponentIndex;
+                self.allocator
+                    .set_location(&e.index, (arch_index, chunk_index, comp_id));
+            }
+
+            trace!(
+                self.logger,
+                "appended {entity_count} entities into chunk",
+                entity_count = allocated;
+                "archetype_id" => arch_index,
+                "chunk_id" => chunk_index
+            );
+        }
+
+        trace!(
+            self.logger,
+            "inserted {entity_count_added} entities",
+            entity_count_added = self.allocator.allocation_buffer().len();
+            "archetype_id" => arch_index
+        );
+
+        self.allocator.allocation_buffer()
+    }
+
+    /// Removes the given `Entity` from the `World`.
+    ///
+    /// Returns `true` if the entity was deleted; else `false`.
+    pub fn delete(&mut self, entity: Entity) -> bool {
+        self.flush_journal();
+        let deleted = self.allocator.delete_entity(entity);
+
+        if deleted {
+            // lookup entity location
+            let ids = self.entity_location(entity);
+            if let Some((archetype_id, _, _)) = ids {
+                let components = &self.archetypes[archetype_id as usize].components;
+                World::log_removed(&mut self.removed, |ty| components.contains(ty), &[entity]);
+            }
+
+            // swap remove with last entity in chunk
+            let swapped = ids.and_then(|(archetype_id, chunk_id, component_id)| {
+                self.archetypes
+                    .get_mut(archetype_id as usize)
+                    .and_then(|archetype| archetype.chunk_mut(chunk_id))
+                    .and_then(|chunk| chunk.remove(component_id))
+            });
+
+            // record swapped entity's new location
+            if let Some(swapped) = swapped {
+                self.allocator.set_location(&swapped.index, ids.unwrap());
+            }
+
+            // release the chunk if it is now empty
+            if let Some((archetype_id, chunk_id, _)) = ids {
+                self.free_chunk_if_empty(archetype_id, chunk_id);
+            }
+
+            self.record_deleted(entity);
+        }
+
+        deleted
+    }
+
+    /// Gets the location of the given entity, or `None` if it is not stored in any chunk because
+    /// all of its components have been removed.
+    fn entity_location(
+        &self,
+        entity: Entity,
+    ) -> Option<(ArchetypeIndex, ChunkIndex, ComponentIndex)> {
+        self.allocator
+            .get_location(&entity.index)
+            .filter(|(archetype, chunk, index)| {
+                self.archetypes
+                    .get(*archetype as usize)
+                    .and_then(|a| a.chunk(*chunk))
+                    .and_then(|c| unsafe { c.entities() }.get(*index as usize))
+                    == Some(&entity)
+            })
+    }
+
+    /// Gets the chunk which contains the given entity.
+    fn entity_chunk(&self, entity: Entity) -> Option<&Chunk> {
+        self.entity_location(entity)
+            .and_then(|(archetype, chunk, _)| self.archetypes[archetype as usize].chunk(chunk))
+    }
+
+    /// Drops the tags and components of an entity, without releasing its ID.
+    ///
+    /// The entity must be re-inserted before it is next accessed.
+    fn remove_entity_data(&mut self, entity: Entity) {
+        if let Some((archetype, chunk, index)) = self.entity_location(entity) {
+            let swapped = self.archetypes[archetype as usize]
+                .chunk_mut(chunk)
+                .and_then(|c| c.remove(index));
+            if let Some(swapped) = swapped {
+                self.allocator
+                    .set_location(&swapped.index, (archetype, chunk, index));
+            }
+
+            self.free_chunk_if_empty(archetype, chunk);
+        }
+    }
+
+    /// Removes all entities which match the given filter from the `World`.
+    ///
+    /// Chunks in which every entity matches are deleted at once, and the deleted `Entity` IDs are
+    /// returned to the allocator for re-use. Filters such as `changed` may match only some of the
+    /// entities in a chunk, in which case those entities are deleted one at a time and the
+    /// archetype is then defragmented, moving at most as many entities as were deleted this way.
+    ///
+    /// Returns the number of entities deleted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Dead;
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// # world.insert_from((Dead,).as_tags(), vec![(Position(0.0),), (Position(1.0),)]);
+    /// world.delete_where(tag::<Dead>());
+    /// ```
+    pub fn delete_where<F: Filter>(&mut self, mut filter: F) -> usize {
+        self.flush_journal();
+        let mut count = 0;
+        for archetype_index in 0..self.archetypes.len() {
+            let archetype = &self.archetypes[archetype_index];
+            let chunks = World::filter_chunks(archetype, &mut filter);
+            let (chunks, entities) = World::split_matches(archetype, chunks);
+
+            // release chunks in reverse order so that the remaining indices stay valid
+            for chunk_index in chunks.into_iter().rev() {
+                let archetype = &mut self.archetypes[archetype_index];
+                let entities = archetype.chunk_mut(chunk_index).unwrap().clear();
+                World::log_removed(
+                    &mut self.removed,
+                    |ty| archetype.components.contains(ty),
+                    &entities,
+                );
+                self.record_deletions(&entities);
+                for entity in entities {
+                    self.allocator.delete_entity(entity);
+                    count += 1;
+                }
+
+                self.free_chunk(archetype_index as ArchetypeIndex, chunk_index);
+            }
+
+            // deleting entities from partly matching chunks leaves the chunks fragmented
+            let mut budget = entities.len();
+            for entity in entities {
+                self.delete(entity);
+                count += 1;
+            }
+            if budget > 0 {
+                self.defrag_archetype(archetype_index as ArchetypeIndex, &mut budget);
+            }
+        }
+
+        trace!(
+            self.logger,
+            "deleted {entity_count} entities",
+            entity_count = count
+        );
+
+        count
+    }
+
+    /// Removes all entities from the `World`.
+    ///
+    /// All archetypes and chunks are released, and the deleted `Entity` IDs are returned to the
+    /// allocator for re-use.
+    pub fn clear(&mut self) {
+        self.flush_journal();
+        for archetype in std::mem::take(&mut self.archetypes) {
+            for chunk in archetype.chunks() {
+                World::log_removed(
+                    &mut self.removed,
+                    |ty| archetype.components.contains(ty),
+                    unsafe { chunk.entities() },
+                );
+                self.record_deletions(unsafe { chunk.entities() });
+                for entity in unsafe { chunk.entities() } {
+                    self.allocator.delete_entity(*entity);
+                }
+            }
+        }
+
+        trace!(self.logger, "cleared world");
+    }
+
+    /// Mutates the composition of an entity in-place. This allows components and tags to be added
+    /// or removed from an entity.
+    ///
+    /// # Performance
+    ///
+    /// Mutating an entity is *significantly slower* than inserting a new entity. Always prefer to
+    /// create entities with the desired layout in the first place, and avoid adding or removing
+    /// components or tags from existing entities.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # use std::sync::Arc;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Static;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Rotation(f32);
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// # let model = 0u8;
+    /// # let color = 0u16;
+    /// # let tags = (model, color).as_tags();
+    /// # let data = vec![
+    /// #     (Position(0.0), Rotation(0.0)),
+    /// #     (Position(1.0), Rotation(1.0)),
+    /// #     (Position(2.0), Rotation(2.0)),
+    /// # ];
+    /// # let entity = *world.insert_from(tags, data).get(0).unwrap();
+    /// world.mutate_entity(entity, |e| {
+    ///     e.set_tag(Arc::new(Static));
+    ///     e.add_component(Position(4.0));
+    ///     e.remove_component::<Rotation>();
+    /// });
+    /// ```
+    pub fn mutate_entity<'env, F: FnOnce(&mut MutEntity<'env>)>(&mut self, entity: Entity, f: F) {
+        assert!(self.is_alive(&entity));
+        self.flush_journal();
+
+        if let Some((arch_id, chunk_id, comp_id)) = self.entity_location(entity) {
+            let added = self.archetypes[arch_id as usize]
+                .chunk(chunk_id)
+                .map(|c| c.added_ticks(comp_id as usize))
+                .unwrap_or_default();
+
+            if let Some((swapped, tags, components)) = self
+                .archetypes
+                .get_mut(arch_id as usize)
+                .and_then(|a| a.chunk_mut(chunk_id))
+                .map(|c| c.fetch_remove(comp_id))
+            {
+                let mut mut_handle = MutEntity::<'env> {
+                    tags,
+                    components,
+                    _phantom: PhantomData,
+                };
+
+                // mutate the entity
+                f(&mut mut_handle);
+
+                // record swapped entity's new location
+                if let Some(swapped) = swapped {
+                    self.allocator
+                        .set_location(&swapped.index, (arch_id, chunk_id, comp_id));
+                }
+
+                // release the chunk if it is now empty
+                self.free_chunk_if_empty(arch_id, chunk_id);
+
+                // re-insert the entity
+                let prev = mut_handle.components.take_prev();
+                let reinserted = !mut_handle.components.is_empty();
+                self.insert_entities(mut_handle.tags, mut_handle.components);
+                if reinserted {
+                    self.restore_prev(entity, prev);
+                }
+                self.record_mutated(entity);
+
+                let previous = &self.archetypes[arch_id as usize].components;
+                if !reinserted {
+                    // an entity without components is not stored in any chunk
+                    World::log_removed(&mut self.removed, |ty| previous.contains(ty), &[entity]);
+                    return;
+                }
+
+                // only the components which the entity did not already have are added
+                let (archetype, chunk, index) = self.allocator.get_location(&entity.index).unwrap();
+                let archetype = &self.archetypes[archetype as usize];
+                let chunk = archetype.chunk(chunk).unwrap();
+                chunk.restore_added_ticks(index as usize, &added);
+                World::log_removed(
+                    &mut self.removed,
+                    |ty| previous.contains(ty) && !archetype.components.contains(ty),
+                    &[entity],
+                );
+            }
+        }
+    }
+
+    /// Adds a component to all entities which match the given filter.
+    ///
+    /// Entities which already contain a `T` component are left unchanged. The value for each
+    /// entity is constructed by calling `value_fn` with the entity's ID.
+    ///
+    /// Matching entities are moved into their new archetype a whole chunk at a time, which is
+    /// significantly faster than calling `mutate_entity` for each entity. Where a filter matches
+    /// only some of the entities in a chunk, those entities are moved one at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Position(f32);
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Frozen;
+    /// # #[derive(Copy, Clone, Debug, PartialEq)]
+    /// # struct Level(u32);
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// # world.insert_from((Level(1),).as_tags(), vec![(Position(0.0),), (Position(1.0),)]);
+    /// world.add_component_where(tag_value(&Level(1)), |_| Frozen);
+    /// ```
+    pub fn add_component_where<T, F, V>(&mut self, mut filter: F, mut value_fn: V)
+    where
+        T: Component,
+        F: Filter,
+        V: FnMut(Entity) -> T,
+    {
+        let archetype_count = self.archetypes.len();
+        for archetype_index in 0..archetype_count {
+            let archetype = &self.archetypes[archetype_index];
+            if archetype.has_component::<T>() {
+                continue;
+            }
+
+            let chunks = World::filter_chunks(archetype, &mut filter);
+            if chunks.is_empty() {
+                continue;
+            }
+
+            let (chunks, entities) = World::split_matches(archetype, chunks);
+            let mut components = archetype.components.clone();
+            components.insert(T::type_id());
+            let tags = archetype.tags.clone();
+            let mutated =
+                self.entities_to_journal(chunks.iter().map(|c| archetype.chunk(*c).unwrap()));
+            let target = self.find_or_create_archetype(components, tags);
+
+            // chunks are released once emptied, so move them in reverse order
+            for chunk_index in chunks.into_iter().rev() {
+                self.move_chunk(
+                    (archetype_index as ArchetypeIndex, chunk_index),
+                    target,
+                    |builder| builder.register_component::<T>(),
+                    |chunk, start, _| unsafe {
+                        let entities = chunk.entities();
+                        let data = chunk.components_mut_raw::<T>().unwrap();
+                        for (i, entity) in entities.iter().enumerate().skip(start) {
+                            std::ptr::write(data.as_ptr().add(i), value_fn(*entity));
+                        }
+                    },
+                );
+            }
+
+            for entity in mutated {
+                self.record_mutated(entity);
+            }
+
+            for entity in entities {
+                let component = value_fn(entity);
+                self.mutate_entity(entity, |e| e.add_component(component));
+            }
+        }
+    }
+
+    /// Removes the `T` component from all entities which match the given filter.
+    ///
+    /// Matching entities are moved into their new archetype a whole chunk at a time, which is
+    /// significantly faster than calling `mutate_entity` for each entity. Where a filter matches
+    /// only some of the entities in a chunk, those entities are moved one at a time.
+    pub fn remove_component_where<T, F>(&mut self, mut filter: F)
+    where
+        T: Component,
+        F: Filter,
+    {
+        let archetype_count = self.archetypes.len();
+        for archetype_index in 0..archetype_count {
+            let archetype = &self.archetypes[archetype_index];
+            if !archetype.has_component::<T>() {
+                continue;
+            }
+
+            let chunks = World::filter_chunks(archetype, &mut filter);
+            if chunks.is_empty() {
+                continue;
+            }
+
+            let (chunks, entities) = World::split_matches(archetype, chunks);
+            let mut components = archetype.components.clone();
+            components.remove(&T::type_id());
+            let tags = archetype.tags.clone();
+            let mutated =
+                self.entities_to_journal(chunks.iter().map(|c| archetype.chunk(*c).unwrap()));
+            let target = self.find_or_create_archetype(components, tags);
+
+            // chunks are released once emptied, so move them in reverse order
+            for chunk_index in chunks.into_iter().rev() {
+                let chunk = self.archetypes[archetype_index].chunk(chunk_index).unwrap();
+                World::log_removed(&mut self.removed, |ty| *ty == T::type_id(), unsafe {
+                    chunk.entities()
+                });
+
+                self.move_chunk(
+                    (archetype_index as ArchetypeIndex, chunk_index),
+                    target,
+                    |builder| builder.unregister_component(&T::type_id()),
+                    |_, _, _| {},
+                );
+            }
+
+            for entity in mutated {
+                self.record_mutated(entity);
+            }
+
+            for entity in entities {
+                self.mutate_entity(entity, |e| {
+                    e.remove_component::<T>();
+                });
+            }
+        }
+    }
+
+    /// Defragments the world's chunks, moving entities out of partially filled chunks and into
+    /// other chunks with the same tag values, and frees any chunks which are left empty.
+    ///
+    /// Defragmentation is incremental. At most `budget` entities are moved per call, and the next
+    /// call resumes where the previous call left off. Pass `None` to defragment the entire world.
+    ///
+    /// Returns `true` if a full pass over all archetypes was completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use legion::prelude::*;
+    /// # let universe = Universe::new(None);
+    /// # let mut world = universe.create_world();
+    /// // spend at most 1000 entity moves per frame defragmenting the world
+    /// world.defrag(Some(1000));
+    /// ```
+    pub fn defrag(&mut self, budget: Option<usize>) -> bool {
+        let mut budget = budget.unwrap_or(usize::MAX);
+
+        while self.defrag_progress < self.archetypes.len() {
+            let archetype_index = self.defrag_progress as ArchetypeIndex;
+            if !self.defrag_archetype(archetype_index, &mut budget) {
+                return false;
+            }
+
+            self.defrag_progress += 1;
+        }
+
+        self.defrag_progress = 0;
+        true
+    }
+
+    /// Releases all empty chunks and archetypes, and shrinks the world's internal storage to fit
+    /// the entities it contains.
+    ///
+    /// Archetypes which no longer contain any chunks are removed, and the archetype indices of all
+    /// entity locations are remapped accordingly.
+    pub fn shrink_to_fit(&mut self) {
+        for archetype_index in 0..self.archetypes.len() {
+            for chunk_index in (0..self.archetypes[archetype_index].chunks().len()).rev() {
+                self.free_chunk_if_empty(
+                    archetype_index as ArchetypeIndex,
+                    chunk_index as ChunkIndex,
+                );
+            }
+
+            self.archetypes[archetype_index].chunks.shrink_to_fit();
+        }
+
+        let before = self.archetypes.len();
+        let mut kept = 0;
+        for archetype_index in 0..before {
+            if self.archetypes[archetype_index].chunks().is_empty() {
+                continue;
+            }
+
+            if kept != archetype_index {
+                self.archetypes.swap(kept, archetype_index);
+
+                // remap the locations of all entities in the moved archetype
+                for (chunk_index, chunk) in self.archetypes[kept].chunks().iter().enumerate() {
+                    for (i, entity) in unsafe { chunk.entities() }.iter().enumerate() {
+                        self.allocator.set_location(
+                            &entity.index,
+                            (
+                                kept as ArchetypeIndex,
+                                chunk_index as ChunkIndex,
+                                i as ComponentIndex,
+                            ),
+                        );
+                    }
+                }
+            }
+
+            kept += 1;
+        }
+
+        self.archetypes.truncate(kept);
+        self.archetypes.shrink_to_fit();
+        self.defrag_progress = 0;
+
+        debug!(
+            self.logger,
+            "released {archetype_count} archetypes",
+            archetype_count = before - kept
+        );
+    }
+
+    /// Defragments the chunks within a single archetype.
+    ///
+    /// Returns `true` if the archetype was fully defragmented within the budget.
+    fn defrag_archetype(&mut self, archetype_index: ArchetypeIndex, budget: &mut usize) -> bool {
+        loop {
+            // release any chunks which are already empty
+            while let Some(empty) = self.archetypes[archetype_index as usize]
+                .chunks()
+                .iter()
+                .position(|c| c.len() == 0)
+            {
+                self.free_chunk(archetype_index, empty as ChunkIndex);
+            }
+
+            // find the emptiest chunk which can be merged into a fuller chunk with the same tags
+            let archetype = &mut self.archetypes[archetype_index as usize];
+            let chunks = archetype.chunks();
+            let mut candidates = (0..chunks.len())
+                .filter(|i| !chunks[*i].is_full())
+                .collect::<Vec<_>>();
+            candidates.sort_by_key(|i| chunks[*i].len());
+
+            let pair = candidates.iter().find_map(|source| {
+                candidates
+                    .iter()
+                    .rev()
+                    .find(|target| {
+                        *target != source && chunks[**target].tags_match(&chunks[*source])
+                    })
+                    .map(|target| (*source, *target))
+            });
+
+            let (source, target) = match pair {
+                Some(pair) => pair,
+                None => return true,
+            };
+
+            if *budget == 0 {
+                return false;
+            }
+
+            let (src_chunk, dst_chunk) = pair_mut(&mut archetype.chunks, source, target);
+            let count =
+                unsafe { dst_chunk.move_from(src_chunk, std::cmp::min(src_chunk.len(), *budget)) };
+            *budget -= count;
+
+            let start = dst_chunk.len() - count;
+            for (i, entity) in unsafe { dst_chunk.entities() }
+                .iter()
+                .enumerate()
+                .skip(start)
+            {
+                self.allocator.set_location(
+                    &entity.index,
+                    (archetype_index, target as ChunkIndex, i as ComponentIndex),
+                );
+            }
+
+            trace!(
+                self.logger,
+                "defragmented {entity_count} entities",
+                entity_count = count;
+                "archetype_id" => archetype_index,
+                "chunk_id" => source,
+                "target_chunk_id" => target
+            );
+        }
+    }
+
+    /// Releases the chunk if it contains no entities.
+    fn free_chunk_if_empty(&mut self, archetype_index: ArchetypeIndex, chunk_index: ChunkIndex) {
+        let empty = match self
+            .archetypes
+            .get(archetype_index as usize)
+            .and_then(|archetype| archetype.chunk(chunk_index))
+        {
+            Some(chunk) => chunk.len() == 0,
+            None => false,
+        };
+
+        if empty {
+            self.free_chunk(archetype_index, chunk_index);
+        }
+    }
+
+    /// Releases an empty chunk.
+    ///
+    /// The last chunk in the archetype is moved into the released chunk's index.
+    fn free_chunk(&mut self, archetype_index: ArchetypeIndex, chunk_index: ChunkIndex) {
+        self.archetypes[archetype_index as usize].remove_chunk(chunk_index);
+
+        // record the new location of entities in the chunk which was moved into the freed index
+        self.relocate_chunk(archetype_index, chunk_index);
+    }
+
+    /// Records the location of all entities in the given chunk, if it exists.
+    fn relocate_chunk(&mut self, archetype_index: ArchetypeIndex, chunk_index: ChunkIndex) {
+        if let Some(chunk) = self.archetypes[archetype_index as usize].chunk(chunk_index) {
+            for (i, entity) in unsafe { chunk.entities() }.iter().enumerate() {
+                self.allocator.set_location(
+                    &entity.index,
+                    (archetype_index, chunk_index, i as ComponentIndex),
+                );
+            }
+        }
+    }
+
+    /// Double-buffers the component type `T`.
+    ///
+    /// Each chunk containing `T` keeps a second column of type `Prev<T>`, holding the values its
+    /// components had as of the last call to `advance`, which can be read with `Read<Prev<T>>`.
+    /// Entities which are inserted, or which are changed with `mutate_entity`, start with their
+    /// previous values equal to their current values.
+    pub fn buffer<T: Component + Clone>(&mut self) {
+        let buffer = ComponentBuffer::of::<T>();
+        if self.buffers.iter().any(|b| b.component == buffer.component) {
+            return;
+        }
 
-        if deleted {
-            // lookup entity location
-            let ids = self.allocator.get_location(&entity.index);
+        self.buffers.push(buffer);
+        for archetype in self.archetypes.iter_mut() {
+            archetype.set_buffers(&self.buffers);
+        }
+    }
 
-            // swap remove with last entity in chunk
-            let swapped = ids.and_then(|(archetype_id, chunk_id, component_id)| {
-                self.archetypes
-                    .get_mut(archetype_id as usize)
-                    .and_then(|archetype| archetype.chunk_mut(chunk_id))
-                    .and_then(|chunk| chunk.remove(component_id))
-            });
+    /// Restores the previous values of the double-buffered components of a re-inserted entity,
+    /// which were removed with it by `Chunk::fetch_remove`.
+    fn restore_prev(&self, entity: Entity, prev: Vec<DynamicComponent>) {
+        let (archetype, chunk, index) = self.allocator.get_location(&entity.index).unwrap();
+        let chunk = self.archetypes[archetype as usize].chunk(chunk).unwrap();
+        unsafe { chunk.restore_prev(index as usize, prev) };
+    }
 
-            // record swapped entity's new location
-            if let Some(swapped) = swapped {
-                self.allocator.set_location(&swapped.index, ids.unwrap());
+    /// Replaces the previous values of all double-buffered components with clones of their
+    /// current values.
+    pub fn advance(&mut self) {
+        for archetype in self.archetypes.iter_mut() {
+            for chunk in archetype.chunks.iter_mut() {
+                chunk.advance_buffers();
             }
         }
 
-        deleted
+        trace!(self.logger, "advanced component buffers");
     }
 
-    /// Mutates the composition of an entity in-place. This allows components and tags to be added
-    /// or removed from an entity.
+    /// Gets the world's ID.
+    pub fn id(&self) -> WorldId {
+        self.id
+    }
+
+    /// Gets the world's change tick.
     ///
-    /// # Performance
+    /// Components record the change tick at which they were last written. The tick advances
+    /// each time a query is run, so that a query's `changed` filters match the components which
+    /// have been written since the query last ran.
+    pub fn change_tick(&self) -> usize {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /// Advances the world's change tick, returning the new tick.
+    pub(crate) fn next_change_tick(&self) -> usize {
+        self.change_tick.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Logs the entities from which a `T` component is removed, until they are taken with
+    /// `drain_removed`.
     ///
-    /// Mutating an entity is *significantly slower* than inserting a new entity. Always prefer to
-    /// create entities with the desired layout in the first place, and avoid adding or removing
-    /// components or tags from existing entities.
+    /// Entities are logged when `T` is removed with `mutate_entity` or `remove_component_where`,
+    /// when they are deleted, when they are moved into another world, and when restoring a
+    /// snapshot leaves them without `T`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use legion::prelude::*;
-    /// # use std::sync::Arc;
-    /// # #[derive(Copy, Clone, Debug, PartialEq)]
-    /// # struct Static;
-    /// # #[derive(Copy, Clone, Debug, PartialEq)]
-    /// # struct Position(f32);
     /// # #[derive(Copy, Clone, Debug, PartialEq)]
-    /// # struct Rotation(f32);
+    /// # struct Mesh(u32);
     /// # let universe = Universe::new(None);
     /// # let mut world = universe.create_world();
-    /// # let model = 0u8;
-    /// # let color = 0u16;
-    /// # let tags = (model, color).as_tags();
-    /// # let data = vec![
-    /// #     (Position(0.0), Rotation(0.0)),
-    /// #     (Position(1.0), Rotation(1.0)),
-    /// #     (Position(2.0), Rotation(2.0)),
-    /// # ];
-    /// # let entity = *world.insert_from(tags, data).get(0).unwrap();
-    /// world.mutate_entity(entity, |e| {
-    ///     e.set_tag(Arc::new(Static));
-    ///     e.add_component(Position(4.0));
-    ///     e.remove_component::<Rotation>();
-    /// });
+    /// world.track_removed::<Mesh>();
+    /// let entity = world.insert_from((), vec![(Mesh(1),)])[0];
+    /// world.delete(entity);
+    ///
+    /// // release the GPU buffers of deleted meshes
+    /// assert_eq!(vec![entity], world.drain_removed::<Mesh>());
     /// ```
-    pub fn mutate_entity<'env, F: FnOnce(&mut MutEntity<'env>)>(&mut self, entity: Entity, f: F) {
-        assert!(self.is_alive(&entity));
-
-        if let Some((arch_id, chunk_id, comp_id)) = self.allocator.get_location(&entity.index) {
-            if let Some((swapped, tags, components)) = self
-                .archetypes
-                .get_mut(arch_id as usize)
-                .and_then(|a| a.chunk_mut(chunk_id))
-                .map(|c| c.fetch_remove(comp_id))
-            {
-                let mut mut_handle = MutEntity::<'env> {
-                    tags,
-                    components,
-                    _phantom: PhantomData,
-                };
-
-                // mutate the entity
-                f(&mut mut_handle);
-
-                // record swapped entity's new location
-                if let Some(swapped) = swapped {
-                    self.allocator
-                        .set_location(&swapped.index, (arch_id, chunk_id, comp_id));
-                }
+    pub fn track_removed<T: Component>(&mut self) {
+        self.removed.entry(T::type_id()).or_default();
+    }
 
-                // re-insert the entity
-                self.insert(mut_handle.tags, mut_handle.components);
-            }
+    /// Takes the entities from which a `T` component has been removed since the log was last
+    /// drained.
+    ///
+    /// Returns an empty `Vec` if removals of `T` are not logged with `track_removed`.
+    pub fn drain_removed<T: Component>(&mut self) -> Vec<Entity> {
+        self.removed
+            .get_mut(&T::type_id())
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Appends `entities` to the log of each tracked component type for which `removed` returns
+    /// `true`.
+    fn log_removed<F: Fn(&ComponentTypeId) -> bool>(
+        log: &mut FnvHashMap<ComponentTypeId, Vec<Entity>>,
+        removed: F,
+        entities: &[Entity],
+    ) {
+        for (_, log) in log.iter_mut().filter(|(ty, _)| removed(ty)) {
+            log.extend_from_slice(entities);
         }
     }
 
@@ -891,14 +2801,17 @@ impl World {
         if !self.allocator.is_alive(&entity) {
             return None;
         }
+        self.record_write(entity, T::type_id());
         let archetypes = &self.archetypes;
         self.allocator.get_location(&entity.index).and_then(
             |(archetype_id, chunk_id, component_id)| {
                 archetypes
                     .get(archetype_id as usize)
                     .and_then(|archetype| archetype.chunk(chunk_id))
-                    .and_then(|chunk| unsafe { chunk.components_mut_unchecked::<T>() })
-                    .and_then(|vec| vec.get_mut(component_id as usize))
+                    .and_then(|chunk| unsafe {
+                        chunk.entity_component_mut_raw(&T::type_id(), component_id as usize)
+                    })
+                    .map(|ptr| unsafe { &mut *(ptr.as_ptr() as *mut T) })
             },
         )
     }
@@ -921,7 +2834,7 @@ impl World {
                 archetypes
                     .get(archetype_id as usize)
                     .and_then(|archetype| archetype.chunk(chunk_id))
-                    .and_then(|chunk| chunk.components_mut_raw_untyped(ty, component_id as usize))
+                    .and_then(|chunk| chunk.entity_component_mut_raw(ty, component_id as usize))
             },
         )
     }
@@ -963,11 +2876,160 @@ impl World {
             })
     }
 
-    fn prep_archetype<'a, T: TagSet, C: EntitySource>(
+    /// Finds the indices of all non-empty chunks in the archetype which match the filter, along
+    /// with which of each chunk's entities match, or `None` if all of them do.
+    fn filter_chunks<F: Filter>(
+        archetype: &Archetype,
+        filter: &mut F,
+    ) -> Vec<(ChunkIndex, Option<EntityMask>)> {
+        if !filter.filter_archetype(archetype).is_pass() {
+            return Vec::new();
+        }
+
+        let mut chunks = Vec::new();
+        for (i, chunk) in archetype.chunks().iter().enumerate() {
+            if chunk.len() == 0 || !filter.filter_chunk(chunk).is_pass() {
+                continue;
+            }
+
+            match filter.filter_entities(chunk) {
+                Some(matches) if matches.all() => chunks.push((i as ChunkIndex, None)),
+                Some(matches) if matches.any() => chunks.push((i as ChunkIndex, Some(matches))),
+                Some(_) => {}
+                None => chunks.push((i as ChunkIndex, None)),
+            }
+        }
+
+        chunks
+    }
+
+    /// Splits the chunks found by `filter_chunks` into the chunks in which every entity matches
+    /// the filter, and the matching entities of the other chunks.
+    fn split_matches(
+        archetype: &Archetype,
+        chunks: Vec<(ChunkIndex, Option<EntityMask>)>,
+    ) -> (Vec<ChunkIndex>, Vec<Entity>) {
+        let mut whole = Vec::new();
+        let mut entities = Vec::new();
+        for (chunk_index, matches) in chunks {
+            match matches {
+                Some(matches) => {
+                    let chunk = archetype.chunk(chunk_index).unwrap();
+                    entities.extend(
+                        unsafe { chunk.entities() }
+                            .iter()
+                            .zip(matches.iter())
+                            .filter(|(_, matches)| *matches)
+                            .map(|(entity, _)| *entity),
+                    );
+                }
+                None => whole.push(chunk_index),
+            }
+        }
+
+        (whole, entities)
+    }
+
+    /// Moves all entities in the `source` chunk into chunks in the `target` archetype, and then
+    /// releases the emptied source chunk.
+    ///
+    /// Components are moved column-wise. `configure` adjusts the source chunk's layout to that of
+    /// the target archetype, and `init` is called to initialize any component columns which do
+    /// not exist in the source chunk for each block of entities moved.
+    fn move_chunk<B, I>(
+        &mut self,
+        source: (ArchetypeIndex, ChunkIndex),
+        target: ArchetypeIndex,
+        configure: B,
+        mut init: I,
+    ) where
+        B: Fn(&mut ChunkBuilder),
+        I: FnMut(&mut Chunk, usize, usize),
+    {
+        let (source_archetype, source_chunk) = source;
+        loop {
+            let (src, dst) = pair_mut(
+                &mut self.archetypes,
+                source_archetype as usize,
+                target as usize,
+            );
+            let src_chunk = src.chunk_mut(source_chunk).unwrap();
+            if src_chunk.len() == 0 {
+                break;
+            }
+
+            let dst_chunk_index = dst.get_or_create_chunk_matching(src_chunk, || {
+                let mut builder = src_chunk.builder();
+                configure(&mut builder);
+                builder
+            });
+            let dst_chunk = dst.chunk_mut(dst_chunk_index).unwrap();
+
+            let count = unsafe { dst_chunk.move_from(src_chunk, src_chunk.len()) };
+            let start = dst_chunk.len() - count;
+            init(dst_chunk, start, count);
+            unsafe { dst_chunk.init_buffers_from(src_chunk, start, count) };
+
+            for (i, entity) in unsafe { dst_chunk.entities() }
+                .iter()
+                .enumerate()
+                .skip(start)
+            {
+                self.allocator.set_location(
+                    &entity.index,
+                    (target, dst_chunk_index, i as ComponentIndex),
+                );
+            }
+
+            trace!(
+                self.logger,
+                "moved {entity_count} entities",
+                entity_count = count;
+                "archetype_id" => source_archetype,
+                "chunk_id" => source_chunk,
+                "target_archetype_id" => target,
+                "target_chunk_id" => dst_chunk_index
+            );
+        }
+
+        self.free_chunk(source_archetype, source_chunk);
+    }
+
+    /// Finds the archetype with exactly the given component and tag types, or constructs a new one.
+    fn find_or_create_archetype(
+        &mut self,
+        components: FnvHashSet<ComponentTypeId>,
+        tags: FnvHashSet<TagTypeId>,
+    ) -> ArchetypeIndex {
+        if let Some(i) = self
+            .archetypes
+            .iter()
+            .position(|a| a.components == components && a.tags == tags)
+        {
+            return i as ArchetypeIndex;
+        }
+
+        let archetype_id = self.id.archetype(self.next_arch_id);
+        let logger = self.logger.new(o!("archetype_id" => archetype_id.1));
+        self.next_arch_id += 1;
+
+        let mut archetype = Archetype::new(archetype_id, logger.clone(), components, tags);
+        archetype.set_chunk_pool(self.chunk_pool.clone());
+        archetype.set_change_tick(self.change_tick.clone());
+        archetype.set_buffers(&self.buffers);
+        self.archetypes.push(archetype);
+
+        debug!(logger, "allocated archetype");
+
+        (self.archetypes.len() - 1) as ArchetypeIndex
+    }
+
+    fn prep_archetype<'a, T: TagSet, C: EntitySource, F: FnOnce(&mut Archetype)>(
         id: &WorldId,
         archetypes: &'a mut Vec<Archetype>,
         next_arch_id: &mut u16,
         logger: &slog::Logger,
+        configure: F,
         tags: &T,
         components: &C,
     ) -> (ArchetypeIndex, &'a mut Archetype) {
@@ -986,12 +3048,13 @@ impl World {
                 let logger = logger.new(o!("archetype_id" => archetype_id.1));
                 *next_arch_id += 1;
 
-                let archetype = Archetype::new(
+                let mut archetype = Archetype::new(
                     archetype_id,
                     logger.clone(),
                     components.types(),
                     tags.types(),
                 );
+                configure(&mut archetype);
                 archetypes.push(archetype);
 
                 debug!(logger, "allocated archetype");
@@ -1005,6 +3068,18 @@ impl World {
     }
 }
 
+/// Gets mutable references to two different elements of a slice.
+fn pair_mut<T>(slice: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = slice.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = slice.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
 pub struct MutEntity<'env> {
     tags: DynamicTagSet,
     components: DynamicSingleEntitySource,
@@ -1236,6 +3311,154 @@ impl_component_source!(3; A => a, B => b, C => c);
 impl_component_source!(4; A => a, B => b, C => c, D => d);
 impl_component_source!(5; A => a, B => b, C => c, D => d, E => e);
 
+/// A prefab describing the tags and component values of an entity, which can be instantiated
+/// into a world many times.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::prelude::*;
+/// # use legion::Template;
+/// # #[derive(Copy, Clone, Debug, PartialEq)]
+/// # struct Position(f32);
+/// # #[derive(Copy, Clone, Debug, PartialEq)]
+/// # struct Velocity(f32);
+/// # #[derive(Copy, Clone, Debug, PartialEq)]
+/// # struct Model(u32);
+/// # let universe = Universe::new(None);
+/// # let mut world = universe.create_world();
+/// let bullet = Template::new((Model(7),).as_tags(), (Position(0.), Velocity(10.)));
+///
+/// // spawn 1000 identical bullets
+/// world.instantiate(&bullet, 1000);
+///
+/// // spawn 1000 bullets with staggered positions
+/// world.instantiate_with(&bullet, 1000, |i, (pos, _)| pos.0 = i as f32);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Template<T: TagSet, C> {
+    tags: T,
+    components: C,
+}
+
+impl<T: TagSet, C> Template<T, C> {
+    /// Constructs a new `Template` from a tag set and a tuple of component values.
+    pub fn new(tags: T, components: C) -> Self {
+        Template { tags, components }
+    }
+
+    /// Gets the template's tags.
+    pub fn tags(&self) -> &T {
+        &self.tags
+    }
+
+    /// Gets the template's component values.
+    pub fn components(&self) -> &C {
+        &self.components
+    }
+}
+
+/// An `EntitySource` which writes clones of a template's component values directly into
+/// chunk columns.
+///
+/// If an override function is provided, it is given the instance index and a clone of the
+/// component values to modify before they are written.
+#[doc(hidden)]
+pub struct TemplateEntitySource<'a, C, F> {
+    components: &'a C,
+    count: usize,
+    index: usize,
+    override_fn: Option<F>,
+}
+
+impl<'a, C, F> TemplateEntitySource<'a, C, F> {
+    /// Constructs a source which writes `count` instances of the given component values.
+    pub fn new(components: &'a C, count: usize, override_fn: Option<F>) -> Self {
+        TemplateEntitySource {
+            components,
+            count,
+            index: 0,
+            override_fn,
+        }
+    }
+}
+
+macro_rules! impl_template_source {
+    ( $arity: expr; $( $ty: ident => $id: ident ),* ) => {
+        impl<'a, F, $( $ty ),*> EntitySource for TemplateEntitySource<'a, ($( $ty, )*), F>
+        where F: FnMut(usize, &mut ($( $ty, )*)),
+              $( $ty: Component + Clone ),*
+        {
+            fn types(&self) -> FnvHashSet<ComponentTypeId> {
+                [$( $ty::type_id() ),*].iter().cloned().collect()
+            }
+
+            fn is_archetype_match(&self, archetype: &Archetype) -> bool {
+                archetype.components.len() == $arity &&
+                $(
+                    archetype.components.contains(&$ty::type_id())
+                )&&*
+            }
+
+            fn configure_chunk(&self, chunk: &mut ChunkBuilder) {
+                $(
+                    chunk.register_component::<$ty>();
+                )*
+            }
+
+            fn is_empty(&mut self) -> bool {
+                self.index == self.count
+            }
+
+            fn write(&mut self, chunk: &mut Chunk, allocator: &mut EntityAllocator) -> usize {
+                #![allow(non_snake_case)]
+                let mut count = 0;
+
+                unsafe {
+                    let entities = chunk.entities_unchecked();
+                    $(
+                        let $ty = chunk.components_mut_raw::<$ty>().unwrap();
+                    )*
+
+                    while self.index < self.count && !chunk.is_full() {
+                        // construct the values before the entity is pushed, so that the chunk
+                        // does not hold uninitialized components if a clone or override panics
+                        let ($( $id, )*) = match self.override_fn {
+                            Some(ref mut override_fn) => {
+                                let mut values = self.components.clone();
+                                override_fn(self.index, &mut values);
+                                values
+                            }
+                            None => {
+                                let ($( $id, )*) = self.components;
+                                ($( $id.clone(), )*)
+                            }
+                        };
+
+                        let entity = allocator.create_entity();
+                        let idx = entities.len();
+                        entities.push(entity);
+                        $(
+                            std::ptr::write($ty.as_ptr().add(idx), $id);
+                        )*
+
+                        self.index += 1;
+                        count += 1;
+                    }
+                }
+
+                count
+            }
+        }
+    }
+}
+
+impl_template_source!(1; A => a);
+impl_template_source!(2; A => a, B => b);
+impl_template_source!(3; A => a, B => b, C => c);
+impl_template_source!(4; A => a, B => b, C => c, D => d);
+impl_template_source!(5; A => a, B => b, C => c, D => d, E => e);
+
 /// Components that are stored once per entity.
 pub trait Component: Send + Sync + Sized + Debug + 'static {
     fn type_id() -> ComponentTypeId;
@@ -1393,6 +3616,51 @@ mod tests {
         assert_eq!(false, allocator.delete_entity(entity));
     }
 
+    #[test]
+    fn saved_blocks_are_copied_on_write() {
+        let mut allocator = EntityAllocator::new(Arc::from(Mutex::new(BlockAllocator::new())));
+        let first = allocator.create_entity();
+        let second = (0..BlockAllocator::BLOCK_SIZE)
+            .map(|_| allocator.create_entity())
+            .last()
+            .unwrap();
+        assert_eq!(2, allocator.blocks.len());
+
+        let state = allocator.save();
+        assert!(Arc::ptr_eq(&allocator.blocks[0], &state.blocks[0]));
+
+        // only the changed block is copied
+        allocator.delete_entity(second);
+        assert!(Arc::ptr_eq(&allocator.blocks[0], &state.blocks[0]));
+        assert!(!Arc::ptr_eq(&allocator.blocks[1], &state.blocks[1]));
+
+        assert!(allocator.restore(&state));
+        assert_eq!(true, allocator.is_alive(&first));
+        assert_eq!(true, allocator.is_alive(&second));
+    }
+
+    #[test]
+    fn adopted_entities_are_returned() {
+        let blocks = Arc::from(Mutex::new(BlockAllocator::new()));
+        let mut allocator_a = EntityAllocator::new(blocks.clone());
+        let mut allocator_b = EntityAllocator::new(blocks.clone());
+
+        let entity = allocator_a.create_entity();
+        assert!(allocator_a.release_entity(entity));
+        allocator_b.adopt_entity(entity);
+        assert!(allocator_b.delete_entity(entity));
+        drop(allocator_b);
+
+        // the released index is re-used once the rest of the block has been allocated
+        for _ in 1..BlockAllocator::BLOCK_SIZE {
+            allocator_a.create_entity();
+        }
+        let reused = allocator_a.create_entity();
+        assert_eq!(entity.index, reused.index);
+        assert_eq!(entity.version + Wrapping(1), reused.version);
+        assert_eq!(1, allocator_a.blocks.len());
+    }
+
     #[test]
     fn multiple_allocators_unique_ids() {
         let blocks = Arc::from(Mutex::new(BlockAllocator::new()));
@@ -1420,6 +3688,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn release_adopt_entity() {
+        let blocks = Arc::from(Mutex::new(BlockAllocator::new()));
+        let mut allocator_a = EntityAllocator::new(blocks.clone());
+        let mut allocator_b = EntityAllocator::new(blocks.clone());
+
+        let entity = allocator_a.create_entity();
+        assert_eq!(true, allocator_a.release_entity(entity));
+        assert_eq!(false, allocator_a.release_entity(entity));
+        allocator_b.adopt_entity(entity);
+
+        assert_eq!(false, allocator_a.is_alive(&entity));
+        assert_eq!(true, allocator_b.is_alive(&entity));
+
+        // the released index is not re-used by the original allocator
+        for _ in 0..2000 {
+            assert_ne!(entity.index, allocator_a.create_entity().index);
+        }
+
+        assert_eq!(true, allocator_b.delete_entity(entity));
+        assert_eq!(false, allocator_b.is_alive(&entity));
+        let recycled = allocator_b.create_entity();
+        assert_eq!(entity.index, recycled.index);
+        assert_ne!(entity.version, recycled.version);
+    }
+
+    #[test]
+    fn merge_reclaims_adopted_entities() {
+        let blocks = Arc::from(Mutex::new(BlockAllocator::new()));
+        let mut allocator_a = EntityAllocator::new(blocks.clone());
+        let mut allocator_b = EntityAllocator::new(blocks.clone());
+
+        let entity = allocator_a.create_entity();
+        allocator_a.release_entity(entity);
+        allocator_b.adopt_entity(entity);
+        allocator_b.set_location(&entity.index, (1, 2, 3));
+
+        allocator_b.merge(allocator_a);
+        assert_eq!(true, allocator_b.is_alive(&entity));
+        assert_eq!(Some((1, 2, 3)), allocator_b.get_location(&entity.index));
+        assert_eq!(true, allocator_b.adopted.is_empty());
+    }
+
+    #[test]
+    fn delete_entity_twice_does_not_double_free() {
+        let mut allocator = EntityAllocator::new(Arc::from(Mutex::new(BlockAllocator::new())));
+        let entity = allocator.create_entity();
+        allocator.delete_entity(entity);
+        allocator.delete_entity(entity);
+
+        let a = allocator.create_entity();
+        let b = allocator.create_entity();
+        assert_ne!(a.index, b.index);
+    }
+
+    #[test]
+    fn reserve_block() {
+        let mut blocks = BlockAllocator::new();
+        assert_eq!(true, blocks.reserve(2048));
+        assert_eq!(false, blocks.reserve(2048));
+
+        // skipped blocks are still available
+        let starts = (0..3).map(|_| blocks.allocate().start).collect::<Vec<_>>();
+        assert!(starts.contains(&0));
+        assert!(starts.contains(&1024));
+        assert!(!starts.contains(&2048));
+    }
+
+    #[test]
+    fn merge_foreign_clears_allocation_buffer() {
+        let mut world_a = Universe::new(None).create_world();
+        let mut world_b = Universe::new(None).create_world();
+        world_b.insert_from((), vec![(1usize,), (2usize,)]);
+
+        world_a.merge_foreign(world_b, &EntityMappers::default());
+        assert_eq!(true, world_a.allocator.allocation_buffer().is_empty());
+    }
+
+    #[test]
+    fn entity_mask_operations() {
+        let mut a = (0..100).map(|i| i % 3 == 0).collect::<EntityMask>();
+        let b = (0..100).map(|i| i % 2 == 0).collect::<EntityMask>();
+        assert_eq!(100, a.len());
+        assert_eq!(true, a.get(99));
+        assert_eq!(false, a.get(98));
+
+        let mut union = a.clone();
+        union.union(&b);
+        assert_eq!(
+            true,
+            union.iter().eq((0..100).map(|i| i % 3 == 0 || i % 2 == 0))
+        );
+
+        a.intersect(&b);
+        assert_eq!(true, a.iter().eq((0..100).map(|i| i % 6 == 0)));
+
+        // bits beyond the mask's length are never set
+        let mut none = EntityMask::new(100);
+        none.invert();
+        assert_eq!(true, none.all());
+        none.invert();
+        assert_eq!(false, none.any());
+    }
+
+    #[test]
+    fn not_filters_prune_archetypes() {
+        use crate::query::filter::{changed, component};
+
+        let universe = Universe::new(None);
+        let mut world = universe.create_world();
+        world.insert_from((), vec![(1usize, 1u32)]);
+        world.insert_from((), vec![(1usize,)]);
+
+        let filter = !component::<u32>();
+        assert_eq!(Some(false), filter.filter_archetype(&world.archetypes[0]));
+        assert_eq!(Some(true), filter.filter_archetype(&world.archetypes[1]));
+
+        // filters which match some of an archetype's entities are resolved per chunk
+        let mut filter = !changed::<u32>();
+        filter.set_last_run(Some(0));
+        assert_eq!(None, filter.filter_archetype(&world.archetypes[0]));
+        assert_eq!(Some(true), filter.filter_archetype(&world.archetypes[1]));
+    }
+
     #[test]
     fn get_component_empty_world() {
         let universe = Universe::new(None);
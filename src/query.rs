@@ -1,4 +1,3 @@
-use fnv::FnvHashMap;
 use itertools::multizip;
 use std::iter::Repeat;
 use std::iter::Take;
@@ -59,6 +58,7 @@ impl<T: DefaultFilter + for<'a> View<'a>> IntoQuery for T {
         QueryDef {
             view: PhantomData,
             filter: Self::filter(),
+            last_run: None,
         }
     }
 }
@@ -134,6 +134,44 @@ impl<T: Component + DataTypeId> ViewElement for Write<T> {
     type Component = T;
 }
 
+/// Writes to a single entity data component type in a `Chunk`, yielding `TrackedMut` references
+/// which only mark a component as changed when they are dereferenced mutably.
+///
+/// `Write<T>` marks every component it yields as changed, whether or not it is written.
+#[derive(Debug)]
+pub struct Tracked<T: Component>(PhantomData<T>);
+
+impl<T: Component> DefaultFilter for Tracked<T> {
+    type Filter = ComponentFilter<T>;
+    fn filter() -> Self::Filter {
+        ComponentFilter::new()
+    }
+}
+
+impl<'a, T: Component> View<'a> for Tracked<T> {
+    type Iter = BorrowedIter<'a, TrackedIter<'a, T>>;
+
+    fn fetch(chunk: &'a Chunk) -> Self::Iter {
+        chunk.components_tracked().unwrap()
+    }
+
+    fn validate() -> bool {
+        true
+    }
+
+    fn reads<D: Component>() -> bool {
+        T::type_id() == D::type_id()
+    }
+
+    fn writes<D: Component>() -> bool {
+        T::type_id() == D::type_id()
+    }
+}
+
+impl<T: Component + DataTypeId> ViewElement for Tracked<T> {
+    type Component = T;
+}
+
 /// Reads a single shared data component type in a `Chunk`.
 #[derive(Debug)]
 pub struct Tagged<T: Tag>(PhantomData<T>);
@@ -173,6 +211,29 @@ impl<T: Tag> ViewElement for Tagged<T> {
     type Component = Tagged<T>;
 }
 
+/// The value of a double-buffered component as of the last call to `World::advance`.
+///
+/// Components of type `T` are double-buffered with `World::buffer`, after which their previous
+/// values can be read with `Read<Prev<T>>`.
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct Prev<T: Component>(T);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+impl<T: Component> DataTypeId for Prev<T> {
+    fn type_id() -> (std::any::TypeId, u32) {
+        (std::any::TypeId::of::<Prev<T>>(), T::type_id().1)
+    }
+}
+
+impl<T: Component> std::ops::Deref for Prev<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
 macro_rules! impl_view_tuple {
     ( $( $ty: ident ),* ) => {
         impl<$( $ty: ViewElement + DefaultFilter ),*> DefaultFilter for ($( $ty, )*) {
@@ -222,7 +283,7 @@ impl_view_tuple!(A, B, C);
 impl_view_tuple!(A, B, C, D);
 impl_view_tuple!(A, B, C, D, E);
 
-trait FilterResult {
+pub(crate) trait FilterResult {
     fn coalesce_and(self, other: Self) -> Self;
     fn coalesce_or(self, other: Self) -> Self;
     fn is_pass(&self) -> bool;
@@ -251,6 +312,109 @@ impl FilterResult for Option<bool> {
     }
 }
 
+/// The entities within a chunk which match a filter, stored as one bit per entity.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntityMask {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl EntityMask {
+    /// Constructs a mask of `len` entities, none of which match.
+    pub fn new(len: usize) -> Self {
+        EntityMask {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// Gets the number of entities in the mask.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Determines if the mask contains no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Determines if the entity at `index` matches.
+    #[inline]
+    pub fn get(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Sets whether the entity at `index` matches.
+    #[inline]
+    pub fn set(&mut self, index: usize, matches: bool) {
+        if matches {
+            self.words[index / 64] |= 1 << (index % 64);
+        } else {
+            self.words[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    /// Determines if any entity matches.
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|word| *word != 0)
+    }
+
+    /// Determines if every entity matches.
+    pub fn all(&self) -> bool {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum::<usize>()
+            == self.len
+    }
+
+    /// Gets an iterator of whether each entity matches.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// Keeps only the entities which also match in `other`.
+    pub fn intersect(&mut self, other: &EntityMask) {
+        self.words
+            .iter_mut()
+            .zip(other.words.iter())
+            .for_each(|(x, y)| *x &= *y);
+    }
+
+    /// Adds the entities which match in `other`.
+    pub fn union(&mut self, other: &EntityMask) {
+        self.words
+            .iter_mut()
+            .zip(other.words.iter())
+            .for_each(|(x, y)| *x |= *y);
+    }
+
+    /// Inverts which entities match.
+    pub fn invert(&mut self) {
+        self.words.iter_mut().for_each(|word| *word = !*word);
+        let tail = self.len % 64;
+        if tail > 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << tail) - 1;
+            }
+        }
+    }
+}
+
+impl std::iter::FromIterator<bool> for EntityMask {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut mask = EntityMask::new(0);
+        for matches in iter {
+            if mask.len == mask.words.len() * 64 {
+                mask.words.push(0);
+            }
+            mask.len += 1;
+            mask.set(mask.len - 1, matches);
+        }
+        mask
+    }
+}
+
 /// Filters chunks to determine which are to be included in a `Query`.
 pub trait Filter: Send + Sync + Sized + Debug {
     /// Determines if an archetype matches the filter's conditions.
@@ -267,10 +431,30 @@ pub trait Filter: Send + Sync + Sized + Debug {
     fn filter_chunk_variable(&mut self, chunk: &Chunk) -> Option<bool>;
 
     /// Determines if a chunk matches the filter's conditions.
+    ///
+    /// Filters which distinguish between the entities in a chunk match the chunk if any of its
+    /// entities may match, and then select those entities with `filter_entities`.
     fn filter_chunk(&mut self, chunk: &Chunk) -> Option<bool> {
         self.filter_chunk_immutable(chunk)
             .coalesce_or(self.filter_chunk_variable(chunk))
     }
+
+    /// Determines which entities within a chunk match the filter's conditions.
+    /// This is called after `filter_chunk` has matched the chunk.
+    ///
+    /// Returns `None` if the filter does not distinguish between the entities in the chunk.
+    fn filter_entities(&mut self, _: &Chunk) -> Option<EntityMask> {
+        None
+    }
+
+    /// Determines if the filter may match only some of the entities within a chunk.
+    fn filters_entities(&self) -> bool {
+        false
+    }
+
+    /// Sets the world change tick at which the query running the filter last ran, or `None` if
+    /// it has not yet run.
+    fn set_last_run(&mut self, _: Option<usize>) {}
 }
 
 pub mod filter {
@@ -295,11 +479,17 @@ pub mod filter {
         TagValueFilter::new(data)
     }
 
-    /// Creates a filter which includes chunks for which entity data components
-    /// of type `T` have changed since the filter was last executed.
+    /// Creates a filter which includes entities whose entity data components
+    /// of type `T` have changed since the query was last executed.
     pub fn changed<T: Component>() -> ComponentChangedFilter<T> {
         ComponentChangedFilter::new()
     }
+
+    /// Creates a filter which includes entities to which entity data components
+    /// of type `T` have been added since the query was last executed.
+    pub fn added<T: Component>() -> ComponentAddedFilter<T> {
+        ComponentAddedFilter::new()
+    }
 }
 
 /// A passthrough filter which allows all chunks.
@@ -358,7 +548,12 @@ pub struct Not<F> {
 impl<F: Filter> Filter for Not<F> {
     #[inline]
     fn filter_archetype(&self, archetype: &Archetype) -> Option<bool> {
-        self.filter.filter_archetype(archetype).map(|x| !x)
+        match self.filter.filter_archetype(archetype) {
+            Some(false) => Some(true),
+            // `F` may only match some of the archetype's entities, which is resolved per chunk
+            Some(true) if self.filter.filters_entities() => None,
+            result => result.map(|x| !x),
+        }
     }
 
     #[inline]
@@ -370,6 +565,40 @@ impl<F: Filter> Filter for Not<F> {
     fn filter_chunk_variable(&mut self, chunk: &Chunk) -> Option<bool> {
         self.filter.filter_chunk_variable(chunk).map(|x| !x)
     }
+
+    fn filter_chunk(&mut self, chunk: &Chunk) -> Option<bool> {
+        match self.filter.filter_chunk(chunk) {
+            // `F` matches none of the entities in the chunk
+            Some(false) => Some(true),
+            // `F` may only match some of the entities, which is resolved per entity
+            _ if self.filter.filters_entities() => None,
+            result => result.map(|x| !x),
+        }
+    }
+
+    fn filter_entities(&mut self, chunk: &Chunk) -> Option<EntityMask> {
+        if !self.filter.filter_chunk(chunk).is_pass() {
+            return None;
+        }
+
+        Some(match self.filter.filter_entities(chunk) {
+            Some(mut entities) => {
+                entities.invert();
+                entities
+            }
+            None => EntityMask::new(chunk.len()),
+        })
+    }
+
+    #[inline]
+    fn filters_entities(&self) -> bool {
+        self.filter.filters_entities()
+    }
+
+    #[inline]
+    fn set_last_run(&mut self, tick: Option<usize>) {
+        self.filter.set_last_run(tick);
+    }
 }
 
 impl<T: Filter> std::ops::Not for Not<T> {
@@ -438,6 +667,48 @@ macro_rules! impl_and_filter {
                 $( result = result.coalesce_and($ty.filter_chunk_variable(chunk)); )*
                 result
             }
+
+            #[inline]
+            fn filter_chunk(&mut self, chunk: &Chunk) -> Option<bool> {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                let mut result: Option<bool> = None;
+                $( result = result.coalesce_and($ty.filter_chunk(chunk)); )*
+                result
+            }
+
+            #[inline]
+            fn filter_entities(&mut self, chunk: &Chunk) -> Option<EntityMask> {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                let mut result: Option<EntityMask> = None;
+                $(
+                    if let Some(entities) = $ty.filter_entities(chunk) {
+                        result = Some(match result {
+                            Some(mut x) => {
+                                x.intersect(&entities);
+                                x
+                            }
+                            None => entities,
+                        });
+                    }
+                )*
+                result
+            }
+
+            #[inline]
+            fn filters_entities(&self) -> bool {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &self.filters;
+                false $( || $ty.filters_entities() )*
+            }
+
+            #[inline]
+            fn set_last_run(&mut self, tick: Option<usize>) {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                $( $ty.set_last_run(tick); )*
+            }
         }
 
         impl<$( $ty: Filter ),*> std::ops::Not for And<($( $ty, )*)> {
@@ -517,6 +788,51 @@ macro_rules! impl_or_filter {
                 $( result = result.coalesce_or($ty.filter_chunk_variable(chunk)); )*
                 result
             }
+
+            #[inline]
+            fn filter_chunk(&mut self, chunk: &Chunk) -> Option<bool> {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                let mut result: Option<bool> = None;
+                $( result = result.coalesce_or($ty.filter_chunk(chunk)); )*
+                result
+            }
+
+            #[inline]
+            fn filter_entities(&mut self, chunk: &Chunk) -> Option<EntityMask> {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                let mut result: Option<EntityMask> = None;
+                $(
+                    // filters which do not match the chunk match none of its entities
+                    if $ty.filter_chunk(chunk).is_pass() {
+                        // every entity matches a filter which does not distinguish between them
+                        let entities = $ty.filter_entities(chunk)?;
+                        result = Some(match result {
+                            Some(mut x) => {
+                                x.union(&entities);
+                                x
+                            }
+                            None => entities,
+                        });
+                    }
+                )*
+                Some(result.unwrap_or_else(|| EntityMask::new(chunk.len())))
+            }
+
+            #[inline]
+            fn filters_entities(&self) -> bool {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &self.filters;
+                false $( || $ty.filters_entities() )*
+            }
+
+            #[inline]
+            fn set_last_run(&mut self, tick: Option<usize>) {
+                #![allow(non_snake_case)]
+                let ($( $ty, )*) = &mut self.filters;
+                $( $ty.set_last_run(tick); )*
+            }
         }
 
         impl<$( $ty: Filter ),*> std::ops::Not for Or<($( $ty, )*)> {
@@ -583,8 +899,8 @@ impl<T: Component> Filter for ComponentFilter<T> {
     }
 
     #[inline]
-    fn filter_chunk_immutable(&self, _: &Chunk) -> Option<bool> {
-        None
+    fn filter_chunk_immutable(&self, chunk: &Chunk) -> Option<bool> {
+        Some(chunk.has_component::<T>())
     }
 }
 
@@ -636,8 +952,8 @@ impl<T: Tag> Filter for TagFilter<T> {
     }
 
     #[inline]
-    fn filter_chunk_immutable(&self, _: &Chunk) -> Option<bool> {
-        None
+    fn filter_chunk_immutable(&self, chunk: &Chunk) -> Option<bool> {
+        Some(chunk.tag::<T>().is_some())
     }
 
     #[inline]
@@ -737,18 +1053,25 @@ impl<'a, Rhs: Filter, T: Tag> std::ops::BitOr<Rhs> for TagValueFilter<'a, T> {
     }
 }
 
-/// A filter which requires that entity data of type `T` has changed within the
-/// chunk since the last time the filter was executed.
+/// A filter which requires that entity data of type `T` has changed since the last time
+/// the query running the filter was executed.
+///
+/// Chunks in which no `T` has been accessed mutably since then are skipped, and within the
+/// remaining chunks, only the entities whose `T` has been written are included.
+///
+/// When used outside of a query, such as with `World::delete_where`, the filter matches the
+/// components written since the tick given to `Filter::set_last_run`, or every component if no
+/// tick has been given.
 #[derive(Debug)]
 pub struct ComponentChangedFilter<T: Component> {
-    versions: FnvHashMap<ChunkId, usize>,
+    last_run: Option<usize>,
     phantom: PhantomData<T>,
 }
 
 impl<T: Component> ComponentChangedFilter<T> {
     fn new() -> ComponentChangedFilter<T> {
         ComponentChangedFilter {
-            versions: FnvHashMap::default(),
+            last_run: None,
             phantom: PhantomData,
         }
     }
@@ -775,19 +1098,30 @@ impl<T: Component> Filter for ComponentChangedFilter<T> {
     }
 
     fn filter_chunk_variable(&mut self, chunk: &Chunk) -> Option<bool> {
-        use std::collections::hash_map::Entry;
-        if let Some(version) = chunk.component_version::<T>() {
-            match self.versions.entry(chunk.id()) {
-                Entry::Occupied(mut entry) => Some(entry.insert(version) != version),
-                Entry::Vacant(entry) => {
-                    entry.insert(version);
-                    Some(true)
-                }
-            }
-        } else {
-            Some(false)
+        // everything is included the first time the query runs
+        let tick = chunk.component_access_tick::<T>();
+        match self.last_run {
+            Some(last_run) => Some(tick.map(|tick| tick >= last_run).unwrap_or(false)),
+            None => Some(tick.is_some()),
         }
     }
+
+    fn filter_entities(&mut self, chunk: &Chunk) -> Option<EntityMask> {
+        let last_run = self.last_run?;
+        Some(
+            (0..chunk.len())
+                .map(|i| chunk.component_tick::<T>(i).unwrap() >= last_run)
+                .collect(),
+        )
+    }
+
+    fn filters_entities(&self) -> bool {
+        self.last_run.is_some()
+    }
+
+    fn set_last_run(&mut self, tick: Option<usize>) {
+        self.last_run = tick;
+    }
 }
 
 impl<Rhs: Filter, T: Component> std::ops::BitAnd<Rhs> for ComponentChangedFilter<T> {
@@ -812,6 +1146,99 @@ impl<Rhs: Filter, T: Component> std::ops::BitOr<Rhs> for ComponentChangedFilter<
     }
 }
 
+/// A filter which requires that entity data of type `T` has been added since the last time
+/// the query running the filter was executed.
+///
+/// Components are added when their entity is inserted or merged into the world, or when they are
+/// added to an existing entity.
+///
+/// When used outside of a query, such as with `World::delete_where`, the filter matches the
+/// components added since the tick given to `Filter::set_last_run`, or every component if no tick
+/// has been given.
+#[derive(Debug)]
+pub struct ComponentAddedFilter<T: Component> {
+    last_run: Option<usize>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Component> ComponentAddedFilter<T> {
+    fn new() -> ComponentAddedFilter<T> {
+        ComponentAddedFilter {
+            last_run: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> std::ops::Not for ComponentAddedFilter<T> {
+    type Output = Not<Self>;
+
+    #[inline]
+    fn not(self) -> Self::Output {
+        Not { filter: self }
+    }
+}
+
+impl<T: Component> Filter for ComponentAddedFilter<T> {
+    #[inline]
+    fn filter_archetype(&self, archetype: &Archetype) -> Option<bool> {
+        Some(archetype.has_component::<T>())
+    }
+
+    #[inline]
+    fn filter_chunk_immutable(&self, _: &Chunk) -> Option<bool> {
+        None
+    }
+
+    fn filter_chunk_variable(&mut self, chunk: &Chunk) -> Option<bool> {
+        // everything is included the first time the query runs
+        let tick = chunk.component_last_added_tick::<T>();
+        match self.last_run {
+            Some(last_run) => Some(tick.map(|tick| tick >= last_run).unwrap_or(false)),
+            None => Some(tick.is_some()),
+        }
+    }
+
+    fn filter_entities(&mut self, chunk: &Chunk) -> Option<EntityMask> {
+        let last_run = self.last_run?;
+        Some(
+            (0..chunk.len())
+                .map(|i| chunk.component_added_tick::<T>(i).unwrap() >= last_run)
+                .collect(),
+        )
+    }
+
+    fn filters_entities(&self) -> bool {
+        self.last_run.is_some()
+    }
+
+    fn set_last_run(&mut self, tick: Option<usize>) {
+        self.last_run = tick;
+    }
+}
+
+impl<Rhs: Filter, T: Component> std::ops::BitAnd<Rhs> for ComponentAddedFilter<T> {
+    type Output = And<(Self, Rhs)>;
+
+    #[inline]
+    fn bitand(self, rhs: Rhs) -> Self::Output {
+        And {
+            filters: (self, rhs),
+        }
+    }
+}
+
+impl<Rhs: Filter, T: Component> std::ops::BitOr<Rhs> for ComponentAddedFilter<T> {
+    type Output = Or<(Self, Rhs)>;
+
+    #[inline]
+    fn bitor(self, rhs: Rhs) -> Self::Output {
+        Or {
+            filters: (self, rhs),
+        }
+    }
+}
+
 /// An iterator which filters chunks by filter `F` and yields `ChunkView`s.
 pub struct ChunkViewIter<'data, 'filter, V: View<'data>, F: Filter> {
     archetypes: Iter<'data, Archetype>,
@@ -833,10 +1260,14 @@ where
             if let Some(ref mut inner) = self.frontier {
                 for x in inner {
                     if self.filter.filter_chunk(x).is_pass() {
-                        return Some(ChunkView {
-                            chunk: x,
-                            view: PhantomData,
-                        });
+                        let matches = self.filter.filter_entities(x);
+                        if matches.as_ref().map(|m| m.any()).unwrap_or(true) {
+                            return Some(ChunkView {
+                                chunk: x,
+                                matches,
+                                view: PhantomData,
+                            });
+                        }
                     }
                 }
             }
@@ -862,7 +1293,7 @@ where
     I: Iterator<Item = ChunkView<'data, V>>,
 {
     iter: I,
-    frontier: Option<V::Iter>,
+    frontier: Option<MatchingIter<V::Iter>>,
     view: PhantomData<V>,
 }
 
@@ -882,7 +1313,7 @@ where
                 }
             }
             match self.iter.next() {
-                Some(mut inner) => self.frontier = Some(inner.iter()),
+                Some(mut inner) => self.frontier = Some(inner.iter_matching()),
                 None => return None,
             }
         }
@@ -1093,6 +1524,7 @@ pub trait Query {
 pub struct QueryDef<V: for<'a> View<'a>, F: Filter> {
     view: PhantomData<V>,
     filter: F,
+    last_run: Option<(WorldId, usize)>,
 }
 
 impl<V: for<'a> View<'a>, F: Filter> Query for QueryDef<V, F> {
@@ -1105,6 +1537,7 @@ impl<V: for<'a> View<'a>, F: Filter> Query for QueryDef<V, F> {
             filter: And {
                 filters: (self.filter, filter),
             },
+            last_run: self.last_run,
         }
     }
 
@@ -1112,6 +1545,15 @@ impl<V: for<'a> View<'a>, F: Filter> Query for QueryDef<V, F> {
         &'a mut self,
         world: &'data World,
     ) -> ChunkViewIter<'data, 'a, Self::View, Self::Filter> {
+        // a tick taken from another world's counter says nothing about this world's changes
+        let last_run = self
+            .last_run
+            .replace((world.id(), world.next_change_tick()));
+        self.filter.set_last_run(
+            last_run
+                .filter(|(id, _)| *id == world.id())
+                .map(|(_, tick)| tick),
+        );
         ChunkViewIter {
             archetypes: world.archetypes.iter(),
             filter: &mut self.filter,
@@ -1149,7 +1591,7 @@ impl<V: for<'a> View<'a>, F: Filter> Query for QueryDef<V, F> {
         T: Fn(<<V as View<'a>>::Iter as Iterator>::Item) + Send + Sync,
     {
         self.par_iter_chunks(world).for_each(|mut chunk| {
-            for data in chunk.iter() {
+            for data in chunk.iter_matching() {
                 f(data);
             }
         });
@@ -1157,6 +1599,23 @@ impl<V: for<'a> View<'a>, F: Filter> Query for QueryDef<V, F> {
 }
 
 impl<V: for<'a> View<'a>, F: Filter> QueryDef<V, F> {
+    /// Gets the world on which the query last ran and its change tick at the time, or `None` if
+    /// it has not yet run.
+    pub fn last_run(&self) -> Option<(WorldId, usize)> {
+        self.last_run
+    }
+
+    /// Sets the world on which the query last ran and its change tick at the time.
+    ///
+    /// The query's `changed` filters match the components which have been written since this
+    /// tick. A query which is run on behalf of several consumers can save each consumer's tick,
+    /// and restore it before running the query for that consumer.
+    ///
+    /// When the query is next run on a different world, it behaves as though it has not yet run.
+    pub fn set_last_run(&mut self, last_run: Option<(WorldId, usize)>) {
+        self.last_run = last_run;
+    }
+
     /// Gets a parallel iterator of chunks that match the query.
     #[cfg(feature = "par-iter")]
     pub fn par_iter_chunks<'a>(
@@ -1167,10 +1626,49 @@ impl<V: for<'a> View<'a>, F: Filter> QueryDef<V, F> {
     }
 }
 
+/// An iterator which yields view data from a `ChunkView`, skipping the entities which do not
+/// match the query's filter.
+pub struct MatchingIter<I: Iterator> {
+    data: I,
+    matches: Option<EntityMask>,
+    index: usize,
+}
+
+impl<I: Iterator> Iterator for MatchingIter<I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.matches {
+            Some(ref matches) => {
+                for data in self.data.by_ref() {
+                    let i = self.index;
+                    self.index += 1;
+                    if matches.get(i) {
+                        return Some(data);
+                    }
+                }
+
+                None
+            }
+            None => self.data.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.matches {
+            Some(ref matches) => (0, Some(matches.len() - self.index)),
+            None => self.data.size_hint(),
+        }
+    }
+}
+
 /// An iterator which yields view data tuples and entity IDs from a `ChunkView`.
 pub struct ZipEntities<'data, V: View<'data>> {
     entities: &'data [Entity],
     data: <V as View<'data>>::Iter,
+    matches: Option<EntityMask>,
     index: usize,
     view: PhantomData<V>,
 }
@@ -1180,25 +1678,31 @@ impl<'data, V: View<'data>> Iterator for ZipEntities<'data, V> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(data) = self.data.next() {
+        for data in self.data.by_ref() {
             let i = self.index;
             self.index += 1;
-            unsafe { Some((*self.entities.get_unchecked(i), data)) }
-        } else {
-            None
+            if self.matches.as_ref().map(|m| m.get(i)).unwrap_or(true) {
+                unsafe { return Some((*self.entities.get_unchecked(i), data)) }
+            }
         }
+
+        None
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
         let len = self.entities.len() - self.index;
-        (len, Some(len))
+        match self.matches {
+            Some(_) => (0, Some(len)),
+            None => (len, Some(len)),
+        }
     }
 }
 
 /// A type-safe view of a `Chunk`.
 pub struct ChunkView<'a, V: View<'a>> {
     chunk: &'a Chunk,
+    matches: Option<EntityMask>,
     view: PhantomData<V>,
 }
 
@@ -1210,22 +1714,46 @@ impl<'a, V: View<'a>> ChunkView<'a, V> {
     }
 
     /// Get an iterator of all data contained within the chunk.
+    ///
+    /// This includes the entities which do not match the query's entity-level filters, such as
+    /// `changed`. Use `iter_matching` to skip them.
     #[inline]
     pub fn iter(&mut self) -> V::Iter {
         V::fetch(self.chunk)
     }
 
-    /// Get an iterator of all data and entity IDs contained within the chunk.
+    /// Get an iterator of the data of all entities within the chunk which match the query.
+    #[inline]
+    pub fn iter_matching(&mut self) -> MatchingIter<V::Iter> {
+        MatchingIter {
+            data: V::fetch(self.chunk),
+            matches: self.matches.clone(),
+            index: 0,
+        }
+    }
+
+    /// Get an iterator of the data and entity IDs of all entities within the chunk which match
+    /// the query.
     #[inline]
     pub fn iter_entities(&mut self) -> ZipEntities<'a, V> {
         ZipEntities {
             entities: self.entities(),
             data: V::fetch(self.chunk),
+            matches: self.matches.clone(),
             index: 0,
             view: PhantomData,
         }
     }
 
+    /// Determines if the entity at `index` within the chunk matches the query.
+    ///
+    /// Entities may be excluded by filters such as `changed`, which distinguish between the
+    /// entities within a chunk.
+    #[inline]
+    pub fn is_match(&self, index: usize) -> bool {
+        self.matches.as_ref().map(|m| m.get(index)).unwrap_or(true)
+    }
+
     /// Get a tag value.
     pub fn tag<T: Tag>(&self) -> Option<&T> {
         self.chunk.tag()
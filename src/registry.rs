@@ -0,0 +1,517 @@
+//! Stable identifiers for component and tag types.
+//!
+//! `ComponentTypeId` and `TagTypeId` are built from `std::any::TypeId`, which may change between
+//! compilations and has no meaning outside of Rust. A `StableTypeId` is instead derived from a
+//! type name or assigned by the user, and so can be persisted, sent over the network or shared
+//! with foreign code. A `TypeRegistry` maps between the two, and also records a human readable
+//! name for each type which is used in debug output.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::registry::{StableTypeId, TypeRegistry};
+//! # use legion::Component;
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! struct Position(f32);
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! struct Velocity(f32);
+//!
+//! let velocity_id = StableTypeId::from_uuid(0x9b1d_1c7e_54a8_4b8e_8f7e_31d4_2a6c_0f15);
+//!
+//! let mut types = TypeRegistry::default();
+//! types
+//!     .register_component::<Position>("position")
+//!     .register_component_with_id::<Velocity>("velocity", velocity_id);
+//!
+//! let position = types.component_by_name("position").unwrap();
+//! assert_eq!(StableTypeId::from_name("position"), types.component(&position).unwrap().id());
+//! assert_eq!(Some(<Velocity as Component>::type_id()), types.component_by_id(velocity_id));
+//! ```
+
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::str::FromStr;
+
+use fnv::FnvHashMap;
+
+use crate::*;
+
+/// A type identifier which is stable across compilations and languages.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct StableTypeId(pub u128);
+
+impl StableTypeId {
+    /// Derives an ID from a type name, using the 128-bit FNV-1a hash of its UTF-8 bytes.
+    pub fn from_name(name: &str) -> Self {
+        const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+        const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+        let mut hash = OFFSET_BASIS;
+        for byte in name.bytes() {
+            hash ^= byte as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+        StableTypeId(hash)
+    }
+
+    /// Uses a user-assigned UUID as the ID.
+    pub const fn from_uuid(uuid: u128) -> Self {
+        StableTypeId(uuid)
+    }
+
+    /// Constructs an ID from its big-endian byte representation.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        StableTypeId(u128::from_be_bytes(bytes))
+    }
+
+    /// Gets the big-endian byte representation of the ID.
+    pub fn to_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+}
+
+impl Display for StableTypeId {
+    /// Formats the ID in the hyphenated form of a UUID.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            (self.0 >> 96) as u32,
+            (self.0 >> 80) as u16,
+            (self.0 >> 64) as u16,
+            (self.0 >> 48) as u16,
+            self.0 & 0xffff_ffff_ffff
+        )
+    }
+}
+
+impl Debug for StableTypeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StableTypeId({})", self)
+    }
+}
+
+/// An error returned when parsing a `StableTypeId` which is not 32 hexadecimal digits.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseStableTypeIdError;
+
+impl Display for ParseStableTypeIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid stable type ID")
+    }
+}
+
+impl std::error::Error for ParseStableTypeIdError {}
+
+impl FromStr for StableTypeId {
+    type Err = ParseStableTypeIdError;
+
+    /// Parses an ID from 32 hexadecimal digits, optionally separated by hyphens.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.chars().filter(|c| *c != '-').collect::<String>();
+        if digits.len() != 32 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ParseStableTypeIdError);
+        }
+
+        u128::from_str_radix(&digits, 16)
+            .map(StableTypeId)
+            .map_err(|_| ParseStableTypeIdError)
+    }
+}
+
+/// The stable ID and name of a registered type.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TypeInfo {
+    id: StableTypeId,
+    name: String,
+}
+
+impl TypeInfo {
+    /// Gets the stable ID of the type.
+    pub fn id(&self) -> StableTypeId {
+        self.id
+    }
+
+    /// Gets the name of the type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A bidirectional mapping between the runtime type IDs of one kind of type and their stable IDs
+/// and names.
+#[derive(Debug, Clone)]
+struct TypeTable<T: Copy + Eq + Hash + Debug> {
+    types: FnvHashMap<T, TypeInfo>,
+    ids: FnvHashMap<StableTypeId, T>,
+    names: FnvHashMap<String, T>,
+}
+
+impl<T: Copy + Eq + Hash + Debug> Default for TypeTable<T> {
+    fn default() -> Self {
+        TypeTable {
+            types: FnvHashMap::default(),
+            ids: FnvHashMap::default(),
+            names: FnvHashMap::default(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash + Debug> TypeTable<T> {
+    fn register(&mut self, kind: &str, ty: T, id: StableTypeId, name: &str) {
+        if let Some(existing) = self.ids.get(&id) {
+            assert!(
+                *existing == ty,
+                "a different {} type is already registered with ID {}",
+                kind,
+                id
+            );
+        }
+        if let Some(existing) = self.names.get(name) {
+            assert!(
+                *existing == ty,
+                "a different {} type is already registered as `{}`",
+                kind,
+                name
+            );
+        }
+
+        if let Some(previous) = self.types.remove(&ty) {
+            self.ids.remove(&previous.id);
+            self.names.remove(&previous.name);
+        }
+
+        self.ids.insert(id, ty);
+        self.names.insert(name.to_owned(), ty);
+        self.types.insert(
+            ty,
+            TypeInfo {
+                id,
+                name: name.to_owned(),
+            },
+        );
+    }
+}
+
+/// A registry of component and tag types, their stable IDs, and their names.
+///
+/// Registering a type again replaces its previous ID and name.
+#[derive(Default, Debug, Clone)]
+pub struct TypeRegistry {
+    components: TypeTable<ComponentTypeId>,
+    tags: TypeTable<TagTypeId>,
+}
+
+impl TypeRegistry {
+    /// Registers the component type `T` under the given name, with an ID derived from the name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub fn register_component<T: Component>(&mut self, name: &str) -> &mut Self {
+        self.register_component_raw(T::type_id(), StableTypeId::from_name(name), name)
+    }
+
+    /// Registers the component type `T` under the given name and ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub fn register_component_with_id<T: Component>(
+        &mut self,
+        name: &str,
+        id: StableTypeId,
+    ) -> &mut Self {
+        self.register_component_raw(T::type_id(), id, name)
+    }
+
+    /// Registers a component type by its runtime type ID, such as a type defined through the C
+    /// API.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub fn register_component_raw(
+        &mut self,
+        ty: ComponentTypeId,
+        id: StableTypeId,
+        name: &str,
+    ) -> &mut Self {
+        self.components.register("component", ty, id, name);
+        self
+    }
+
+    /// Registers the tag type `T` under the given name, with an ID derived from the name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub fn register_tag<T: Tag>(&mut self, name: &str) -> &mut Self {
+        self.register_tag_raw(T::type_id(), StableTypeId::from_name(name), name)
+    }
+
+    /// Registers the tag type `T` under the given name and ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub fn register_tag_with_id<T: Tag>(&mut self, name: &str, id: StableTypeId) -> &mut Self {
+        self.register_tag_raw(T::type_id(), id, name)
+    }
+
+    /// Registers a tag type by its runtime type ID, such as a type defined through the C API.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub fn register_tag_raw(&mut self, ty: TagTypeId, id: StableTypeId, name: &str) -> &mut Self {
+        self.tags.register("tag", ty, id, name);
+        self
+    }
+
+    /// Gets the stable ID and name of a registered component type.
+    pub fn component(&self, ty: &ComponentTypeId) -> Option<&TypeInfo> {
+        self.components.types.get(ty)
+    }
+
+    /// Finds the component type registered with the given stable ID.
+    pub fn component_by_id(&self, id: StableTypeId) -> Option<ComponentTypeId> {
+        self.components.ids.get(&id).cloned()
+    }
+
+    /// Finds the component type registered with the given name.
+    pub fn component_by_name(&self, name: &str) -> Option<ComponentTypeId> {
+        self.components.names.get(name).cloned()
+    }
+
+    /// Gets the stable ID and name of a registered tag type.
+    pub fn tag(&self, ty: &TagTypeId) -> Option<&TypeInfo> {
+        self.tags.types.get(ty)
+    }
+
+    /// Finds the tag type registered with the given stable ID.
+    pub fn tag_by_id(&self, id: StableTypeId) -> Option<TagTypeId> {
+        self.tags.ids.get(&id).cloned()
+    }
+
+    /// Finds the tag type registered with the given name.
+    pub fn tag_by_name(&self, name: &str) -> Option<TagTypeId> {
+        self.tags.names.get(name).cloned()
+    }
+
+    /// Iterates through all registered component types.
+    pub fn components(&self) -> impl Iterator<Item = (ComponentTypeId, &TypeInfo)> {
+        self.components.types.iter().map(|(ty, info)| (*ty, info))
+    }
+
+    /// Iterates through all registered tag types.
+    pub fn tags(&self) -> impl Iterator<Item = (TagTypeId, &TypeInfo)> {
+        self.tags.types.iter().map(|(ty, info)| (*ty, info))
+    }
+}
+
+/// Debug output for a `World` which shows its types by their registered names.
+///
+/// Created by `World::describe`.
+pub struct WorldDescription<'a> {
+    world: &'a World,
+    types: &'a TypeRegistry,
+}
+
+struct Unquoted<'a>(&'a str);
+
+impl<'a> Debug for Unquoted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Formats a set of type names without quotes.
+struct TypeNames(Vec<String>);
+
+impl Debug for TypeNames {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set()
+            .entries(self.0.iter().map(|name| Unquoted(name)))
+            .finish()
+    }
+}
+
+impl TypeNames {
+    /// Names each type by its registered name, falling back to its runtime type ID.
+    fn new<'a, T: Debug + 'a, I>(types: I) -> Self
+    where
+        I: Iterator<Item = (&'a T, Option<&'a TypeInfo>)>,
+    {
+        let mut names = types
+            .map(|(ty, info)| match info {
+                Some(info) => info.name().to_owned(),
+                None => format!("{:?}", ty),
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        TypeNames(names)
+    }
+}
+
+struct ArchetypeDescription {
+    tags: TypeNames,
+    components: TypeNames,
+    entity_count: usize,
+}
+
+impl Debug for ArchetypeDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Archetype")
+            .field("tags", &self.tags)
+            .field("components", &self.components)
+            .field("entities", &self.entity_count)
+            .finish()
+    }
+}
+
+impl<'a> Debug for WorldDescription<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut list = f.debug_list();
+        for archetype in self.world.archetypes.iter() {
+            let entity_count = archetype.chunks().iter().map(|c| c.len()).sum::<usize>();
+            if entity_count == 0 {
+                continue;
+            }
+
+            list.entry(&ArchetypeDescription {
+                tags: TypeNames::new(archetype.tags.iter().map(|ty| (ty, self.types.tag(ty)))),
+                components: TypeNames::new(
+                    archetype
+                        .components
+                        .iter()
+                        .map(|ty| (ty, self.types.component(ty))),
+                ),
+                entity_count,
+            });
+        }
+        list.finish()
+    }
+}
+
+impl World {
+    /// Describes the archetypes of the world for debug output, naming their tag and component
+    /// types as they are registered in `types`.
+    ///
+    /// Empty archetypes are omitted.
+    pub fn describe<'a>(&'a self, types: &'a TypeRegistry) -> WorldDescription<'a> {
+        WorldDescription { world: self, types }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Pos(f32);
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Rot(f32);
+
+    #[cfg(not(feature = "blanket-impl-comp"))]
+    impl DefaultComponentImpl for Pos {}
+    #[cfg(not(feature = "blanket-impl-comp"))]
+    impl DefaultComponentImpl for Rot {}
+
+    #[test]
+    fn stable_id_from_name() {
+        // test vectors for 128-bit FNV-1a
+        assert_eq!(
+            StableTypeId(0x6c62_272e_07bb_0142_62b8_2175_6295_c58d),
+            StableTypeId::from_name("")
+        );
+        assert_eq!(
+            StableTypeId(0xd228_cb69_6f1a_8caf_7891_2b70_4e4a_8964),
+            StableTypeId::from_name("a")
+        );
+        assert_ne!(
+            StableTypeId::from_name("pos"),
+            StableTypeId::from_name("rot")
+        );
+    }
+
+    #[test]
+    fn stable_id_format() {
+        let id = StableTypeId(0x0123_4567_89ab_cdef_0011_2233_4455_6677);
+        let formatted = id.to_string();
+        assert_eq!("01234567-89ab-cdef-0011-223344556677", formatted);
+        assert_eq!(Ok(id), formatted.parse());
+        assert_eq!(Ok(id), "0123456789abcdef0011223344556677".parse());
+        assert_eq!(StableTypeId::from_bytes(id.to_bytes()), id);
+        assert!("0123".parse::<StableTypeId>().is_err());
+        assert!("0123456789abcdef001122334455667g"
+            .parse::<StableTypeId>()
+            .is_err());
+    }
+
+    #[test]
+    fn lookups() {
+        let rot_id = StableTypeId::from_uuid(42);
+        let mut types = TypeRegistry::default();
+        types
+            .register_component::<Pos>("pos")
+            .register_component_with_id::<Rot>("rot", rot_id)
+            .register_tag::<Pos>("pos");
+
+        let info = types.component(&<Pos as Component>::type_id()).unwrap();
+        assert_eq!("pos", info.name());
+        assert_eq!(StableTypeId::from_name("pos"), info.id());
+        assert_eq!(
+            Some(<Pos as Component>::type_id()),
+            types.component_by_id(StableTypeId::from_name("pos"))
+        );
+        assert_eq!(
+            Some(<Rot as Component>::type_id()),
+            types.component_by_id(rot_id)
+        );
+        assert_eq!(
+            Some(<Rot as Component>::type_id()),
+            types.component_by_name("rot")
+        );
+        assert_eq!(Some(<Pos as Tag>::type_id()), types.tag_by_name("pos"));
+        assert_eq!(None, types.tag_by_name("rot"));
+    }
+
+    #[test]
+    fn reregister_replaces() {
+        let mut types = TypeRegistry::default();
+        types.register_component::<Pos>("pos");
+        types.register_component::<Pos>("position");
+
+        assert_eq!(None, types.component_by_name("pos"));
+        assert_eq!(None, types.component_by_id(StableTypeId::from_name("pos")));
+        assert_eq!(
+            Some(<Pos as Component>::type_id()),
+            types.component_by_name("position")
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn conflicting_id() {
+        let mut types = TypeRegistry::default();
+        types
+            .register_component_with_id::<Pos>("pos", StableTypeId(1))
+            .register_component_with_id::<Rot>("rot", StableTypeId(1));
+    }
+
+    #[test]
+    fn describe() {
+        let universe = Universe::new(None);
+        let mut world = universe.create_world();
+        world.insert_from((Pos(0.),).as_tags(), vec![(Rot(0.),), (Rot(1.),)]);
+
+        let mut types = TypeRegistry::default();
+        types.register_tag::<Pos>("pos");
+        let description = format!("{:?}", world.describe(&types));
+        assert!(description.contains("tags: {pos}"));
+        assert!(description.contains("ComponentTypeId"));
+        assert!(description.contains("entities: 2"));
+    }
+}
@@ -0,0 +1,547 @@
+//! A human-readable scene format for authoring entities in text files.
+//!
+//! A scene lists entities individually, each with an optional `id` and maps of its tag and
+//! component values keyed by the names under which their types were registered in a
+//! `SerializeRegistry`. Scenes can be written in any self-describing serde format, such as JSON
+//! or RON.
+//!
+//! ```json
+//! {
+//!   "entities": [
+//!     {
+//!       "id": { "index": 1, "version": 0 },
+//!       "tags": { "model": 5 },
+//!       "components": { "position": [0.0, 1.0] }
+//!     },
+//!     {
+//!       "components": { "position": [2.0, 0.0], "parent": { "index": 1, "version": 0 } }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Each entity is inserted with a newly allocated `Entity` ID. The `id`s in the file only identify
+//! entities within the scene; `Entity` references held by components which are registered in an
+//! `EntityMappers` are rewritten from these IDs to those of the inserted entities. References to
+//! IDs which do not appear in the scene are left unchanged.
+//!
+//! Entities can be exported back into the format with `World::serializable_scene`, which writes
+//! their current IDs as their scene IDs.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::serialize::SerializeRegistry;
+//! # use legion::{EntityMap, EntityMappers, MapEntities};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Position(f32, f32);
+//! #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Parent(Entity);
+//!
+//! impl MapEntities for Parent {
+//!     fn map_entities(&mut self, map: &EntityMap) {
+//!         if let Some(entity) = map.get(&self.0) {
+//!             self.0 = *entity;
+//!         }
+//!     }
+//! }
+//!
+//! let mut registry = SerializeRegistry::default();
+//! registry
+//!     .register_component::<Position>("position")
+//!     .register_component::<Parent>("parent");
+//! let mut mappers = EntityMappers::default();
+//! mappers.register::<Parent>();
+//!
+//! let scene = r#"{
+//!     "entities": [
+//!         { "id": { "index": 1, "version": 0 }, "components": { "position": [0.0, 1.0] } },
+//!         { "components": { "position": [2.0, 0.0], "parent": { "index": 1, "version": 0 } } }
+//!     ]
+//! }"#;
+//!
+//! let universe = Universe::new(None);
+//! let mut world = universe.create_world();
+//! let mut deserializer = serde_json::Deserializer::from_str(scene);
+//! let map = world.load_scene(&registry, &mappers, &mut deserializer).unwrap();
+//!
+//! let root = map[&serde_json::from_str(r#"{ "index": 1, "version": 0 }"#).unwrap()];
+//! let mut query = Read::<Parent>::query();
+//! let parents = query.iter(&world).map(|p| p.0).collect::<Vec<_>>();
+//! assert_eq!(vec![root], parents);
+//! ```
+
+use std::fmt;
+
+use fnv::FnvHashSet;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeStruct};
+use serde::{Deserializer, Serialize, Serializer};
+
+use crate::query::{EntityMask, Filter};
+use crate::serialize::{
+    ComponentRegistration, FieldName, Fields, MapFields, SeqFields, SerializeRegistry,
+    TagRegistration, TagSeed,
+};
+use crate::storage::{Archetype, Chunk, DynamicComponent, DynamicEntitySource, DynamicTagSet};
+use crate::*;
+
+const ENTITY_FIELDS: &[&str] = &["id", "tags", "components"];
+
+impl World {
+    /// Wraps the entities which match `filter` in a type which serializes them in the scene
+    /// format, using the component and tag types registered in `registry`.
+    ///
+    /// Serialization fails if any of the entities has a component or tag type which has not been
+    /// registered.
+    pub fn serializable_scene<'a, F: Filter>(
+        &'a self,
+        registry: &'a SerializeRegistry,
+        mut filter: F,
+    ) -> SerializableScene<'a> {
+        let mut chunks = Vec::new();
+        for archetype in self.archetypes.iter() {
+            for (chunk_index, matches) in World::filter_chunks(archetype, &mut filter) {
+                chunks.push((archetype, archetype.chunk(chunk_index).unwrap(), matches));
+            }
+        }
+
+        SerializableScene { registry, chunks }
+    }
+
+    /// Inserts the entities of a scene into this world.
+    ///
+    /// Each entity is allocated a new ID, and components registered in `mappers` have their
+    /// references to other entities in the scene rewritten to the new IDs.
+    ///
+    /// Returns a map from the `id` of each entity in the scene to its new ID. Nothing is added to
+    /// the world if deserialization fails.
+    pub fn load_scene<'de, D: Deserializer<'de>>(
+        &mut self,
+        registry: &SerializeRegistry,
+        mappers: &EntityMappers,
+        deserializer: D,
+    ) -> Result<EntityMap, D::Error> {
+        SceneDeserializer::new(self, registry, mappers).deserialize(deserializer)
+    }
+}
+
+/// The names and registrations of the tag and component types of an archetype.
+type Layout<'a> = (
+    Vec<(&'a str, &'a TagRegistration)>,
+    Vec<(&'a str, &'a ComponentRegistration)>,
+);
+
+/// A set of entities which can be serialized in the scene format.
+///
+/// Constructed by `World::serializable_scene`.
+pub struct SerializableScene<'a> {
+    registry: &'a SerializeRegistry,
+    chunks: Vec<(&'a Archetype, &'a Chunk, Option<EntityMask>)>,
+}
+
+impl<'a> SerializableScene<'a> {
+    /// Gets the names and registrations of the archetype's types, sorted by name.
+    fn registrations(&self, archetype: &Archetype) -> Result<Layout<'a>, String> {
+        let mut tags = archetype
+            .tags
+            .iter()
+            .map(|ty| {
+                self.registry
+                    .tag_registration(ty)
+                    .ok_or_else(|| format!("tag type {:?} is not registered", ty))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut components = archetype
+            .components
+            .iter()
+            .map(|ty| {
+                self.registry
+                    .component_registration(ty)
+                    .ok_or_else(|| format!("component type {:?} is not registered", ty))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        tags.sort_by_key(|(name, _)| *name);
+        components.sort_by_key(|(name, _)| *name);
+        Ok((tags, components))
+    }
+}
+
+impl<'a> Serialize for SerializableScene<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entities = Vec::new();
+        let mut layouts = Vec::new();
+        for (archetype, _, _) in self.chunks.iter() {
+            layouts.push(self.registrations(archetype).map_err(ser::Error::custom)?);
+        }
+
+        for ((_, chunk, matches), (tags, components)) in self.chunks.iter().zip(layouts.iter()) {
+            for (index, entity) in unsafe { chunk.entities() }.iter().enumerate() {
+                if matches.as_ref().map(|m| !m.get(index)).unwrap_or(false) {
+                    continue;
+                }
+
+                entities.push(SceneEntity {
+                    entity: *entity,
+                    chunk,
+                    index,
+                    tags,
+                    components,
+                });
+            }
+        }
+
+        let mut state = serializer.serialize_struct("Scene", 1)?;
+        state.serialize_field("entities", &entities)?;
+        state.end()
+    }
+}
+
+struct SceneEntity<'a> {
+    entity: Entity,
+    chunk: &'a Chunk,
+    index: usize,
+    tags: &'a [(&'a str, &'a TagRegistration)],
+    components: &'a [(&'a str, &'a ComponentRegistration)],
+}
+
+impl<'a> Serialize for SceneEntity<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Entity", 3)?;
+        state.serialize_field("id", &self.entity)?;
+        state.serialize_field("tags", &TagValues(self))?;
+        state.serialize_field("components", &ComponentValues(self))?;
+        state.end()
+    }
+}
+
+struct TagValues<'a, 'b>(&'b SceneEntity<'a>);
+
+impl<'a, 'b> Serialize for TagValues<'a, 'b> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entity = self.0;
+        serializer.collect_map(
+            entity
+                .tags
+                .iter()
+                .map(|(name, r)| (name, (r.serialize_fn)(entity.chunk))),
+        )
+    }
+}
+
+struct ComponentValues<'a, 'b>(&'b SceneEntity<'a>);
+
+impl<'a, 'b> Serialize for ComponentValues<'a, 'b> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entity = self.0;
+        serializer.collect_map(
+            entity
+                .components
+                .iter()
+                .map(|(name, r)| (name, (r.serialize_one_fn)(entity.chunk, entity.index))),
+        )
+    }
+}
+
+/// Deserializes a scene into a `World`, using the types registered in a `SerializeRegistry`.
+///
+/// Produces a map from the `id` of each entity in the scene to its new ID.
+///
+/// `World::load_scene` is a convenience wrapper around this `DeserializeSeed`.
+pub struct SceneDeserializer<'a> {
+    world: &'a mut World,
+    registry: &'a SerializeRegistry,
+    mappers: &'a EntityMappers,
+}
+
+impl<'a> SceneDeserializer<'a> {
+    /// Constructs a new `SceneDeserializer`.
+    pub fn new(
+        world: &'a mut World,
+        registry: &'a SerializeRegistry,
+        mappers: &'a EntityMappers,
+    ) -> Self {
+        SceneDeserializer {
+            world,
+            registry,
+            mappers,
+        }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for SceneDeserializer<'a> {
+    type Value = EntityMap;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<EntityMap, D::Error> {
+        // entities are inserted into a separate world so that only references held by the
+        // scene's entities are rewritten, and so that a failed load leaves no partial scene
+        let mut loaded = self.world.create_sibling();
+        let mut map = EntityMap::default();
+        deserializer.deserialize_struct(
+            "Scene",
+            &["entities"],
+            SceneVisitor {
+                world: &mut loaded,
+                registry: self.registry,
+                map: &mut map,
+            },
+        )?;
+
+        loaded.map_entities(self.mappers, &map);
+        self.world.merge(loaded);
+        Ok(map)
+    }
+}
+
+struct SceneVisitor<'a> {
+    world: &'a mut World,
+    registry: &'a SerializeRegistry,
+    map: &'a mut EntityMap,
+}
+
+impl<'a> SceneVisitor<'a> {
+    fn visit_fields<'de, F: Fields<'de>>(self, mut fields: F) -> Result<(), F::Error> {
+        fields.next(
+            "entities",
+            EntitiesSeed {
+                world: self.world,
+                registry: self.registry,
+                map: self.map,
+            },
+        )
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for SceneVisitor<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a scene")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        self.visit_fields(SeqFields(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        self.visit_fields(MapFields::new(map))
+    }
+}
+
+struct EntitiesSeed<'a> {
+    world: &'a mut World,
+    registry: &'a SerializeRegistry,
+    map: &'a mut EntityMap,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for EntitiesSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for EntitiesSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of entities")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some((id, tags, components)) = seq.next_element_seed(EntitySeed {
+            registry: self.registry,
+        })? {
+            let entity = self.world.insert(tags, components)[0];
+            if let Some(id) = id {
+                if self.map.insert(id, entity).is_some() {
+                    return Err(de::Error::custom(format!("duplicate entity ID {}", id)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes the ID, tags and components of a single entity.
+struct EntitySeed<'a> {
+    registry: &'a SerializeRegistry,
+}
+
+type EntityData = (Option<Entity>, DynamicTagSet, DynamicEntitySource);
+
+impl<'de, 'a> DeserializeSeed<'de> for EntitySeed<'a> {
+    type Value = EntityData;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<EntityData, D::Error> {
+        deserializer.deserialize_struct("Entity", ENTITY_FIELDS, self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for EntitySeed<'a> {
+    type Value = EntityData;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an entity")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<EntityData, A::Error> {
+        let id = seq
+            .next_element::<Entity>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let mut tags = DynamicTagSet::default();
+        let mut components = DynamicEntitySource::new();
+        seq.next_element_seed(TagValuesSeed {
+            registry: self.registry,
+            tags: &mut tags,
+        })?
+        .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        seq.next_element_seed(ComponentValuesSeed {
+            registry: self.registry,
+            components: &mut components,
+        })?
+        .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+
+        Ok((Some(id), tags, components))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<EntityData, A::Error> {
+        let mut id = None;
+        let mut tags = DynamicTagSet::default();
+        let mut components = DynamicEntitySource::new();
+        let mut seen = FnvHashSet::default();
+        while let Some(FieldName(key)) = map.next_key::<FieldName>()? {
+            let field = ENTITY_FIELDS
+                .iter()
+                .find(|f| **f == key)
+                .ok_or_else(|| de::Error::unknown_field(&key, ENTITY_FIELDS))?;
+            if !seen.insert(*field) {
+                return Err(de::Error::duplicate_field(field));
+            }
+
+            match *field {
+                "id" => id = Some(map.next_value::<Entity>()?),
+                "tags" => map.next_value_seed(TagValuesSeed {
+                    registry: self.registry,
+                    tags: &mut tags,
+                })?,
+                _ => map.next_value_seed(ComponentValuesSeed {
+                    registry: self.registry,
+                    components: &mut components,
+                })?,
+            }
+        }
+
+        Ok((id, tags, components))
+    }
+}
+
+/// Deserializes a map of tag values keyed by their registered names.
+struct TagValuesSeed<'a> {
+    registry: &'a SerializeRegistry,
+    tags: &'a mut DynamicTagSet,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for TagValuesSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for TagValuesSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map of tag values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = FnvHashSet::default();
+        while let Some(name) = map.next_key::<String>()? {
+            let registration = self
+                .registry
+                .types()
+                .tag_by_name(&name)
+                .and_then(|ty| self.registry.tag_registration(&ty))
+                .map(|(_, registration)| registration)
+                .ok_or_else(|| de::Error::custom(format!("unknown tag type `{}`", name)))?;
+            if !seen.insert(name.clone()) {
+                return Err(de::Error::custom(format!("duplicate tag `{}`", name)));
+            }
+
+            map.next_value_seed(TagSeed {
+                registration,
+                tags: &mut *self.tags,
+                upgrades: &[],
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes a map of component values keyed by their registered names.
+struct ComponentValuesSeed<'a> {
+    registry: &'a SerializeRegistry,
+    components: &'a mut DynamicEntitySource,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ComponentValuesSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ComponentValuesSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map of component values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = FnvHashSet::default();
+        while let Some(name) = map.next_key::<String>()? {
+            let registration = self
+                .registry
+                .types()
+                .component_by_name(&name)
+                .and_then(|ty| self.registry.component_registration(&ty))
+                .map(|(_, registration)| registration)
+                .ok_or_else(|| de::Error::custom(format!("unknown component type `{}`", name)))?;
+            if !seen.insert(name.clone()) {
+                return Err(de::Error::custom(format!("duplicate component `{}`", name)));
+            }
+
+            let component = map.next_value_seed(ComponentSeed { registration })?;
+            self.components.add_dynamic_component(component);
+        }
+
+        Ok(())
+    }
+}
+
+struct ComponentSeed<'a> {
+    registration: &'a ComponentRegistration,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ComponentSeed<'a> {
+    type Value = DynamicComponent;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<DynamicComponent, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.registration.deserialize_one_fn)(&mut deserializer).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,399 @@
+//! Schema evolution for worlds persisted with the `serialize` module.
+//!
+//! Each type registered in a `SerializeRegistry` has a schema version, which is written alongside
+//! the world whenever it is serialized. A type starts at version 0, and a new version is created
+//! each time the type's serialized representation changes by attaching a `Schema` to its
+//! registration. The schema lists the representation of every previous version of the type and
+//! the function which upgrades each version to the next, so that data written by older builds is
+//! migrated as it is loaded.
+//!
+//! Serialized data may also contain types which are not registered at all, such as those which
+//! have been removed from the game or belong to a plugin which is not loaded. By default, loading
+//! such data fails, but the registry may instead be configured with an `UnknownTypePolicy` to drop
+//! those types with a warning, or to preserve their values as `OpaqueValue`s. Preserved values are
+//! attached to their entities via the `OpaqueTypes` tag and the `OpaqueComponents` component, and
+//! are written back out unchanged when the world is serialized again.
+//!
+//! Preserving values requires a self-describing format such as JSON or RON, and dropping types
+//! requires a format which can skip values. Neither is supported by non-self-describing formats
+//! such as bincode.
+//!
+//! Schema versions apply only to the world format of `World::serializable`. Scenes and the binary
+//! format of the `binary` module are not versioned.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::registry::StableTypeId;
+//! # use legion::schema::Schema;
+//! # use legion::serialize::SerializeRegistry;
+//! # use serde::{Deserialize, Serialize};
+//! // the first version of the position component stored only two dimensions
+//! #[derive(Deserialize)]
+//! struct PositionV0(f32, f32);
+//!
+//! #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Position {
+//!     x: f32,
+//!     y: f32,
+//!     z: f32,
+//! }
+//!
+//! // a world saved before the third dimension was added
+//! let id = StableTypeId::from_name("position");
+//! let old_data = serde_json::json!({
+//!     "schema": { "components": [[id, 0]], "tags": [] },
+//!     "archetypes": [{
+//!         "tags": [],
+//!         "components": [id],
+//!         "chunks": [{
+//!             "tags": [],
+//!             "entities": [{ "index": 0, "version": 0 }],
+//!             "components": [[[1.0, 2.0]]]
+//!         }]
+//!     }]
+//! });
+//!
+//! let mut registry = SerializeRegistry::default();
+//! registry
+//!     .register_component::<Position>("position")
+//!     .set_component_schema(Schema::<PositionV0>::new().upgrade(|old| Position {
+//!         x: old.0,
+//!         y: old.1,
+//!         z: 0.,
+//!     }));
+//!
+//! let universe = Universe::new(None);
+//! let world = World::deserialize(&universe, &registry, old_data).unwrap();
+//! let positions = Read::<Position>::query().iter(&world).map(|p| *p).collect::<Vec<_>>();
+//! assert_eq!(vec![Position { x: 1., y: 2., z: 0. }], positions);
+//! ```
+
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::registry::StableTypeId;
+use crate::DefaultComponentImpl;
+
+type DeserializeVersionFn = for<'de> fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<Box<dyn Any>, erased_serde::Error>;
+type UpgradeFn = Arc<dyn Fn(Box<dyn Any>) -> Box<dyn Any> + Send + Sync>;
+
+/// The representation of one previous version of a type, and the function which upgrades it to
+/// the next version.
+#[derive(Clone)]
+pub(crate) struct Upgrade {
+    deserialize_fn: DeserializeVersionFn,
+    upgrade_fn: UpgradeFn,
+}
+
+/// The version history of a persisted type, ending with the type `T`.
+///
+/// A schema is started with the representation of version 0 of a type, and each call to
+/// `upgrade` appends a new version. The schema of a registered type must end with the registered
+/// type itself, and so its current version is the number of upgrades it contains.
+pub struct Schema<T> {
+    upgrades: Vec<Upgrade>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Schema<T> {
+    /// Constructs a new schema whose version 0 is represented by `T`.
+    pub fn new() -> Self {
+        Schema {
+            upgrades: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Gets the version of the type at the end of the schema.
+    pub fn version(&self) -> u32 {
+        self.upgrades.len() as u32
+    }
+
+    /// Appends a new version represented by `U`, which is produced from the current version by
+    /// the `upgrade` function.
+    pub fn upgrade<U, F>(mut self, upgrade: F) -> Schema<U>
+    where
+        T: DeserializeOwned,
+        U: 'static,
+        F: Fn(T) -> U + Send + Sync + 'static,
+    {
+        self.upgrades.push(Upgrade {
+            deserialize_fn: deserialize_version::<T>,
+            upgrade_fn: Arc::new(move |value| {
+                Box::new(upgrade(*value.downcast::<T>().unwrap())) as Box<dyn Any>
+            }),
+        });
+
+        Schema {
+            upgrades: self.upgrades,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn into_upgrades(self) -> Vec<Upgrade> {
+        self.upgrades
+    }
+}
+
+impl<T: 'static> Default for Schema<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn deserialize_version<'de, T: DeserializeOwned + 'static>(
+    deserializer: &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<Box<dyn Any>, erased_serde::Error> {
+    erased_serde::deserialize::<T>(deserializer).map(|value| Box::new(value) as Box<dyn Any>)
+}
+
+/// Deserializes a value written by an older version of `T`, and upgrades it to `T`.
+///
+/// `upgrades` begins with the version the value was written with.
+pub(crate) struct UpgradeSeed<'a, T> {
+    upgrades: &'a [Upgrade],
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> UpgradeSeed<'a, T> {
+    pub(crate) fn new(upgrades: &'a [Upgrade]) -> Self {
+        UpgradeSeed {
+            upgrades,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'a, T: 'static> DeserializeSeed<'de> for UpgradeSeed<'a, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        let mut value =
+            (self.upgrades[0].deserialize_fn)(&mut deserializer).map_err(de::Error::custom)?;
+        for upgrade in self.upgrades {
+            value = (upgrade.upgrade_fn)(value);
+        }
+
+        Ok(*value.downcast::<T>().unwrap())
+    }
+}
+
+/// Determines how types which are not registered are handled when a world is deserialized.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum UnknownTypePolicy {
+    /// Deserialization fails.
+    #[default]
+    Error,
+    /// The values of unknown types are discarded, and a warning is logged.
+    Drop,
+    /// The values of unknown types are kept as `OpaqueValue`s, attached to their entities via the
+    /// `OpaqueTypes` tag and the `OpaqueComponents` component.
+    Preserve,
+}
+
+/// The stable ID and schema version of a type which was preserved without being registered.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OpaqueType {
+    id: StableTypeId,
+    version: u32,
+}
+
+impl OpaqueType {
+    pub(crate) fn new(id: StableTypeId, version: u32) -> Self {
+        OpaqueType { id, version }
+    }
+
+    /// Gets the stable ID of the type.
+    pub fn id(&self) -> StableTypeId {
+        self.id
+    }
+
+    /// Gets the schema version which the type's values were written with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// A tag which describes the unknown types preserved on the entities of a chunk.
+///
+/// Every entity with this tag also has an `OpaqueComponents` component when any component types
+/// were preserved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpaqueTypes {
+    components: Vec<OpaqueType>,
+    tags: Vec<(OpaqueType, OpaqueValue)>,
+}
+
+impl OpaqueTypes {
+    pub(crate) fn new(components: Vec<OpaqueType>, tags: Vec<(OpaqueType, OpaqueValue)>) -> Self {
+        OpaqueTypes { components, tags }
+    }
+
+    /// Gets the preserved component types, in the order of their values in `OpaqueComponents`.
+    pub fn components(&self) -> &[OpaqueType] {
+        &self.components
+    }
+
+    /// Gets the preserved tag types and their values.
+    pub fn tags(&self) -> &[(OpaqueType, OpaqueValue)] {
+        &self.tags
+    }
+}
+
+impl DefaultComponentImpl for OpaqueTypes {}
+
+/// The values of the unknown component types preserved on an entity, in the order of
+/// `OpaqueTypes::components`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpaqueComponents(Vec<OpaqueValue>);
+
+impl OpaqueComponents {
+    pub(crate) fn new(values: Vec<OpaqueValue>) -> Self {
+        OpaqueComponents(values)
+    }
+
+    /// Gets the preserved values.
+    pub fn values(&self) -> &[OpaqueValue] {
+        &self.0
+    }
+}
+
+impl DefaultComponentImpl for OpaqueComponents {}
+
+/// A value of an unknown type, captured from a self-describing format so that it can be
+/// serialized again unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpaqueValue {
+    Unit,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<OpaqueValue>),
+    Seq(Vec<OpaqueValue>),
+    Map(Vec<(OpaqueValue, OpaqueValue)>),
+}
+
+impl Serialize for OpaqueValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OpaqueValue::Unit => serializer.serialize_unit(),
+            OpaqueValue::Bool(value) => serializer.serialize_bool(*value),
+            OpaqueValue::I64(value) => serializer.serialize_i64(*value),
+            OpaqueValue::U64(value) => serializer.serialize_u64(*value),
+            OpaqueValue::F64(value) => serializer.serialize_f64(*value),
+            OpaqueValue::Char(value) => serializer.serialize_char(*value),
+            OpaqueValue::String(value) => serializer.serialize_str(value),
+            OpaqueValue::Bytes(value) => serializer.serialize_bytes(value),
+            OpaqueValue::None => serializer.serialize_none(),
+            OpaqueValue::Some(value) => serializer.serialize_some(value),
+            OpaqueValue::Seq(values) => serializer.collect_seq(values),
+            OpaqueValue::Map(entries) => {
+                serializer.collect_map(entries.iter().map(|(key, value)| (key, value)))
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OpaqueValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(OpaqueValueVisitor)
+    }
+}
+
+struct OpaqueValueVisitor;
+
+impl<'de> Visitor<'de> for OpaqueValueVisitor {
+    type Value = OpaqueValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::I64(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::U64(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::F64(value))
+    }
+
+    fn visit_char<E: de::Error>(self, value: char) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Char(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::String(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::String(value))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Bytes(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Bytes(value))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::Unit)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<OpaqueValue, E> {
+        Ok(OpaqueValue::None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<OpaqueValue, D::Error> {
+        OpaqueValue::deserialize(deserializer).map(|value| OpaqueValue::Some(Box::new(value)))
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<OpaqueValue, D::Error> {
+        OpaqueValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<OpaqueValue, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(OpaqueValue::Seq(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OpaqueValue, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+
+        Ok(OpaqueValue::Map(entries))
+    }
+}
@@ -0,0 +1,1546 @@
+//! Serialization of `World`s with serde.
+//!
+//! Component and tag types are not required to implement `Serialize`, so the types which are to
+//! be persisted must be registered in a `SerializeRegistry`. Each type is registered under a name
+//! and a `StableTypeId`, which is derived from the name unless one is given explicitly. The ID
+//! identifies the type in serialized data, and so should remain stable across builds.
+//!
+//! A world is serialized as a table of the schema version of each type it contains, followed by
+//! its archetypes, each of which contains the stable IDs of its tag and component types followed by
+//! its chunks. Each chunk contains its tag values, the IDs of its entities and one column of values
+//! for each component type. Chunks are read straight into chunk storage, so formats which represent
+//! structs as maps must preserve the order of their fields. See the `schema` module for how
+//! versions are used to upgrade data saved by older builds. Worlds saved as maps without a schema
+//! table are read as version 0 of every type.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::serialize::SerializeRegistry;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Position(f32);
+//!
+//! let universe = Universe::new(None);
+//! let mut world = universe.create_world();
+//! let entity = world.insert_from((), vec![(Position(1.),)])[0];
+//!
+//! let mut registry = SerializeRegistry::default();
+//! registry.register_component::<Position>("position");
+//!
+//! let json = serde_json::to_string(&world.serializable(&registry)).unwrap();
+//!
+//! let load_universe = Universe::new(None);
+//! let mut deserializer = serde_json::Deserializer::from_str(&json);
+//! let loaded = World::deserialize(&load_universe, &registry, &mut deserializer).unwrap();
+//!
+//! assert_eq!(Position(1.), *loaded.component::<Position>(entity).unwrap());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeSeq, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use slog::warn;
+
+use crate::borrows::BorrowedSlice;
+use crate::registry::{StableTypeId, TypeRegistry};
+use crate::schema::{
+    OpaqueComponents, OpaqueType, OpaqueTypes, OpaqueValue, Schema, UnknownTypePolicy, Upgrade,
+    UpgradeSeed,
+};
+use crate::storage::{Archetype, Chunk, ChunkBuilder, DynamicComponent, DynamicTagSet};
+use crate::*;
+
+type SerializeColumnFn = for<'a> fn(&'a Chunk) -> Box<dyn erased_serde::Serialize + 'a>;
+type DeserializeColumnFn = for<'de> fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+    &[Chunk],
+    usize,
+    &[Upgrade],
+) -> Result<(), erased_serde::Error>;
+type SerializeComponentFn = for<'a> fn(&'a Chunk, usize) -> Box<dyn erased_serde::Serialize + 'a>;
+type DeserializeComponentFn = for<'de> fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<DynamicComponent, erased_serde::Error>;
+type SerializeTagFn = fn(&Chunk) -> &dyn erased_serde::Serialize;
+type DeserializeTagFn = for<'de> fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+    &mut DynamicTagSet,
+    &[Upgrade],
+) -> Result<(), erased_serde::Error>;
+
+#[derive(Clone)]
+pub(crate) struct ComponentRegistration {
+    pub(crate) id: StableTypeId,
+    pub(crate) ty: ComponentTypeId,
+    size: usize,
+    drop_fn: Option<fn(*mut u8)>,
+    pub(crate) serialize_fn: SerializeColumnFn,
+    deserialize_fn: DeserializeColumnFn,
+    upgrades: Vec<Upgrade>,
+    pub(crate) serialize_one_fn: SerializeComponentFn,
+    pub(crate) deserialize_one_fn: DeserializeComponentFn,
+}
+
+#[derive(Clone)]
+pub(crate) struct TagRegistration {
+    pub(crate) id: StableTypeId,
+    pub(crate) serialize_fn: SerializeTagFn,
+    deserialize_fn: DeserializeTagFn,
+    upgrades: Vec<Upgrade>,
+}
+
+/// Gets the upgrades which apply to values serialized with the given version of a type, or `None`
+/// if the version is newer than the type's current version.
+fn upgrades_from(upgrades: &[Upgrade], version: u32) -> Option<&[Upgrade]> {
+    upgrades.get(version as usize..)
+}
+
+/// A registry of the component and tag types which can be serialized, and the stable IDs which
+/// identify them in serialized data.
+#[derive(Default, Clone)]
+pub struct SerializeRegistry {
+    types: TypeRegistry,
+    components: FnvHashMap<ComponentTypeId, ComponentRegistration>,
+    tags: FnvHashMap<TagTypeId, TagRegistration>,
+    unknown_types: UnknownTypePolicy,
+}
+
+impl SerializeRegistry {
+    /// Registers the component type `T` under the given name, with a stable ID derived from the
+    /// name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub fn register_component<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.register_component_with_id::<T>(name, StableTypeId::from_name(name))
+    }
+
+    /// Registers the component type `T` under the given name and stable ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different component type has already been registered with the same name or ID.
+    pub fn register_component_with_id<T>(&mut self, name: &str, id: StableTypeId) -> &mut Self
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        let ty = T::type_id();
+        self.types.register_component_raw(ty, id, name);
+        self.components.insert(
+            ty,
+            ComponentRegistration {
+                id,
+                ty,
+                size: std::mem::size_of::<T>(),
+                drop_fn: Some(|ptr| unsafe { std::ptr::drop_in_place::<T>(ptr as *mut T) }),
+                serialize_fn: serialize_column::<T>,
+                deserialize_fn: deserialize_column::<T>,
+                upgrades: Vec::new(),
+                serialize_one_fn: serialize_component::<T>,
+                deserialize_one_fn: deserialize_component::<T>,
+            },
+        );
+        self
+    }
+
+    /// Registers the tag type `T` under the given name, with a stable ID derived from the name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub fn register_tag<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: Tag + Serialize + DeserializeOwned,
+    {
+        self.register_tag_with_id::<T>(name, StableTypeId::from_name(name))
+    }
+
+    /// Registers the tag type `T` under the given name and stable ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different tag type has already been registered with the same name or ID.
+    pub fn register_tag_with_id<T>(&mut self, name: &str, id: StableTypeId) -> &mut Self
+    where
+        T: Tag + Serialize + DeserializeOwned,
+    {
+        let ty = T::type_id();
+        self.types.register_tag_raw(ty, id, name);
+        self.tags.insert(
+            ty,
+            TagRegistration {
+                id,
+                serialize_fn: serialize_tag::<T>,
+                deserialize_fn: deserialize_tag::<T>,
+                upgrades: Vec::new(),
+            },
+        );
+        self
+    }
+
+    /// Sets the version history of the registered component type `T`.
+    ///
+    /// The type's current version becomes the version at the end of `schema`, and data
+    /// serialized with any earlier version is upgraded as it is deserialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` has not been registered.
+    pub fn set_component_schema<T: Component>(&mut self, schema: Schema<T>) -> &mut Self {
+        self.components
+            .get_mut(&T::type_id())
+            .expect("component type has not been registered")
+            .upgrades = schema.into_upgrades();
+        self
+    }
+
+    /// Sets the version history of the registered tag type `T`.
+    ///
+    /// The type's current version becomes the version at the end of `schema`, and data
+    /// serialized with any earlier version is upgraded as it is deserialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` has not been registered.
+    pub fn set_tag_schema<T: Tag>(&mut self, schema: Schema<T>) -> &mut Self {
+        self.tags
+            .get_mut(&T::type_id())
+            .expect("tag type has not been registered")
+            .upgrades = schema.into_upgrades();
+        self
+    }
+
+    /// Sets how types which are not registered are handled when a world is deserialized.
+    ///
+    /// Defaults to `UnknownTypePolicy::Error`.
+    pub fn set_unknown_type_policy(&mut self, policy: UnknownTypePolicy) -> &mut Self {
+        self.unknown_types = policy;
+        self
+    }
+
+    /// Gets the stable IDs and names of the registered types.
+    pub fn types(&self) -> &TypeRegistry {
+        &self.types
+    }
+
+    /// Gets the name and registration of a component type.
+    pub(crate) fn component_registration(
+        &self,
+        ty: &ComponentTypeId,
+    ) -> Option<(&str, &ComponentRegistration)> {
+        self.components
+            .get(ty)
+            .map(|r| (self.types.component(ty).unwrap().name(), r))
+    }
+
+    /// Gets the name and registration of a tag type.
+    pub(crate) fn tag_registration(&self, ty: &TagTypeId) -> Option<(&str, &TagRegistration)> {
+        self.tags
+            .get(ty)
+            .map(|r| (self.types.tag(ty).unwrap().name(), r))
+    }
+
+    pub(crate) fn component(&self, id: StableTypeId) -> Option<&ComponentRegistration> {
+        self.types
+            .component_by_id(id)
+            .map(|ty| &self.components[&ty])
+    }
+
+    pub(crate) fn tag(&self, id: StableTypeId) -> Option<(TagTypeId, &TagRegistration)> {
+        self.types.tag_by_id(id).map(|ty| (ty, &self.tags[&ty]))
+    }
+}
+
+impl Serialize for StableTypeId {
+    /// Serializes the ID as a hyphenated string in human readable formats, or as a `u128`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u128(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StableTypeId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(de::Error::custom)
+        } else {
+            u128::deserialize(deserializer).map(StableTypeId)
+        }
+    }
+}
+
+struct Column<'a, T: Component>(BorrowedSlice<'a, T>);
+
+impl<'a, T: Component + Serialize> Serialize for Column<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+fn serialize_column<T: Component + Serialize>(
+    chunk: &Chunk,
+) -> Box<dyn erased_serde::Serialize + '_> {
+    Box::new(Column(chunk.components::<T>().unwrap()))
+}
+
+struct Element<'a, T: Component>(BorrowedSlice<'a, T>, usize);
+
+impl<'a, T: Component + Serialize> Serialize for Element<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0[self.1].serialize(serializer)
+    }
+}
+
+fn serialize_component<T: Component + Serialize>(
+    chunk: &Chunk,
+    index: usize,
+) -> Box<dyn erased_serde::Serialize + '_> {
+    Box::new(Element(chunk.components::<T>().unwrap(), index))
+}
+
+fn deserialize_component<'de, T: Component + DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<DynamicComponent, erased_serde::Error> {
+    erased_serde::deserialize::<T>(deserializer).map(DynamicComponent::new)
+}
+
+/// Deserializes a sequence of exactly `len` components directly into the storage of `chunks`,
+/// filling each chunk to capacity before moving on to the next. Components serialized with an
+/// older version of `T` are upgraded as they are read.
+///
+/// Components which were written before an error occurred are dropped.
+fn deserialize_column<'de, T: Component + DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer<'de>,
+    chunks: &[Chunk],
+    len: usize,
+    upgrades: &[Upgrade],
+) -> Result<(), erased_serde::Error> {
+    let ptrs = chunks
+        .iter()
+        .map(|chunk| unsafe { chunk.components_mut_raw::<T>() }.unwrap())
+        .collect::<Vec<_>>();
+    let capacity = chunks[0].capacity();
+    let mut count = 0;
+    let result = serde::Deserializer::deserialize_seq(
+        deserializer,
+        ColumnVisitor {
+            ptrs: &ptrs,
+            capacity,
+            len,
+            upgrades,
+            count: &mut count,
+        },
+    );
+
+    if result.is_err() {
+        for i in 0..count {
+            unsafe { std::ptr::drop_in_place(ptrs[i / capacity].as_ptr().add(i % capacity)) };
+        }
+    }
+
+    result
+}
+
+struct ColumnVisitor<'a, T> {
+    ptrs: &'a [NonNull<T>],
+    capacity: usize,
+    len: usize,
+    upgrades: &'a [Upgrade],
+    count: &'a mut usize,
+}
+
+impl<'de, 'a, T: Component + DeserializeOwned> Visitor<'de> for ColumnVisitor<'a, T> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of {} components", self.len)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        loop {
+            let component = if self.upgrades.is_empty() {
+                seq.next_element::<T>()?
+            } else {
+                seq.next_element_seed(UpgradeSeed::<T>::new(self.upgrades))?
+            };
+            let component = match component {
+                Some(component) => component,
+                None => break,
+            };
+
+            if *self.count == self.len {
+                return Err(de::Error::invalid_length(self.len + 1, &self));
+            }
+
+            let ptr = self.ptrs[*self.count / self.capacity];
+            unsafe {
+                ptr.as_ptr()
+                    .add(*self.count % self.capacity)
+                    .write(component)
+            };
+            *self.count += 1;
+        }
+
+        if *self.count != self.len {
+            return Err(de::Error::invalid_length(*self.count, &self));
+        }
+
+        Ok(())
+    }
+}
+
+fn serialize_tag<T: Tag + Serialize>(chunk: &Chunk) -> &dyn erased_serde::Serialize {
+    chunk.tag::<T>().unwrap()
+}
+
+fn deserialize_tag<'de, T: Tag + DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer<'de>,
+    tags: &mut DynamicTagSet,
+    upgrades: &[Upgrade],
+) -> Result<(), erased_serde::Error> {
+    let tag = if upgrades.is_empty() {
+        erased_serde::deserialize::<T>(deserializer)?
+    } else {
+        UpgradeSeed::<T>::new(upgrades).deserialize(deserializer)?
+    };
+    tags.set_tag(tag);
+    Ok(())
+}
+
+impl World {
+    /// Wraps the world in a type which implements `Serialize`, using the component and tag types
+    /// registered in `registry`.
+    ///
+    /// Serialization fails if any entity in the world has a component or tag type which has not
+    /// been registered.
+    pub fn serializable<'a>(&'a self, registry: &'a SerializeRegistry) -> SerializableWorld<'a> {
+        SerializableWorld {
+            world: self,
+            registry,
+        }
+    }
+
+    /// Deserializes a world which was serialized via `World::serializable` into a new `World`
+    /// within `universe`.
+    ///
+    /// Entities keep the IDs they were serialized with. Deserialization fails if any of those IDs
+    /// may already have been allocated by another world in `universe`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        universe: &Universe,
+        registry: &SerializeRegistry,
+        deserializer: D,
+    ) -> Result<World, D::Error> {
+        WorldDeserializer::new(universe, registry).deserialize(deserializer)
+    }
+
+    /// Deserializes a world which was serialized via `World::serializable` into this world.
+    ///
+    /// Each deserialized entity is allocated a new ID from this world's universe, so the data can
+    /// be loaded any number of times. Components registered in `mappers` have their `Entity`
+    /// references rewritten to the new IDs.
+    ///
+    /// Returns a map from each entity's serialized ID to its new ID. Nothing is added to the world
+    /// if deserialization fails.
+    pub fn deserialize_into<'de, D: Deserializer<'de>>(
+        &mut self,
+        registry: &SerializeRegistry,
+        mappers: &EntityMappers,
+        deserializer: D,
+    ) -> Result<EntityMap, D::Error> {
+        WorldMergeDeserializer::new(self, registry, mappers).deserialize(deserializer)
+    }
+}
+
+/// A `World` which can be serialized with serde.
+///
+/// Constructed by `World::serializable`.
+pub struct SerializableWorld<'a> {
+    world: &'a World,
+    registry: &'a SerializeRegistry,
+}
+
+impl<'a> Serialize for SerializableWorld<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut archetypes = Vec::new();
+        for archetype in self.world.archetypes.iter() {
+            SerializableArchetype::collect(archetype, self.registry, &mut archetypes)
+                .map_err(ser::Error::custom)?;
+        }
+
+        let schema = SchemaTable::new(&archetypes).map_err(ser::Error::custom)?;
+
+        let mut state = serializer.serialize_struct("World", 2)?;
+        state.serialize_field("schema", &schema)?;
+        state.serialize_field("archetypes", &archetypes)?;
+        state.end()
+    }
+}
+
+/// The schema version of each type which appears in a serialized world.
+#[derive(Default, Serialize, Deserialize)]
+struct SchemaTable {
+    components: Vec<(StableTypeId, u32)>,
+    tags: Vec<(StableTypeId, u32)>,
+}
+
+impl SchemaTable {
+    fn new(archetypes: &[SerializableArchetype]) -> Result<Self, String> {
+        fn insert(
+            versions: &mut BTreeMap<StableTypeId, u32>,
+            id: StableTypeId,
+            version: u32,
+        ) -> Result<(), String> {
+            match versions.insert(id, version) {
+                Some(existing) if existing != version => Err(format!(
+                    "type {} has been preserved with versions {} and {}",
+                    id, existing, version
+                )),
+                _ => Ok(()),
+            }
+        }
+
+        let mut components = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+        for archetype in archetypes {
+            for registration in &archetype.components {
+                insert(
+                    &mut components,
+                    registration.id,
+                    registration.upgrades.len() as u32,
+                )?;
+            }
+            for registration in &archetype.tags {
+                insert(
+                    &mut tags,
+                    registration.id,
+                    registration.upgrades.len() as u32,
+                )?;
+            }
+            if let Some(opaque) = archetype.opaque {
+                for ty in opaque.components() {
+                    insert(&mut components, ty.id(), ty.version())?;
+                }
+                for (ty, _) in opaque.tags() {
+                    insert(&mut tags, ty.id(), ty.version())?;
+                }
+            }
+        }
+
+        Ok(SchemaTable {
+            components: components.into_iter().collect(),
+            tags: tags.into_iter().collect(),
+        })
+    }
+}
+
+struct SerializableArchetype<'a> {
+    tags: Vec<&'a TagRegistration>,
+    components: Vec<&'a ComponentRegistration>,
+    opaque: Option<&'a OpaqueTypes>,
+    chunks: Vec<&'a Chunk>,
+}
+
+impl<'a> SerializableArchetype<'a> {
+    /// Collects the non-empty chunks of an archetype into serializable archetypes.
+    ///
+    /// Chunks with preserved unknown types are each serialized as their own archetype, as the
+    /// types they contain depend upon their `OpaqueTypes` tag.
+    fn collect(
+        archetype: &'a Archetype,
+        registry: &'a SerializeRegistry,
+        archetypes: &mut Vec<Self>,
+    ) -> Result<(), String> {
+        let chunks = archetype
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.len() > 0)
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
+        let opaque_tag = <OpaqueTypes as Tag>::type_id();
+        let opaque_component = <OpaqueComponents as Component>::type_id();
+        let tags = archetype
+            .tags
+            .iter()
+            .filter(|ty| **ty != opaque_tag)
+            .map(|ty| {
+                registry
+                    .tags
+                    .get(ty)
+                    .ok_or_else(|| format!("tag type {:?} is not registered", ty))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let components = archetype
+            .components
+            .iter()
+            .filter(|ty| **ty != opaque_component)
+            .map(|ty| {
+                registry
+                    .components
+                    .get(ty)
+                    .ok_or_else(|| format!("component type {:?} is not registered", ty))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !archetype.tags.contains(&opaque_tag) {
+            if archetype.components.contains(&opaque_component) {
+                return Err("entities with opaque components have no opaque types tag".to_owned());
+            }
+
+            archetypes.push(SerializableArchetype {
+                tags,
+                components,
+                opaque: None,
+                chunks,
+            });
+            return Ok(());
+        }
+
+        for chunk in chunks {
+            let opaque = chunk.tag::<OpaqueTypes>().unwrap();
+            if !opaque.components().is_empty() && !archetype.components.contains(&opaque_component)
+            {
+                return Err("entities with opaque types have no opaque components".to_owned());
+            }
+
+            let conflict = opaque
+                .components()
+                .iter()
+                .find(|ty| components.iter().any(|r| r.id == ty.id()))
+                .or_else(|| {
+                    opaque
+                        .tags()
+                        .iter()
+                        .map(|(ty, _)| ty)
+                        .find(|ty| tags.iter().any(|r| r.id == ty.id()))
+                });
+            if let Some(ty) = conflict {
+                return Err(format!(
+                    "type {} has been both preserved and registered",
+                    ty.id()
+                ));
+            }
+
+            archetypes.push(SerializableArchetype {
+                tags: tags.clone(),
+                components: components.clone(),
+                opaque: Some(opaque),
+                chunks: vec![chunk],
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Serialize for SerializableArchetype<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tag_ids = self.tags.iter().map(|r| r.id).collect::<Vec<_>>();
+        let mut component_ids = self.components.iter().map(|r| r.id).collect::<Vec<_>>();
+        if let Some(opaque) = self.opaque {
+            tag_ids.extend(opaque.tags().iter().map(|(ty, _)| ty.id()));
+            component_ids.extend(opaque.components().iter().map(|ty| ty.id()));
+        }
+
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|chunk| SerializableChunk {
+                chunk,
+                archetype: self,
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = serializer.serialize_struct("Archetype", 3)?;
+        state.serialize_field("tags", &tag_ids)?;
+        state.serialize_field("components", &component_ids)?;
+        state.serialize_field("chunks", &chunks)?;
+        state.end()
+    }
+}
+
+struct SerializableChunk<'a> {
+    chunk: &'a Chunk,
+    archetype: &'a SerializableArchetype<'a>,
+}
+
+impl<'a> Serialize for SerializableChunk<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tags = self
+            .archetype
+            .tags
+            .iter()
+            .map(|r| (r.serialize_fn)(self.chunk))
+            .collect::<Vec<_>>();
+        let mut components = self
+            .archetype
+            .components
+            .iter()
+            .map(|r| (r.serialize_fn)(self.chunk))
+            .collect::<Vec<_>>();
+        if let Some(opaque) = self.archetype.opaque {
+            tags.extend(
+                opaque
+                    .tags()
+                    .iter()
+                    .map(|(_, value)| value as &dyn erased_serde::Serialize),
+            );
+            for index in 0..opaque.components().len() {
+                let values = self.chunk.components::<OpaqueComponents>().unwrap();
+                components.push(Box::new(OpaqueColumn(values, index)));
+            }
+        }
+
+        let mut state = serializer.serialize_struct("Chunk", 3)?;
+        state.serialize_field("tags", &tags)?;
+        state.serialize_field("entities", unsafe { self.chunk.entities() })?;
+        state.serialize_field("components", &components)?;
+        state.end()
+    }
+}
+
+/// The column of a chunk's preserved values for one unknown component type.
+struct OpaqueColumn<'a>(BorrowedSlice<'a, OpaqueComponents>, usize);
+
+impl<'a> Serialize for OpaqueColumn<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for components in self.0.iter() {
+            let value = components
+                .values()
+                .get(self.1)
+                .ok_or_else(|| ser::Error::custom("entity is missing opaque component values"))?;
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+/// Reads the fields of a struct in order, from either a sequence or a map.
+pub(crate) trait Fields<'de> {
+    type Error: de::Error;
+
+    fn next<T: DeserializeSeed<'de>>(
+        &mut self,
+        name: &'static str,
+        seed: T,
+    ) -> Result<T::Value, Self::Error>;
+
+    /// Reads the next field if it is present.
+    ///
+    /// Sequences do not name their elements, so only trailing fields may be omitted from them.
+    fn next_optional<T: DeserializeSeed<'de>>(
+        &mut self,
+        name: &'static str,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error>;
+}
+
+pub(crate) struct SeqFields<A>(pub(crate) A);
+
+impl<'de, A: SeqAccess<'de>> Fields<'de> for SeqFields<A> {
+    type Error = A::Error;
+
+    fn next<T: DeserializeSeed<'de>>(
+        &mut self,
+        name: &'static str,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        self.0
+            .next_element_seed(seed)?
+            .ok_or_else(|| de::Error::missing_field(name))
+    }
+
+    fn next_optional<T: DeserializeSeed<'de>>(
+        &mut self,
+        _: &'static str,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.0.next_element_seed(seed)
+    }
+}
+
+pub(crate) struct MapFields<A> {
+    map: A,
+    /// A key which has been read by `next_optional` but did not belong to its field.
+    pending: Option<String>,
+}
+
+impl<A> MapFields<A> {
+    pub(crate) fn new(map: A) -> Self {
+        MapFields { map, pending: None }
+    }
+}
+
+impl<'de, A: MapAccess<'de>> MapFields<A> {
+    fn next_key(&mut self) -> Result<Option<String>, A::Error> {
+        match self.pending.take() {
+            Some(key) => Ok(Some(key)),
+            None => Ok(self.map.next_key::<FieldName>()?.map(|key| key.0)),
+        }
+    }
+}
+
+impl<'de, A: MapAccess<'de>> Fields<'de> for MapFields<A> {
+    type Error = A::Error;
+
+    fn next<T: DeserializeSeed<'de>>(
+        &mut self,
+        name: &'static str,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.next_key()? {
+            Some(ref key) if key == name => self.map.next_value_seed(seed),
+            Some(key) => Err(de::Error::custom(format!(
+                "expected field `{}`, found `{}`",
+                name, key
+            ))),
+            None => Err(de::Error::missing_field(name)),
+        }
+    }
+
+    fn next_optional<T: DeserializeSeed<'de>>(
+        &mut self,
+        name: &'static str,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.next_key()? {
+            Some(ref key) if key == name => self.map.next_value_seed(seed).map(Some),
+            key => {
+                self.pending = key;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// The name of a struct field, deserialized as an identifier.
+pub(crate) struct FieldName(pub(crate) String);
+
+impl<'de> Deserialize<'de> for FieldName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(FieldNameVisitor)
+    }
+}
+
+struct FieldNameVisitor;
+
+impl<'de> Visitor<'de> for FieldNameVisitor {
+    type Value = FieldName;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a field name")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<FieldName, E> {
+        Ok(FieldName(value.to_owned()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<FieldName, E> {
+        String::from_utf8(value.to_vec())
+            .map(FieldName)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Bytes(value), &self))
+    }
+}
+
+/// Deserializes a `World` within a `Universe`, using the types registered in a
+/// `SerializeRegistry`.
+///
+/// `World::deserialize` is a convenience wrapper around this `DeserializeSeed`.
+pub struct WorldDeserializer<'a> {
+    universe: &'a Universe,
+    registry: &'a SerializeRegistry,
+}
+
+impl<'a> WorldDeserializer<'a> {
+    /// Constructs a new `WorldDeserializer`.
+    pub fn new(universe: &'a Universe, registry: &'a SerializeRegistry) -> Self {
+        WorldDeserializer { universe, registry }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for WorldDeserializer<'a> {
+    type Value = World;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<World, D::Error> {
+        let (mut world, entities) = WorldSeed {
+            world: self.universe.create_world(),
+            registry: self.registry,
+        }
+        .deserialize(deserializer)?;
+
+        if !world.allocator.restore_ids(entities.into_values()) {
+            return Err(de::Error::custom(
+                "entity IDs have already been allocated in the target universe",
+            ));
+        }
+
+        world.relocate_all();
+        Ok(world)
+    }
+}
+
+/// Deserializes a `World` into an existing world, allocating new IDs for each entity and
+/// rewriting the `Entity` references of the component types registered in an `EntityMappers`.
+///
+/// Produces a map from each entity's serialized ID to its new ID.
+///
+/// `World::deserialize_into` is a convenience wrapper around this `DeserializeSeed`.
+pub struct WorldMergeDeserializer<'a> {
+    world: &'a mut World,
+    registry: &'a SerializeRegistry,
+    mappers: &'a EntityMappers,
+}
+
+impl<'a> WorldMergeDeserializer<'a> {
+    /// Constructs a new `WorldMergeDeserializer`.
+    pub fn new(
+        world: &'a mut World,
+        registry: &'a SerializeRegistry,
+        mappers: &'a EntityMappers,
+    ) -> Self {
+        WorldMergeDeserializer {
+            world,
+            registry,
+            mappers,
+        }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for WorldMergeDeserializer<'a> {
+    type Value = EntityMap;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<EntityMap, D::Error> {
+        let (loaded, _) = WorldSeed {
+            world: self.world.create_sibling(),
+            registry: self.registry,
+        }
+        .deserialize(deserializer)?;
+
+        Ok(self.world.merge_foreign(loaded, self.mappers))
+    }
+}
+
+/// Deserializes the archetypes of a world into an empty `World`, without allocating the IDs of
+/// the entities which are read.
+///
+/// Produces the world along with the IDs of the entities which were read.
+struct WorldSeed<'a> {
+    world: World,
+    registry: &'a SerializeRegistry,
+}
+
+impl<'a> WorldSeed<'a> {
+    fn visit_fields<'de, F: Fields<'de>>(
+        mut self,
+        mut fields: F,
+    ) -> Result<(World, FnvHashMap<EntityIndex, Entity>), F::Error> {
+        // worlds saved before types were versioned have no schema, and so every type in them is
+        // at version 0
+        let schema = fields
+            .next_optional("schema", PhantomData::<SchemaTable>)?
+            .unwrap_or_default();
+        let versions = SchemaVersions {
+            components: schema.components.into_iter().collect(),
+            tags: schema.tags.into_iter().collect(),
+        };
+
+        let mut entities = FnvHashMap::default();
+        fields.next(
+            "archetypes",
+            ArchetypesSeed {
+                world: &mut self.world,
+                registry: self.registry,
+                versions: &versions,
+                entities: &mut entities,
+            },
+        )?;
+
+        Ok((self.world, entities))
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for WorldSeed<'a> {
+    type Value = (World, FnvHashMap<EntityIndex, Entity>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("World", &["schema", "archetypes"], self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for WorldSeed<'a> {
+    type Value = (World, FnvHashMap<EntityIndex, Entity>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a world")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.visit_fields(SeqFields(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.visit_fields(MapFields::new(map))
+    }
+}
+
+/// The schema versions which each type in a world was serialized with.
+///
+/// Types which are missing from the schema are assumed to be at version 0.
+struct SchemaVersions {
+    components: FnvHashMap<StableTypeId, u32>,
+    tags: FnvHashMap<StableTypeId, u32>,
+}
+
+/// How the values of one type in a serialized archetype are to be deserialized.
+enum TypeEntry<'a, R> {
+    /// The type is registered, and its values are upgraded by the given upgrades.
+    Registered(&'a R, &'a [Upgrade]),
+    /// The type is unknown, and its values are skipped.
+    Dropped,
+    /// The type is unknown, and its values are kept as `OpaqueValue`s.
+    Preserved(OpaqueType),
+}
+
+impl<'a, R> TypeEntry<'a, R> {
+    fn is_registered(&self) -> bool {
+        matches!(self, TypeEntry::Registered(..))
+    }
+
+    fn preserved(&self) -> Option<OpaqueType> {
+        match self {
+            TypeEntry::Preserved(ty) => Some(*ty),
+            _ => None,
+        }
+    }
+}
+
+struct ArchetypesSeed<'a> {
+    world: &'a mut World,
+    registry: &'a SerializeRegistry,
+    versions: &'a SchemaVersions,
+    entities: &'a mut FnvHashMap<EntityIndex, Entity>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ArchetypesSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ArchetypesSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of archetypes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while seq
+            .next_element_seed(ArchetypeSeed {
+                world: &mut *self.world,
+                registry: self.registry,
+                versions: self.versions,
+                entities: &mut *self.entities,
+            })?
+            .is_some()
+        {}
+
+        Ok(())
+    }
+}
+
+struct ArchetypeSeed<'a> {
+    world: &'a mut World,
+    registry: &'a SerializeRegistry,
+    versions: &'a SchemaVersions,
+    entities: &'a mut FnvHashMap<EntityIndex, Entity>,
+}
+
+impl<'a> ArchetypeSeed<'a> {
+    /// Determines how the values of a type are to be deserialized, given its registration, the
+    /// version it was serialized with and the registry's policy for unknown types.
+    fn entry<R, E: de::Error>(
+        &self,
+        kind: &str,
+        id: StableTypeId,
+        version: u32,
+        registration: Option<(&'a R, &'a [Upgrade])>,
+    ) -> Result<TypeEntry<'a, R>, E> {
+        if let Some((registration, upgrades)) = registration {
+            return match upgrades_from(upgrades, version) {
+                Some(upgrades) => Ok(TypeEntry::Registered(registration, upgrades)),
+                None => Err(de::Error::custom(format!(
+                    "{} type {} was serialized with version {}, but the latest version is {}",
+                    kind,
+                    id,
+                    version,
+                    upgrades.len()
+                ))),
+            };
+        }
+
+        match self.registry.unknown_types {
+            UnknownTypePolicy::Error => {
+                Err(de::Error::custom(format!("unknown {} type {}", kind, id)))
+            }
+            UnknownTypePolicy::Drop => {
+                warn!(self.world.logger, "dropping values of unknown type";
+                    "kind" => kind, "id" => %id);
+                Ok(TypeEntry::Dropped)
+            }
+            UnknownTypePolicy::Preserve => Ok(TypeEntry::Preserved(OpaqueType::new(id, version))),
+        }
+    }
+
+    fn visit_fields<'de, F: Fields<'de>>(self, mut fields: F) -> Result<(), F::Error> {
+        let tag_ids = fields.next("tags", PhantomData::<Vec<StableTypeId>>)?;
+        let component_ids = fields.next("components", PhantomData::<Vec<StableTypeId>>)?;
+
+        let mut tag_types = FnvHashSet::default();
+        let mut tags = Vec::with_capacity(tag_ids.len());
+        for id in tag_ids {
+            let version = self.versions.tags.get(&id).cloned().unwrap_or(0);
+            let registration = self.registry.tag(id).map(|(ty, r)| {
+                tag_types.insert(ty);
+                (r, r.upgrades.as_slice())
+            });
+            tags.push(self.entry("tag", id, version, registration)?);
+        }
+
+        let mut component_types = FnvHashSet::default();
+        let mut components = Vec::with_capacity(component_ids.len());
+        for id in component_ids {
+            let version = self.versions.components.get(&id).cloned().unwrap_or(0);
+            let registration = self.registry.component(id).map(|r| {
+                component_types.insert(r.ty);
+                (r, r.upgrades.as_slice())
+            });
+            components.push(self.entry("component", id, version, registration)?);
+        }
+
+        if tag_types.len() != tags.iter().filter(|e| e.is_registered()).count()
+            || component_types.len() != components.iter().filter(|e| e.is_registered()).count()
+        {
+            return Err(de::Error::custom("archetype contains duplicate types"));
+        }
+
+        let preserved_components = components
+            .iter()
+            .filter_map(TypeEntry::preserved)
+            .collect::<Vec<_>>();
+        let preserves =
+            !preserved_components.is_empty() || tags.iter().any(|e| e.preserved().is_some());
+        if !preserved_components.is_empty() {
+            component_types.insert(<OpaqueComponents as Component>::type_id());
+        }
+        if preserves {
+            tag_types.insert(<OpaqueTypes as Tag>::type_id());
+        }
+
+        let archetype_index = self
+            .world
+            .find_or_create_archetype(component_types, tag_types);
+
+        fields.next(
+            "chunks",
+            ChunksSeed {
+                world: self.world,
+                archetype_index,
+                tags: &tags,
+                components: &components,
+                preserved_components: if preserves {
+                    Some(&preserved_components)
+                } else {
+                    None
+                },
+                entities: self.entities,
+            },
+        )
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ArchetypeSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_struct("Archetype", &["tags", "components", "chunks"], self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ArchetypeSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an archetype")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        self.visit_fields(SeqFields(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        self.visit_fields(MapFields::new(map))
+    }
+}
+
+type TagEntry<'a> = TypeEntry<'a, TagRegistration>;
+type ComponentEntry<'a> = TypeEntry<'a, ComponentRegistration>;
+
+struct ChunksSeed<'a> {
+    world: &'a mut World,
+    archetype_index: ArchetypeIndex,
+    tags: &'a [TagEntry<'a>],
+    components: &'a [ComponentEntry<'a>],
+    /// The preserved component types, if the archetype preserves any unknown types.
+    preserved_components: Option<&'a [OpaqueType]>,
+    entities: &'a mut FnvHashMap<EntityIndex, Entity>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ChunksSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ChunksSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of chunks")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while seq
+            .next_element_seed(ChunkSeed {
+                world: &mut *self.world,
+                archetype_index: self.archetype_index,
+                tags: self.tags,
+                components: self.components,
+                preserved_components: self.preserved_components,
+                entities: &mut *self.entities,
+            })?
+            .is_some()
+        {}
+
+        Ok(())
+    }
+}
+
+struct ChunkSeed<'a> {
+    world: &'a mut World,
+    archetype_index: ArchetypeIndex,
+    tags: &'a [TagEntry<'a>],
+    components: &'a [ComponentEntry<'a>],
+    preserved_components: Option<&'a [OpaqueType]>,
+    entities: &'a mut FnvHashMap<EntityIndex, Entity>,
+}
+
+impl<'a> ChunkSeed<'a> {
+    fn build_chunk(&mut self, tags: &DynamicTagSet) -> Chunk {
+        let mut builder = ChunkBuilder::new();
+        builder.set_pool(self.world.chunk_pool.clone());
+        tags.configure_chunk(&mut builder);
+        for entry in self.components {
+            if let TypeEntry::Registered(registration, _) = entry {
+                builder.register_component_raw(
+                    registration.ty,
+                    registration.size,
+                    registration.drop_fn,
+                );
+            }
+        }
+        if let Some(preserved) = self.preserved_components {
+            if !preserved.is_empty() {
+                builder.register_component::<OpaqueComponents>();
+            }
+        }
+
+        let archetype = &mut self.world.archetypes[self.archetype_index as usize];
+        builder.build(archetype.allocate_chunk_id())
+    }
+
+    fn visit_fields<'de, F: Fields<'de>>(mut self, mut fields: F) -> Result<(), F::Error> {
+        let (mut tags, preserved_tags) = fields.next("tags", TagsSeed { entries: self.tags })?;
+        if let Some(preserved) = self.preserved_components {
+            tags.set_tag(OpaqueTypes::new(preserved.to_vec(), preserved_tags));
+        }
+
+        let entities = fields.next("entities", PhantomData::<Vec<Entity>>)?;
+        for entity in entities.iter() {
+            // entity IDs which differ only in their version would still collide when allocated
+            if self.entities.insert(entity.index, *entity).is_some() {
+                return Err(de::Error::custom(format!("duplicate entity {}", entity)));
+            }
+        }
+
+        // the layout of the chunk may differ from that of the chunk which was serialized, so the
+        // entities are split across as many chunks as are needed to hold them
+        let mut chunks = vec![self.build_chunk(&tags)];
+        let capacity = chunks[0].capacity();
+        while chunks.len() * capacity < entities.len() {
+            chunks.push(self.build_chunk(&tags));
+        }
+
+        let preserved = fields.next(
+            "components",
+            ColumnsSeed {
+                chunks: &chunks,
+                entries: self.components,
+                len: entities.len(),
+            },
+        )?;
+
+        if !preserved.is_empty() {
+            for (i, chunk) in chunks.iter().enumerate() {
+                let ptr = unsafe { chunk.components_mut_raw::<OpaqueComponents>() }.unwrap();
+                let start = i * capacity;
+                let end = std::cmp::min(start + capacity, entities.len());
+                for index in start..end {
+                    let values = preserved.iter().map(|column| column[index].clone());
+                    let components = OpaqueComponents::new(values.collect());
+                    unsafe { ptr.as_ptr().add(index - start).write(components) };
+                }
+            }
+        }
+
+        // all columns have been initialized, so the chunks can now take ownership of them
+        let archetype = &mut self.world.archetypes[self.archetype_index as usize];
+        for (mut chunk, entities) in chunks.into_iter().zip(entities.chunks(capacity)) {
+            tags.clone().write(&mut chunk);
+            unsafe { chunk.entities_unchecked().extend(entities) };
+            archetype.push_chunk(chunk);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ChunkSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_struct("Chunk", &["tags", "entities", "components"], self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ChunkSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a chunk")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        self.visit_fields(SeqFields(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        self.visit_fields(MapFields::new(map))
+    }
+}
+
+/// Deserializes the tag values of a chunk, producing the registered tags along with the values of
+/// any preserved unknown tag types.
+struct TagsSeed<'a> {
+    entries: &'a [TagEntry<'a>],
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for TagsSeed<'a> {
+    type Value = (DynamicTagSet, Vec<(OpaqueType, OpaqueValue)>);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for TagsSeed<'a> {
+    type Value = (DynamicTagSet, Vec<(OpaqueType, OpaqueValue)>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of {} tag values", self.entries.len())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut tags = DynamicTagSet::default();
+        let mut preserved = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let found = match entry {
+                TypeEntry::Registered(registration, upgrades) => seq
+                    .next_element_seed(TagSeed {
+                        registration,
+                        tags: &mut tags,
+                        upgrades,
+                    })?
+                    .is_some(),
+                TypeEntry::Dropped => seq.next_element::<de::IgnoredAny>()?.is_some(),
+                TypeEntry::Preserved(ty) => match seq.next_element::<OpaqueValue>()? {
+                    Some(value) => {
+                        preserved.push((*ty, value));
+                        true
+                    }
+                    None => false,
+                },
+            };
+
+            if !found {
+                return Err(de::Error::invalid_length(i, &self));
+            }
+        }
+
+        Ok((tags, preserved))
+    }
+}
+
+pub(crate) struct TagSeed<'a> {
+    pub(crate) registration: &'a TagRegistration,
+    pub(crate) tags: &'a mut DynamicTagSet,
+    pub(crate) upgrades: &'a [Upgrade],
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for TagSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.registration.deserialize_fn)(&mut deserializer, self.tags, self.upgrades)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Deserializes the component columns of a chunk into `chunks`, producing the columns of any
+/// preserved unknown component types.
+struct ColumnsSeed<'a> {
+    chunks: &'a [Chunk],
+    entries: &'a [ComponentEntry<'a>],
+    len: usize,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ColumnsSeed<'a> {
+    type Value = Vec<Vec<OpaqueValue>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut written = Vec::new();
+        let result = deserializer.deserialize_seq(ColumnsVisitor {
+            seed: &self,
+            written: &mut written,
+        });
+
+        // drop the columns which were fully written before the error
+        if result.is_err() {
+            let capacity = self.chunks[0].capacity();
+            for registration in written {
+                if let Some(drop_fn) = registration.drop_fn {
+                    for i in 0..self.len {
+                        unsafe {
+                            let component = self.chunks[i / capacity]
+                                .entity_component_mut_raw(&registration.ty, i % capacity)
+                                .unwrap();
+                            drop_fn(component.as_ptr());
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+struct ColumnsVisitor<'a, 'b> {
+    seed: &'b ColumnsSeed<'a>,
+    written: &'b mut Vec<&'a ComponentRegistration>,
+}
+
+impl<'de, 'a, 'b> Visitor<'de> for ColumnsVisitor<'a, 'b> {
+    type Value = Vec<Vec<OpaqueValue>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a sequence of {} component columns",
+            self.seed.entries.len()
+        )
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut preserved = Vec::new();
+        for (i, entry) in self.seed.entries.iter().enumerate() {
+            let found = match entry {
+                TypeEntry::Registered(registration, upgrades) => {
+                    let found = seq
+                        .next_element_seed(ColumnSeed {
+                            registration,
+                            chunks: self.seed.chunks,
+                            len: self.seed.len,
+                            upgrades,
+                        })?
+                        .is_some();
+                    if found {
+                        self.written.push(registration);
+                    }
+                    found
+                }
+                TypeEntry::Dropped => seq.next_element::<de::IgnoredAny>()?.is_some(),
+                TypeEntry::Preserved(_) => match seq.next_element::<Vec<OpaqueValue>>()? {
+                    Some(column) if column.len() != self.seed.len => {
+                        return Err(de::Error::invalid_length(
+                            column.len(),
+                            &format!("a sequence of {} components", self.seed.len).as_str(),
+                        ));
+                    }
+                    Some(column) => {
+                        preserved.push(column);
+                        true
+                    }
+                    None => false,
+                },
+            };
+
+            if !found {
+                return Err(de::Error::invalid_length(i, &self));
+            }
+        }
+
+        Ok(preserved)
+    }
+}
+
+struct ColumnSeed<'a> {
+    registration: &'a ComponentRegistration,
+    chunks: &'a [Chunk],
+    len: usize,
+    upgrades: &'a [Upgrade],
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ColumnSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.registration.deserialize_fn)(&mut deserializer, self.chunks, self.len, self.upgrades)
+            .map_err(de::Error::custom)
+    }
+}
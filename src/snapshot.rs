@@ -0,0 +1,296 @@
+//! Cheap snapshots of `World` state, for rollback.
+//!
+//! `World::snapshot` captures the entities, components and tags of a world, along with the state
+//! of its entity allocator. `World::restore` later rewinds the world to exactly that state, such
+//! that entities are found at the same locations and the same `Entity` IDs are allocated again
+//! afterwards.
+//!
+//! Snapshots share the copies of chunks which have not been changed. Each world remembers the
+//! copy it last made of each of its chunks, along with the chunk's version numbers at the time.
+//! When a chunk's versions have not changed by the next snapshot, that copy is shared rather than
+//! cloned again. Likewise, restoring only replaces the chunks which have changed since the copy
+//! in the snapshot was made. The cost of both therefore scales with the number of chunks which
+//! have been accessed mutably, or had entities added or removed, rather than with the size of the
+//! world. The copies are only remembered for as long as a snapshot which contains them is alive.
+//! The entity allocator's blocks of IDs are shared in the same way, and are copied when an ID
+//! within them is next allocated, deleted or moved.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::storage::CloneRegistry;
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! struct Position(f32);
+//!
+//! let mut registry = CloneRegistry::default();
+//! registry.register::<Position>();
+//!
+//! let universe = Universe::new(None);
+//! let mut world = universe.create_world();
+//! let entity = world.insert_from((), vec![(Position(0.),)])[0];
+//!
+//! let snapshot = world.snapshot(&registry).unwrap();
+//!
+//! *world.component_mut::<Position>(entity).unwrap() = Position(1.);
+//! let created = world.insert_from((), vec![(Position(2.),)])[0];
+//!
+//! world.restore(&snapshot).unwrap();
+//! assert_eq!(Position(0.), *world.component::<Position>(entity).unwrap());
+//! assert!(!world.is_alive(&created));
+//! ```
+
+use std::sync::Weak;
+
+use crate::storage::{Archetype, Chunk, ChunkVersions, CloneRegistry};
+use crate::*;
+
+/// An error returned when taking or restoring a `Snapshot`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotError {
+    /// A component type has no clone function registered in the `CloneRegistry`.
+    UnregisteredComponent(ComponentTypeId),
+    /// The snapshot was taken of a different world.
+    WrongWorld,
+    /// Entity IDs which were owned by the world when the snapshot was taken have since been
+    /// allocated to another world.
+    EntityIdsInUse,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SnapshotError::UnregisteredComponent(ty) => {
+                write!(
+                    f,
+                    "component type {:?} has no registered clone function",
+                    ty
+                )
+            }
+            SnapshotError::WrongWorld => write!(f, "the snapshot was taken of a different world"),
+            SnapshotError::EntityIdsInUse => write!(
+                f,
+                "the snapshot's entity IDs have been allocated to another world"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// The copy most recently made of each chunk in a world, keyed by chunk ID, along with the
+/// versions of the chunk which it matches.
+pub(crate) type SnapshotCache = FnvHashMap<ChunkId, (ChunkVersions, Weak<Chunk>)>;
+
+/// The saved state of a `World`.
+///
+/// Constructed by `World::snapshot`, and restored with `World::restore`. Snapshots can only be
+/// restored into the world from which they were taken.
+pub struct Snapshot {
+    world: WorldId,
+    allocator: EntityAllocatorState,
+    archetypes: Vec<ArchetypeSnapshot>,
+    registry: CloneRegistry,
+}
+
+impl Snapshot {
+    /// Gets the number of entities in the snapshot.
+    pub fn len(&self) -> usize {
+        self.archetypes
+            .iter()
+            .flat_map(|a| a.chunks.iter())
+            .map(|c| c.len())
+            .sum()
+    }
+
+    /// Determines if the snapshot contains no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+struct ArchetypeSnapshot {
+    id: ArchetypeId,
+    components: FnvHashSet<ComponentTypeId>,
+    tags: FnvHashSet<TagTypeId>,
+    next_chunk_id: u64,
+    chunks: Vec<Arc<Chunk>>,
+}
+
+impl World {
+    /// Saves the current state of the world.
+    ///
+    /// Chunks which have not changed since they were copied into a previous snapshot which is
+    /// still alive are shared with it, rather than cloned. All component types in the world must
+    /// have a clone function registered in `registry`.
+    pub fn snapshot(&mut self, registry: &CloneRegistry) -> Result<Snapshot, SnapshotError> {
+        if let Some(ty) = self
+            .archetypes
+            .iter()
+            .flat_map(|a| a.components.iter())
+            .find(|ty| !registry.is_registered(ty))
+        {
+            return Err(SnapshotError::UnregisteredComponent(*ty));
+        }
+
+        let mut cache = SnapshotCache::default();
+        let mut cloned = 0;
+        let archetypes = self
+            .archetypes
+            .iter()
+            .map(|archetype| {
+                let chunks = archetype
+                    .chunks()
+                    .iter()
+                    .map(|chunk| {
+                        let versions = chunk.versions();
+                        let copy = self
+                            .snapshot_cache
+                            .get(&chunk.id())
+                            .filter(|(v, _)| *v == versions)
+                            .and_then(|(_, copy)| copy.upgrade())
+                            .unwrap_or_else(|| {
+                                cloned += 1;
+                                Arc::new(
+                                    chunk
+                                        .try_clone(chunk.builder(), chunk.id(), registry)
+                                        .unwrap(),
+                                )
+                            });
+                        cache.insert(chunk.id(), (versions, Arc::downgrade(&copy)));
+                        copy
+                    })
+                    .collect();
+
+                ArchetypeSnapshot {
+                    id: archetype.id(),
+                    components: archetype.components.clone(),
+                    tags: archetype.tags.clone(),
+                    next_chunk_id: archetype.next_chunk_id(),
+                    chunks,
+                }
+            })
+            .collect();
+
+        self.snapshot_cache = cache;
+        trace!(self.logger, "took snapshot"; "cloned_chunks" => cloned);
+
+        Ok(Snapshot {
+            world: self.id,
+            allocator: self.allocator.save(),
+            archetypes,
+            registry: registry.clone(),
+        })
+    }
+
+    /// Rewinds the world to the state saved in `snapshot`.
+    ///
+    /// Only chunks which have changed since their copy in the snapshot was made are replaced.
+    /// `Entity` IDs which have been allocated since the snapshot was taken are no longer alive,
+    /// and those which have been deleted are alive again.
+    ///
+    /// The world is left unchanged if an error is returned.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        if snapshot.world != self.id {
+            return Err(SnapshotError::WrongWorld);
+        }
+
+        // the entities which have each tracked component type, to find those which lose it
+        let tracked = self
+            .removed
+            .keys()
+            .map(|ty| {
+                let entities = self
+                    .archetypes
+                    .iter()
+                    .filter(|a| a.components.contains(ty))
+                    .flat_map(|a| a.chunks())
+                    .flat_map(|c| unsafe { c.entities() }.iter().copied())
+                    .collect::<Vec<_>>();
+                (*ty, entities)
+            })
+            .collect::<Vec<_>>();
+
+        if !self.allocator.restore(&snapshot.allocator) {
+            return Err(SnapshotError::EntityIdsInUse);
+        }
+
+        let mut archetypes = std::mem::take(&mut self.archetypes)
+            .into_iter()
+            .map(|a| (a.id(), a))
+            .collect::<FnvHashMap<_, _>>();
+        let mut cache = SnapshotCache::default();
+        let mut cloned = 0;
+
+        for saved in snapshot.archetypes.iter() {
+            let mut archetype = archetypes.remove(&saved.id).unwrap_or_else(|| {
+                let logger = self.logger.new(o!("archetype_id" => saved.id.1));
+                let mut archetype = Archetype::new(
+                    saved.id,
+                    logger,
+                    saved.components.clone(),
+                    saved.tags.clone(),
+                );
+                archetype.set_chunk_pool(self.chunk_pool.clone());
+                archetype.set_change_tick(self.change_tick.clone());
+                archetype.set_buffers(&self.buffers);
+                archetype
+            });
+
+            let mut current = std::mem::take(&mut archetype.chunks)
+                .into_iter()
+                .map(|c| (c.id(), c))
+                .collect::<FnvHashMap<_, _>>();
+
+            let mut chunks = Vec::with_capacity(saved.chunks.len());
+            for copy in saved.chunks.iter() {
+                let unchanged = current.remove(&copy.id()).filter(|chunk| {
+                    matches!(
+                        self.snapshot_cache.get(&chunk.id()),
+                        Some((versions, cached))
+                            if *versions == chunk.versions() && cached.as_ptr() == Arc::as_ptr(copy)
+                    )
+                });
+
+                let chunk = match unchanged {
+                    Some(chunk) => chunk,
+                    None => {
+                        cloned += 1;
+                        copy.try_clone(copy.builder(), copy.id(), &snapshot.registry)
+                            .unwrap()
+                    }
+                };
+
+                cache.insert(chunk.id(), (chunk.versions(), Arc::downgrade(copy)));
+                chunks.push(chunk);
+            }
+
+            archetype.restore_chunks(chunks, saved.next_chunk_id);
+            self.archetypes.push(archetype);
+        }
+
+        self.snapshot_cache = cache;
+        self.defrag_progress = 0;
+
+        for (ty, entities) in tracked {
+            let removed = entities
+                .into_iter()
+                .filter(|entity| {
+                    !self.allocator.is_alive(entity)
+                        || !self
+                            .allocator
+                            .get_location(&entity.index)
+                            .map(|(archetype, _, _)| {
+                                self.archetypes[archetype as usize].components.contains(&ty)
+                            })
+                            .unwrap_or(false)
+                })
+                .collect::<Vec<_>>();
+            self.removed.get_mut(&ty).unwrap().extend(removed);
+        }
+
+        debug!(self.logger, "restored snapshot"; "cloned_chunks" => cloned);
+
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
+use crate::query::Prev;
 use crate::*;
-use downcast_rs::{impl_downcast, Downcast};
 use fnv::{FnvHashMap, FnvHashSet};
 use std::any::TypeId;
 use std::cell::UnsafeCell;
@@ -8,20 +8,6 @@ use std::mem::size_of;
 use std::ptr::NonNull;
 use std::sync::atomic::AtomicIsize;
 
-impl_downcast!(ComponentStorage);
-trait ComponentStorage: Downcast + Debug {
-    fn remove(&mut self, id: ComponentIndex);
-    fn fetch_remove(
-        &mut self,
-        id: ComponentIndex,
-    ) -> (
-        ComponentTypeId,
-        Box<dyn Fn(&mut ChunkBuilder)>,
-        Box<dyn ChunkInit>,
-    );
-    fn len(&self) -> usize;
-}
-
 #[derive(Debug)]
 struct StorageVec<T: Component> {
     version: UnsafeCell<Wrapping<usize>>,
@@ -36,9 +22,9 @@ impl<T: Component> StorageVec<T> {
         }
     }
 
-    /*fn version(&self) -> usize {
+    fn version(&self) -> usize {
         unsafe { (*self.version.get()).0 }
-    }*/
+    }
 
     unsafe fn data(&self) -> &Vec<T> {
         &(*self.data.get())
@@ -48,26 +34,6 @@ impl<T: Component> StorageVec<T> {
         *self.version.get() += Wrapping(1);
         &mut (*self.data.get())
     }
-}
-
-impl<T: Component> ComponentStorage for StorageVec<T> {
-    fn remove(&mut self, id: ComponentIndex) {
-        unsafe {
-            self.data_mut().swap_remove(id as usize);
-        }
-    }
-
-    fn fetch_remove(
-        &mut self,
-        id: ComponentIndex,
-    ) -> (
-        ComponentTypeId,
-        Box<dyn Fn(&mut ChunkBuilder)>,
-        Box<dyn ChunkInit>,
-    ) {
-        let component = unsafe { self.data_mut().swap_remove(id as usize) };
-        DynamicSingleEntitySource::component_tuple(component)
-    }
 
     fn len(&self) -> usize {
         unsafe { self.data().len() }
@@ -125,11 +91,91 @@ impl TagStorageVTable {
         }
     }
 }
+/// The version numbers of a chunk's entity list and component storage at some point in time.
+///
+/// If a chunk's versions are unchanged, then its contents have not been accessed mutably.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ChunkVersions {
+    pub(crate) entities: usize,
+    pub(crate) components: FnvHashMap<ComponentTypeId, usize>,
+}
+
+/// A registry of functions which clone component data, for component types which implement
+/// `Clone`.
+///
+/// Component types are only required to be `Send + Sync + Debug`, so chunks cannot clone their
+/// data without a clone function for each component type they contain.
+#[derive(Default, Clone)]
+pub struct CloneRegistry {
+    clone_fns: FnvHashMap<ComponentTypeId, fn(*const u8, *mut u8, usize)>,
+}
+
+impl CloneRegistry {
+    /// Registers a clone function for the component type `T`.
+    pub fn register<T: Component + Clone>(&mut self) -> &mut Self {
+        self.clone_fns.insert(T::type_id(), clone_components::<T>);
+        self
+    }
+
+    /// Registers a clone function for a component type.
+    ///
+    /// The function is given pointers to `count` contiguous source elements and to the
+    /// uninitialized memory they should be cloned into.
+    pub fn register_raw(&mut self, ty: ComponentTypeId, clone_fn: fn(*const u8, *mut u8, usize)) {
+        self.clone_fns.insert(ty, clone_fn);
+    }
+
+    /// Determines if a clone function has been registered for the given component type.
+    pub fn is_registered(&self, ty: &ComponentTypeId) -> bool {
+        self.clone_fns.contains_key(ty)
+    }
+}
+
+/// Clones `count` contiguous components of type `T` from `src` into the uninitialized memory at
+/// `dst`.
+fn clone_components<T: Clone>(src: *const u8, dst: *mut u8, count: usize) {
+    unsafe {
+        let src = src as *const T;
+        let dst = dst as *mut T;
+        for i in 0..count {
+            std::ptr::write(dst.add(i), <T as Clone>::clone(&*src.add(i)));
+        }
+    }
+}
+
+/// Describes a double-buffered component type, whose values as of the last call to
+/// `World::advance` are kept in a second column of type `Prev<T>`.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ComponentBuffer {
+    pub(crate) component: ComponentTypeId,
+    pub(crate) prev: ComponentTypeId,
+    size: usize,
+    drop_fn: Option<fn(*mut u8)>,
+    clone_fn: fn(*const u8, *mut u8, usize),
+}
+
+impl ComponentBuffer {
+    /// Describes the double-buffering of component type `T`.
+    pub(crate) fn of<T: Component + Clone>() -> Self {
+        ComponentBuffer {
+            component: T::type_id(),
+            prev: <Prev<T> as Component>::type_id(),
+            size: size_of::<T>(),
+            drop_fn: Some(|ptr| unsafe { std::ptr::drop_in_place::<T>(ptr as *mut T) }),
+            clone_fn: clone_components::<T>,
+        }
+    }
+}
+
 struct OwnedTag {
     info: Option<TagStorageInfo>,
     #[allow(unused)]
     storage: Vec<u8>,
 }
+// tag values are required to be `Send + Sync`
+unsafe impl Send for OwnedTag {}
+unsafe impl Sync for OwnedTag {}
+
 impl Drop for OwnedTag {
     fn drop(&mut self) {
         if let Some(info) = self.info {
@@ -208,25 +254,136 @@ impl TagStorageInfo {
     }
 }
 
-#[derive(Clone, Copy)]
-struct ComponentStorageInfo {
+/// A block of memory allocated for chunk data.
+///
+/// The block is released once the chunk and every column and view which shares it have been
+/// dropped. Tag values stored in the block are dropped along with it.
+#[derive(Debug)]
+struct ChunkAllocation {
+    ptr: NonNull<u8>,
+    layout: std::alloc::Layout,
+    pool: Option<Arc<ChunkPool>>,
+    tags: Vec<TagStorageInfo>,
+}
+
+// tag values are required to be `Send + Sync`
+unsafe impl Send for ChunkAllocation {}
+unsafe impl Sync for ChunkAllocation {}
+
+impl Drop for ChunkAllocation {
+    fn drop(&mut self) {
+        unsafe {
+            for storage in self.tags.iter() {
+                if let Some(drop_fn) = storage.vtable.drop_fn {
+                    drop_fn(storage.data().as_ptr());
+                }
+            }
+            match self.pool {
+                Some(ref pool) => pool.free(self.ptr, self.layout),
+                None => std::alloc::dealloc(self.ptr.as_ptr(), self.layout),
+            }
+        }
+    }
+}
+
+/// The memory holding the values of a component column.
+///
+/// A column is shared between a chunk and the `FrozenChunk`s taken from it. The values are
+/// dropped by whichever is the last to release the column.
+struct ColumnData {
     ptr: NonNull<u8>,
+    #[allow(unused)]
+    allocation: Arc<ChunkAllocation>,
+    // the number of values, and how to clone them, as of when the column was last shared
+    len: usize,
+    clone_fn: Option<fn(*const u8, *mut u8, usize)>,
+}
+
+// component values are required to be `Send + Sync`
+unsafe impl Send for ColumnData {}
+unsafe impl Sync for ColumnData {}
+
+impl ColumnData {
+    /// Releases a share of the column, dropping its first `len` values if it was the last one.
+    unsafe fn release(
+        column: Arc<ColumnData>,
+        len: usize,
+        component_size: usize,
+        drop_fn: Option<fn(*mut u8)>,
+    ) {
+        if let (Some(column), Some(drop_fn)) = (Arc::into_inner(column), drop_fn) {
+            for i in 0..len {
+                drop_fn(column.ptr.as_ptr().add(i * component_size));
+            }
+        }
+    }
+}
+
+/// The change ticks of the components in a column.
+///
+/// A component's tick is the world change tick at which it was last written. Writes to the whole
+/// column record a single tick, rather than updating the tick of every component.
+///
+/// Each component also records the tick at which it was added to its entity.
+#[derive(Clone, Debug)]
+struct ChangeTicks {
+    all: usize,
+    // the tick at which the column was last accessed mutably, which no component's tick exceeds
+    accessed: usize,
+    components: Vec<usize>,
+    // the tick at which a component was last added, which no component's added tick exceeds
+    last_added: usize,
+    added: Vec<usize>,
+}
+
+impl ChangeTicks {
+    fn new(capacity: usize) -> Self {
+        ChangeTicks {
+            all: 0,
+            accessed: 0,
+            components: vec![0; capacity],
+            last_added: 0,
+            added: vec![0; capacity],
+        }
+    }
+
+    fn get(&self, index: usize) -> usize {
+        std::cmp::max(self.all, self.components[index])
+    }
+
+    /// Copies the ticks of the component at `src` in `other` to the component at `dst`.
+    fn copy_from(&mut self, dst: usize, other: &ChangeTicks, src: usize) {
+        self.components[dst] = other.get(src);
+        self.added[dst] = other.added[src];
+        self.accessed = std::cmp::max(self.accessed, other.accessed);
+        self.last_added = std::cmp::max(self.last_added, other.last_added);
+    }
+}
+
+struct ComponentStorageInfo {
+    data: UnsafeCell<Arc<ColumnData>>,
+    ticks: UnsafeCell<ChangeTicks>,
+    capacity: usize,
     component_size: usize,
     drop_fn: Option<fn(*mut u8)>,
 }
 impl std::fmt::Debug for ComponentStorageInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("ComponentStorageInfo")
-            .field("ptr", &self.ptr)
+            .field("ptr", &self.ptr())
             .field("component_size", &self.component_size)
             .finish()
     }
 }
 
 impl ComponentStorageInfo {
+    fn ptr(&self) -> NonNull<u8> {
+        let data: &Arc<ColumnData> = unsafe { &*self.data.get() };
+        data.ptr
+    }
     pub unsafe fn header(&self) -> &mut ComponentStorageHeader {
         (align_down(
-            self.ptr.as_ptr() as usize - std::mem::size_of::<ComponentStorageHeader>(),
+            self.ptr().as_ptr() as usize - std::mem::size_of::<ComponentStorageHeader>(),
             COMPONENT_STORAGE_ALIGNMENT,
         ) as *mut ComponentStorageHeader)
             .as_mut()
@@ -234,25 +391,132 @@ impl ComponentStorageInfo {
     }
     pub unsafe fn element(&self, idx: usize) -> NonNull<u8> {
         NonNull::new_unchecked(
-            self.ptr
+            self.ptr()
                 .as_ptr()
                 .offset((idx * self.component_size) as isize),
         )
     }
     pub unsafe fn element_mut(&self, idx: usize) -> NonNull<u8> {
+        self.make_unique();
         self.header().version += Wrapping(1);
         NonNull::new_unchecked(
-            self.ptr
+            self.ptr()
                 .as_ptr()
                 .offset((idx * self.component_size) as isize),
         )
     }
     pub unsafe fn data(&self) -> NonNull<u8> {
-        self.ptr
+        self.ptr()
     }
     pub unsafe fn data_mut(&self) -> NonNull<u8> {
+        self.make_unique();
         self.header().version += Wrapping(1);
-        self.ptr
+        self.ptr()
+    }
+
+    /// Gets the change ticks of the column's components.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn ticks(&self) -> &mut ChangeTicks {
+        &mut *self.ticks.get()
+    }
+
+    /// Marks the component at `index` as changed at `tick`.
+    unsafe fn mark(&self, index: usize, tick: usize) {
+        let ticks = self.ticks();
+        ticks.components[index] = tick;
+        ticks.accessed = tick;
+    }
+
+    /// Marks `count` components from `start` as changed at `tick`.
+    unsafe fn mark_range(&self, start: usize, count: usize, tick: usize) {
+        let ticks = self.ticks();
+        for component in ticks.components[start..(start + count)].iter_mut() {
+            *component = tick;
+        }
+        ticks.accessed = tick;
+    }
+
+    /// Marks all of the column's components as changed at `tick`.
+    unsafe fn mark_all(&self, tick: usize) {
+        let ticks = self.ticks();
+        ticks.all = tick;
+        ticks.accessed = tick;
+    }
+
+    /// Marks `count` components from `start` as added at `tick`.
+    unsafe fn mark_added(&self, start: usize, count: usize, tick: usize) {
+        let ticks = self.ticks();
+        for component in ticks.added[start..(start + count)].iter_mut() {
+            *component = tick;
+        }
+        ticks.last_added = tick;
+    }
+
+    /// Marks all of the column's components as both added and changed at `tick`, discarding any
+    /// later ticks.
+    unsafe fn reset_ticks(&self, tick: usize) {
+        let ticks = self.ticks();
+        for component in ticks.components.iter_mut().chain(ticks.added.iter_mut()) {
+            *component = tick;
+        }
+        ticks.all = tick;
+        ticks.accessed = tick;
+        ticks.last_added = tick;
+    }
+
+    /// Shares the column with a view, recording the number of values it holds and how to
+    /// clone them.
+    ///
+    /// # Safety
+    ///
+    /// No other code may be accessing the column.
+    unsafe fn share(&self, len: usize, clone_fn: fn(*const u8, *mut u8, usize)) -> Arc<ColumnData> {
+        let data = &mut *self.data.get();
+        // a column which is already shared has not been changed since
+        if let Some(unique) = Arc::get_mut(data) {
+            unique.len = len;
+            unique.clone_fn = Some(clone_fn);
+        }
+        data.clone()
+    }
+
+    /// Copies the column into new memory if it is shared with a view.
+    unsafe fn make_unique(&self) {
+        let data = &mut *self.data.get();
+        if Arc::get_mut(data).is_some() {
+            return;
+        }
+
+        let offset = align_up(
+            std::mem::size_of::<ComponentStorageHeader>(),
+            COMPONENT_STORAGE_ALIGNMENT,
+        );
+        let layout = std::alloc::Layout::from_size_align(
+            offset + self.component_size * self.capacity,
+            COMPONENT_STORAGE_ALIGNMENT,
+        )
+        .expect("invalid component data size/alignment");
+        let block = NonNull::new(std::alloc::alloc(layout))
+            .unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+        let ptr = NonNull::new_unchecked(block.as_ptr().add(offset));
+        *(block.as_ptr() as *mut ComponentStorageHeader) = ComponentStorageHeader {
+            version: self.header().version,
+        };
+
+        let clone_fn = data.clone_fn.expect("shared column has no clone function");
+        clone_fn(data.ptr.as_ptr(), ptr.as_ptr(), data.len);
+
+        *data = Arc::new(ColumnData {
+            ptr,
+            allocation: Arc::new(ChunkAllocation {
+                ptr: block,
+                layout,
+                pool: None,
+                tags: Vec::new(),
+            }),
+            len: 0,
+            clone_fn: None,
+        });
     }
 }
 /// Raw unsafe storage for components associated with entities.
@@ -268,34 +532,31 @@ pub struct Chunk {
     components: FnvHashMap<ComponentTypeId, ComponentStorageInfo>,
     tags: FnvHashMap<TagTypeId, TagStorageInfo>,
     borrows: FnvHashMap<ComponentTypeId, AtomicIsize>,
-    component_data_layout: std::alloc::Layout,
-    component_data: NonNull<u8>,
+    buffers: Vec<ComponentBuffer>,
+    #[allow(unused)]
+    allocation: Arc<ChunkAllocation>,
+    pool: Option<Arc<ChunkPool>>,
+    change_tick: Arc<AtomicUsize>,
 }
 
 impl Drop for Chunk {
     fn drop(&mut self) {
-        unsafe {
-            // drop all tags
-            for (_, storage) in self.tags.iter() {
-                if let Some(drop_fn) = storage.vtable.drop_fn {
-                    let data = storage.data();
-                    drop_fn(data.as_ptr());
-                }
-            }
-            // drop all components
-            for (_, storage) in self.components.iter() {
-                if let Some(drop_fn) = storage.drop_fn {
-                    let data = storage.data_mut();
-                    for i in 0..self.len() {
-                        drop_fn(data.as_ptr().offset((i * storage.component_size) as isize));
-                    }
-                }
+        // tags are dropped along with the chunk's allocation
+        let len = self.len();
+        for (_, storage) in self.components.drain() {
+            unsafe {
+                ColumnData::release(
+                    storage.data.into_inner(),
+                    len,
+                    storage.component_size,
+                    storage.drop_fn,
+                );
             }
-            std::alloc::dealloc(self.component_data.as_ptr(), self.component_data_layout);
         }
     }
 }
 
+unsafe impl Send for Chunk {}
 unsafe impl Sync for Chunk {}
 
 impl Chunk {
@@ -309,6 +570,11 @@ impl Chunk {
         self.entities.len()
     }
 
+    /// Gets the maximum number of entities the chunk can store.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     /// Determines if the chunk has reached capacity and can no longer accept more entities.
     pub fn is_full(&self) -> bool {
         self.len() == self.capacity
@@ -357,9 +623,11 @@ impl Chunk {
     /// This function bypasses any borrow checking. Ensure no other code is reading or writing to
     /// this component type in the chunk before calling this function.
     pub unsafe fn components_mut_unchecked<T: Component>(&self) -> Option<&mut [T]> {
-        self.components
-            .get(&T::type_id())
-            .map(|c| std::slice::from_raw_parts_mut(c.data_mut().cast().as_ptr(), self.len()))
+        self.components.get(&T::type_id()).map(|c| {
+            let data = c.data_mut();
+            c.mark_all(self.change_tick());
+            std::slice::from_raw_parts_mut(data.cast().as_ptr(), self.len())
+        })
     }
 
     /// Gets a mutable vector of all, possibly uninitialized, component data.
@@ -374,17 +642,51 @@ impl Chunk {
     /// This function ignores the number of entities allocated for the chunk and may return
     /// uninitialized data.
     pub unsafe fn components_mut_raw<T: Component>(&self) -> Option<NonNull<T>> {
-        self.components
-            .get(&T::type_id())
-            .map(|c| c.data_mut().cast())
+        self.components.get(&T::type_id()).map(|c| {
+            let data = c.data_mut();
+            c.mark_all(self.change_tick());
+            data.cast()
+        })
     }
 
+    /// Gets a pointer to the component data of the given type, starting from `offset`.
+    ///
+    /// The components from `offset` onwards are marked as changed, as the pointer may be used to
+    /// write any of them. Those before `offset` are left unmarked.
+    ///
+    /// # Safety
+    ///
+    /// This function bypasses any borrow checking. Ensure no other code is reading or writing to
+    /// this component type in the chunk before calling this function.
     pub unsafe fn components_mut_raw_untyped(
         &self,
         ty: &ComponentTypeId,
         offset: usize,
     ) -> Option<NonNull<u8>> {
-        self.components.get(ty).map(|c| c.element_mut(offset))
+        self.components.get(ty).map(|c| {
+            let element = c.element_mut(offset);
+            c.mark_range(offset, c.capacity - offset, self.change_tick());
+            element
+        })
+    }
+
+    /// Gets a pointer to the component of the given type of the entity at `index`, marking only
+    /// that component as changed.
+    ///
+    /// # Safety
+    ///
+    /// This function bypasses any borrow checking. Ensure no other code is reading or writing to
+    /// this component type in the chunk before calling this function.
+    pub(crate) unsafe fn entity_component_mut_raw(
+        &self,
+        ty: &ComponentTypeId,
+        index: usize,
+    ) -> Option<NonNull<u8>> {
+        self.components.get(ty).map(|c| {
+            let element = c.element_mut(index);
+            c.mark(index, self.change_tick());
+            element
+        })
     }
 
     /// Gets a slice of component data.
@@ -423,6 +725,56 @@ impl Chunk {
         }
     }
 
+    /// Gets an iterator of mutable references to component data, which only mark a component as
+    /// changed when they are dereferenced mutably.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    ///
+    /// # Panics
+    ///
+    /// This function performs runtime borrow checking. It will panic if other code is borrowing
+    /// the same component type.
+    pub fn components_tracked<'a, T: Component>(
+        &'a self,
+    ) -> Option<BorrowedIter<'a, TrackedIter<'a, T>>> {
+        self.components.get(&T::type_id()).map(|c| {
+            let borrow = self.borrow_mut::<T>();
+            unsafe {
+                let tick = self.change_tick();
+                let data = c.data_mut();
+                let values = std::slice::from_raw_parts_mut(data.cast().as_ptr(), self.len());
+                let ticks = c.ticks();
+                ticks.accessed = tick;
+                let iter = TrackedIter::new(values, &mut ticks.components[..self.len()], tick);
+                BorrowedIter::new(iter, borrow)
+            }
+        })
+    }
+
+    /// Gets the raw bytes of the component data of the given type, for all entities in the chunk.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    ///
+    /// # Panics
+    ///
+    /// This function performs runtime borrow checking. It will panic if other code is borrowing
+    /// the same component type mutably.
+    pub fn component_bytes<'a>(&'a self, ty: &ComponentTypeId) -> Option<BorrowedSlice<'a, u8>> {
+        self.components.get(ty).map(|storage| unsafe {
+            let state = self.borrows.get(ty).unwrap();
+            let bytes = std::slice::from_raw_parts(
+                storage.data().as_ptr() as *const u8,
+                storage.component_size * self.len(),
+            );
+            BorrowedSlice::new(bytes, Borrow::aquire_read(state).unwrap())
+        })
+    }
+
+    /// Determines if the chunk contains entity data components of type `T`.
+    pub fn has_component<T: Component>(&self) -> bool {
+        self.components.contains_key(&T::type_id())
+    }
+
     unsafe fn component_storage_header<T: Component>(&self) -> Option<&mut ComponentStorageHeader> {
         self.components.get(&T::type_id()).map(|c| c.header())
     }
@@ -435,6 +787,128 @@ impl Chunk {
         unsafe { self.component_storage_header::<T>().map(|s| s.version()) }
     }
 
+    /// Gets the change tick of the component of type `T` of the entity at `index`.
+    ///
+    /// The tick is the world change tick at which the component was last written, or at which
+    /// the entity was added to the world.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn component_tick<T: Component>(&self, index: usize) -> Option<usize> {
+        self.components
+            .get(&T::type_id())
+            .map(|c| unsafe { c.ticks().get(index) })
+    }
+
+    /// Gets the world change tick at which the components of type `T` in the chunk were last
+    /// accessed mutably.
+    ///
+    /// None of the components have changed since any later tick.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn component_access_tick<T: Component>(&self) -> Option<usize> {
+        self.components
+            .get(&T::type_id())
+            .map(|c| unsafe { c.ticks().accessed })
+    }
+
+    /// Gets the tick at which the component of type `T` of the entity at `index` was added.
+    ///
+    /// The tick is the world change tick at which the entity was inserted into the world, or at
+    /// which the component was added to the entity.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn component_added_tick<T: Component>(&self, index: usize) -> Option<usize> {
+        self.components
+            .get(&T::type_id())
+            .map(|c| unsafe { c.ticks().added[index] })
+    }
+
+    /// Gets the world change tick at which a component of type `T` was last added to an entity
+    /// in the chunk.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn component_last_added_tick<T: Component>(&self) -> Option<usize> {
+        self.components
+            .get(&T::type_id())
+            .map(|c| unsafe { c.ticks().last_added })
+    }
+
+    /// Gets the current change tick of the world which owns the chunk.
+    fn change_tick(&self) -> usize {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /// Moves the chunk into the world which owns the given change tick, marking all of its
+    /// components as added and changed.
+    pub(crate) fn set_change_tick(&mut self, change_tick: Arc<AtomicUsize>) {
+        self.change_tick = change_tick;
+        let tick = self.change_tick();
+        for storage in self.components.values() {
+            unsafe { storage.reset_ticks(tick) };
+        }
+    }
+
+    /// Marks all of the chunk's components as changed.
+    pub(crate) fn mark_changed(&self) {
+        let tick = self.change_tick();
+        for storage in self.components.values() {
+            unsafe { storage.mark_all(tick) };
+        }
+    }
+
+    /// Marks the components of `count` entities from `start` as added.
+    pub(crate) fn mark_added(&self, start: usize, count: usize) {
+        let tick = self.change_tick();
+        for storage in self.components.values() {
+            unsafe { storage.mark_added(start, count, tick) };
+        }
+    }
+
+    /// Gets the ticks at which each of the components of the entity at `index` were added.
+    pub(crate) fn added_ticks(&self, index: usize) -> Vec<(ComponentTypeId, usize)> {
+        self.components
+            .iter()
+            .map(|(ty, c)| (*ty, unsafe { c.ticks().added[index] }))
+            .collect()
+    }
+
+    /// Restores the ticks at which those of the components of the entity at `index` which the
+    /// chunk contains were added.
+    pub(crate) fn restore_added_ticks(&self, index: usize, added: &[(ComponentTypeId, usize)]) {
+        for (ty, tick) in added {
+            if let Some(c) = self.components.get(ty) {
+                unsafe { c.ticks().added[index] = *tick };
+            }
+        }
+    }
+
+    /// Gets the version number of a given component type.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn component_version_untyped(&self, ty: &ComponentTypeId) -> Option<usize> {
+        unsafe { self.components.get(ty).map(|c| c.header().version()) }
+    }
+
+    /// Gets the version number of the chunk's entity list.
+    ///
+    /// The version is incremented every time entities are added to, removed from or moved
+    /// within the chunk.
+    pub fn entities_version(&self) -> usize {
+        self.entities.version()
+    }
+
+    /// Gets the current version numbers of the chunk's entity list and all of its components.
+    pub(crate) fn versions(&self) -> ChunkVersions {
+        ChunkVersions {
+            entities: self.entities_version(),
+            components: self
+                .components
+                .iter()
+                .map(|(ty, c)| (*ty, unsafe { c.header().version() }))
+                .collect(),
+        }
+    }
+
     /// Gets a tag value associated with all entities in the chunk.
     ///
     /// Returns `None` if the chunk does not contain the requested data type.
@@ -475,74 +949,476 @@ impl Chunk {
     pub fn remove(&mut self, id: ComponentIndex) -> Option<Entity> {
         unsafe {
             let index = id as usize;
-            self.entities.data_mut().swap_remove(index);
             for (_, storage) in self.components.iter() {
-                let to_remove = storage.element_mut(id as usize);
                 // Drop component
                 if let Some(drop_fn) = storage.drop_fn {
-                    drop_fn(to_remove.as_ptr());
+                    drop_fn(storage.element_mut(index).as_ptr());
                 }
-                // Move the last element into the place of the dropped component
-                let swap_target = storage.element_mut(self.entities().len());
-                std::ptr::copy_nonoverlapping(
-                    swap_target.as_ptr(),
-                    to_remove.as_ptr(),
-                    storage.component_size,
-                );
             }
 
-            if self.entities.len() > index {
-                Some(*self.entities.data().get(index).unwrap())
-            } else {
-                None
-            }
+            self.swap_remove_storage(index)
         }
     }
 
-    /// Removes and entity from the chunk and returns a dynamic tag set and entity source
-    /// which can be used to re-insert the removed entity into a world.
+    /// Removes all entities from the chunk, dropping their component data.
     ///
-    /// Returns the ID of any entity which was swapped into the location of the
-    /// removed entity.
-    pub fn fetch_remove(
-        &mut self,
-        id: ComponentIndex,
-    ) -> (Option<Entity>, DynamicTagSet, DynamicSingleEntitySource) {
+    /// Returns the IDs of the removed entities.
+    pub fn clear(&mut self) -> Vec<Entity> {
         unsafe {
-            let index = id as usize;
-            let entity = self.entities.data_mut().swap_remove(index);
-            // TODO ensure the returned structs drop the owned component data when they are dropped
-            let components = self.components.iter().map(|(ty, storage)| {
-                DynamicSingleEntitySource::component_tuple_raw(
-                    *ty,
-                    Vec::from(std::slice::from_raw_parts(
-                        storage.element(index).as_ptr(),
+            for (_, storage) in self.components.iter() {
+                let data = storage.data_mut();
+                if let Some(drop_fn) = storage.drop_fn {
+                    for i in 0..self.len() {
+                        drop_fn(data.as_ptr().add(i * storage.component_size));
+                    }
+                }
+            }
+
+            self.entities.data_mut().drain(..).collect()
+        }
+    }
+
+    /// Removes and entity from the chunk and returns a dynamic tag set and entity source
+    /// which can be used to re-insert the removed entity into a world.
+    ///
+    /// Ownership of the entity's components is moved into the returned entity source.
+    ///
+    /// Returns the ID of any entity which was swapped into the location of the
+    /// removed entity.
+    pub fn fetch_remove(
+        &mut self,
+        id: ComponentIndex,
+    ) -> (Option<Entity>, DynamicTagSet, DynamicSingleEntitySource) {
+        unsafe {
+            let index = id as usize;
+            let entity = *self.entities.data().get(index).unwrap();
+
+            let (prev, components) = self
+                .components
+                .iter()
+                .map(|(ty, storage)| {
+                    DynamicComponent::from_raw(
+                        *ty,
+                        storage.element_mut(index).as_ptr(),
                         storage.component_size,
-                    )),
-                    storage.drop_fn,
-                )
-            });
+                        storage.drop_fn,
+                    )
+                })
+                .partition(|c| self.is_buffer(&c.ty));
             let mut tags_info = FnvHashMap::default();
             for (ty, info) in self.tags.iter() {
                 tags_info.insert(*ty, info.clone_into_owned());
             }
             let tags = DynamicTagSet { tags: tags_info };
 
+            // previous values are carried with the entity, but are not part of its archetype
             let components = DynamicSingleEntitySource {
                 entity,
-                components: components.collect(),
+                components,
+                prev,
             };
 
-            let moved = if self.entities.len() > index {
-                Some(*self.entities.data().get(index).unwrap())
-            } else {
-                None
-            };
+            // the component data now belongs to the returned source, so it is not dropped here
+            let moved = self.swap_remove_storage(index);
 
             (moved, tags, components)
         }
     }
 
+    /// Clones the tags and components of the entity at `index`.
+    ///
+    /// Returns `Err` with the first component type found which has no registered clone function.
+    pub(crate) fn clone_entity(
+        &self,
+        index: ComponentIndex,
+        registry: &CloneRegistry,
+    ) -> Result<(DynamicTagSet, Vec<DynamicComponent>), ComponentTypeId> {
+        let tags = self
+            .tags
+            .iter()
+            .map(|(ty, info)| (*ty, unsafe { info.clone_into_owned() }))
+            .collect();
+        let components = self
+            .components
+            .keys()
+            .filter(|ty| !self.is_buffer(ty))
+            .map(|ty| self.clone_component(ty, index, registry).unwrap())
+            .collect::<Result<_, _>>()?;
+
+        Ok((DynamicTagSet { tags }, components))
+    }
+
+    /// Clones a component of the entity at `index`.
+    ///
+    /// Returns `None` if the chunk does not contain the component type, or `Err` if the type has
+    /// no registered clone function.
+    pub(crate) fn clone_component(
+        &self,
+        ty: &ComponentTypeId,
+        index: ComponentIndex,
+        registry: &CloneRegistry,
+    ) -> Option<Result<DynamicComponent, ComponentTypeId>> {
+        let storage = self.components.get(ty)?;
+        Some(
+            unsafe {
+                DynamicComponent::clone_from_raw(
+                    *ty,
+                    storage.element(index as usize).as_ptr(),
+                    storage.component_size,
+                    storage.drop_fn,
+                    registry,
+                )
+            }
+            .ok_or(*ty),
+        )
+    }
+
+    /// Removes the entity at `index` by moving the last entity into its place.
+    ///
+    /// The removed entity's component data is not dropped.
+    unsafe fn swap_remove_storage(&mut self, index: usize) -> Option<Entity> {
+        self.entities.data_mut().swap_remove(index);
+        let last = self.entities.len();
+        if last > index {
+            for (_, storage) in self.components.iter() {
+                std::ptr::copy_nonoverlapping(
+                    storage.element(last).as_ptr(),
+                    storage.element_mut(index).as_ptr(),
+                    storage.component_size,
+                );
+                let ticks = storage.ticks();
+                ticks.components[index] = ticks.get(last);
+                ticks.added[index] = ticks.added[last];
+            }
+
+            Some(*self.entities.data().get(index).unwrap())
+        } else {
+            None
+        }
+    }
+
+    /// Constructs a view of the chunk's current contents, which shares its memory.
+    ///
+    /// While the view is alive, each component column is copied with its clone function in
+    /// `registry` the next time it is accessed mutably, so the view is left unchanged.
+    ///
+    /// Returns `Err` with the first component type found which has no registered clone function.
+    pub(crate) fn freeze(
+        &mut self,
+        registry: &CloneRegistry,
+    ) -> Result<FrozenChunk, ComponentTypeId> {
+        if let Some(ty) = self
+            .components
+            .keys()
+            .find(|ty| self.clone_fn(ty, registry).is_none())
+        {
+            return Err(*ty);
+        }
+
+        let len = self.len();
+        let components = self
+            .components
+            .iter()
+            .map(|(ty, storage)| {
+                let clone_fn = self.clone_fn(ty, registry).unwrap();
+                let column = FrozenColumn {
+                    data: unsafe { storage.share(len, clone_fn) },
+                    component_size: storage.component_size,
+                    drop_fn: storage.drop_fn,
+                };
+                (*ty, column)
+            })
+            .collect();
+
+        Ok(FrozenChunk {
+            id: self.id,
+            entities: unsafe { self.entities.data().clone() },
+            components,
+            tags: self.tags.clone(),
+            allocation: self.allocation.clone(),
+        })
+    }
+
+    /// Determines if this chunk contains the same tag values as `other`.
+    pub fn tags_match(&self, other: &Chunk) -> bool {
+        self.tags.len() == other.tags.len()
+            && self.tags.iter().all(|(ty, info)| {
+                match other.tags.get(ty) {
+                    Some(other) => unsafe { info.data_eq(*other) },
+                    None => false,
+                }
+            })
+    }
+
+    /// Constructs a `ChunkBuilder` which is configured with the same component
+    /// and tag layout as this chunk.
+    pub fn builder(&self) -> ChunkBuilder {
+        let mut builder = ChunkBuilder::new();
+        if let Some(ref pool) = self.pool {
+            builder.set_pool(pool.clone());
+        }
+        for (ty, info) in self.tags.iter() {
+            builder.register_tag_raw(*ty, info.data_size, info.vtable);
+        }
+        for (ty, info) in self.components.iter() {
+            builder.register_component_raw(*ty, info.component_size, info.drop_fn);
+        }
+        builder.buffers = self.buffers.clone();
+        builder.change_tick = Some(self.change_tick.clone());
+        builder
+    }
+
+    /// Constructs a deep copy of this chunk from `builder`, which must be configured with the
+    /// same layout as this chunk.
+    ///
+    /// Returns `Err` with the first component type found which has no registered clone function.
+    pub(crate) fn try_clone(
+        &self,
+        builder: ChunkBuilder,
+        id: ChunkId,
+        registry: &CloneRegistry,
+    ) -> Result<Chunk, ComponentTypeId> {
+        if let Some(ty) = self
+            .components
+            .keys()
+            .find(|ty| self.clone_fn(ty, registry).is_none())
+        {
+            return Err(*ty);
+        }
+
+        let mut chunk = builder.build(id);
+        unsafe {
+            chunk.clone_tags_from(self);
+            for (ty, src) in self.components.iter() {
+                let dst = chunk.components.get(ty).unwrap();
+                let clone_fn = self.clone_fn(ty, registry).unwrap();
+                clone_fn(
+                    src.element(0).as_ptr(),
+                    dst.element_mut(0).as_ptr(),
+                    self.len(),
+                );
+                let (dst_ticks, src_ticks) = (dst.ticks(), src.ticks());
+                for i in 0..self.len() {
+                    dst_ticks.copy_from(i, src_ticks, i);
+                }
+                dst.mark_all(chunk.change_tick());
+            }
+            chunk
+                .entities
+                .data_mut()
+                .extend_from_slice(self.entities.data());
+        }
+
+        Ok(chunk)
+    }
+
+    /// Initializes the tags of this chunk with clones of the tag values in `other`.
+    ///
+    /// # Safety
+    ///
+    /// Both chunks must contain the same tag types, and the tags in this chunk must not
+    /// have been initialized yet.
+    pub(crate) unsafe fn clone_tags_from(&mut self, other: &Chunk) {
+        for (ty, info) in self.tags.iter() {
+            let src = other
+                .tags
+                .get(ty)
+                .expect("tag type not found in source chunk");
+            src.clone_data(src.ptr.as_ptr(), info.ptr.as_ptr());
+        }
+    }
+
+    /// Moves up to `count` entities from the end of `source` onto the end of this chunk.
+    ///
+    /// Component types which exist in both chunks are moved column-wise. Components of `source`
+    /// which do not exist in this chunk are dropped.
+    ///
+    /// Returns the number of entities moved.
+    ///
+    /// # Safety
+    ///
+    /// Both chunks must contain the same tag values. Components in this chunk which do not exist
+    /// in `source` are left uninitialized for the moved entities and must be written by the caller.
+    pub(crate) unsafe fn move_from(&mut self, source: &mut Chunk, count: usize) -> usize {
+        let count = std::cmp::min(
+            count,
+            std::cmp::min(source.len(), self.capacity - self.len()),
+        );
+        let src_start = source.len() - count;
+        let dst_start = self.len();
+
+        for (ty, src_storage) in source.components.iter() {
+            match self.components.get(ty) {
+                Some(dst_storage) => {
+                    std::ptr::copy_nonoverlapping(
+                        src_storage.element_mut(src_start).as_ptr(),
+                        dst_storage.element_mut(dst_start).as_ptr(),
+                        src_storage.component_size * count,
+                    );
+                    if Arc::ptr_eq(&self.change_tick, &source.change_tick) {
+                        let src_ticks = src_storage.ticks();
+                        let dst_ticks = dst_storage.ticks();
+                        for i in 0..count {
+                            dst_ticks.copy_from(dst_start + i, src_ticks, src_start + i);
+                        }
+                    } else {
+                        // entities moved in from another world are new to this one
+                        dst_storage.mark_range(dst_start, count, self.change_tick());
+                        dst_storage.mark_added(dst_start, count, self.change_tick());
+                    }
+                }
+                None => {
+                    if let Some(drop_fn) = src_storage.drop_fn {
+                        for i in src_start..(src_start + count) {
+                            drop_fn(src_storage.element_mut(i).as_ptr());
+                        }
+                    }
+                }
+            }
+        }
+
+        // components which the source does not have are added to the moved entities
+        let tick = self.change_tick();
+        for (_, dst_storage) in self
+            .components
+            .iter()
+            .filter(|(ty, _)| !source.components.contains_key(ty))
+        {
+            dst_storage.mark_added(dst_start, count, tick);
+        }
+
+        // buffers which the source does not have start with the values they were moved with
+        self.init_buffers_where(dst_start, count, |buffer| {
+            !source.components.contains_key(&buffer.prev)
+                && source.components.contains_key(&buffer.component)
+        });
+
+        let moved = source.entities.data_mut().drain(src_start..);
+        self.entities.data_mut().extend(moved);
+
+        count
+    }
+
+    /// Initializes the previous values of all double-buffered components of `count` entities from
+    /// `start` with clones of their current values.
+    ///
+    /// # Safety
+    ///
+    /// The previous values of the entities must be uninitialized, and their current values
+    /// initialized.
+    pub(crate) unsafe fn init_buffers(&mut self, start: usize, count: usize) {
+        self.init_buffers_where(start, count, |_| true);
+    }
+
+    /// Initializes the previous values of `count` entities from `start`, for the
+    /// double-buffered components which do not exist in `source`.
+    ///
+    /// # Safety
+    ///
+    /// As per `init_buffers`, for the components which do not exist in `source`.
+    pub(crate) unsafe fn init_buffers_from(&mut self, source: &Chunk, start: usize, count: usize) {
+        self.init_buffers_where(start, count, |buffer| {
+            !source.components.contains_key(&buffer.component)
+        });
+    }
+
+    unsafe fn init_buffers_where<F: Fn(&ComponentBuffer) -> bool>(
+        &mut self,
+        start: usize,
+        count: usize,
+        filter: F,
+    ) {
+        for buffer in self.buffers.iter().filter(|b| filter(b)) {
+            let current = &self.components[&buffer.component];
+            let prev = &self.components[&buffer.prev];
+            (buffer.clone_fn)(
+                current.element(start).as_ptr(),
+                prev.element_mut(start).as_ptr(),
+                count,
+            );
+        }
+    }
+
+    /// Replaces the previous values of all double-buffered components with clones of their
+    /// current values.
+    pub(crate) fn advance_buffers(&mut self) {
+        let len = self.len();
+        for buffer in self.buffers.iter() {
+            let current = &self.components[&buffer.component];
+            let prev = &self.components[&buffer.prev];
+            unsafe {
+                // clone into a temporary first, so that the previous values are left intact if a
+                // clone panics
+                let size = len * buffer.size;
+                let clones = DynamicComponent::alloc(size);
+                (buffer.clone_fn)(current.data().as_ptr(), clones.as_ptr(), len);
+
+                let data = prev.data_mut();
+                if let Some(drop_fn) = buffer.drop_fn {
+                    for i in 0..len {
+                        drop_fn(data.as_ptr().add(i * buffer.size));
+                    }
+                }
+                std::ptr::copy_nonoverlapping(clones.as_ptr(), data.as_ptr(), size);
+                DynamicComponent::dealloc_raw(clones, size);
+            }
+        }
+    }
+
+    /// Restores the previous values of the double-buffered components of the entity at `index`,
+    /// which were carried with it from another chunk by `fetch_remove`.
+    ///
+    /// Values for which the chunk has no `Prev<T>` column are dropped.
+    ///
+    /// # Safety
+    ///
+    /// The previous values of the entity must be initialized.
+    pub(crate) unsafe fn restore_prev(&self, index: usize, prev: Vec<DynamicComponent>) {
+        for component in prev {
+            // values which have no column to replace are handed back, and dropped here
+            let _ = component.replace(self, index);
+        }
+    }
+
+    /// Determines if the component type is the `Prev<T>` column of a double-buffered component.
+    fn is_buffer(&self, ty: &ComponentTypeId) -> bool {
+        self.buffers.iter().any(|b| b.prev == *ty)
+    }
+
+    /// Gets the function used to clone components of the given type, from `registry` or from
+    /// the chunk's buffers.
+    fn clone_fn(
+        &self,
+        ty: &ComponentTypeId,
+        registry: &CloneRegistry,
+    ) -> Option<fn(*const u8, *mut u8, usize)> {
+        match self.buffers.iter().find(|b| b.prev == *ty) {
+            Some(buffer) => Some(buffer.clone_fn),
+            None => registry.clone_fns.get(ty).copied(),
+        }
+    }
+
+    /// Moves the chunk's contents into a new chunk constructed from `builder`, keeping the chunk's
+    /// ID, capacity and entity order.
+    ///
+    /// The versions of the new chunk are advanced past those of this chunk.
+    pub(crate) fn rebuild(mut self, mut builder: ChunkBuilder) -> Chunk {
+        builder.set_capacity(self.capacity);
+        let mut chunk = builder.build(self.id);
+        unsafe {
+            chunk.clone_tags_from(&self);
+            for (ty, storage) in chunk.components.iter() {
+                if let Some(old) = self.components.get(ty) {
+                    storage.header().version = old.header().version;
+                }
+            }
+            *chunk.entities.version.get() = *self.entities.version.get();
+            let len = self.len();
+            chunk.move_from(&mut self, len);
+        }
+        chunk
+    }
+
     fn borrow<'a, T: Component>(&'a self) -> Borrow<'a> {
         let id = T::type_id();
         let state = self
@@ -561,6 +1437,91 @@ impl Chunk {
         Borrow::aquire_write(state).unwrap()
     }
 }
+struct FrozenColumn {
+    data: Arc<ColumnData>,
+    component_size: usize,
+    drop_fn: Option<fn(*mut u8)>,
+}
+
+/// A read-only view of the contents of a chunk at the time it was frozen.
+///
+/// The view shares the chunk's memory. The chunk copies a component column before it next
+/// writes to it, so the view is unaffected by later changes to the chunk.
+pub struct FrozenChunk {
+    id: ChunkId,
+    entities: Vec<Entity>,
+    components: FnvHashMap<ComponentTypeId, FrozenColumn>,
+    tags: FnvHashMap<TagTypeId, TagStorageInfo>,
+    #[allow(unused)]
+    allocation: Arc<ChunkAllocation>,
+}
+
+// component and tag values are required to be `Send + Sync`
+unsafe impl Send for FrozenChunk {}
+unsafe impl Sync for FrozenChunk {}
+
+impl FrozenChunk {
+    /// Gets the ID of the chunk the view was frozen from.
+    pub fn id(&self) -> ChunkId {
+        self.id
+    }
+
+    /// Gets the number of entities in the view.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Determines if the view contains no entities.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Gets a slice of `Entity` IDs of the entities in the view.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Gets a slice of component data.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn components<T: Component>(&self) -> Option<&[T]> {
+        self.components.get(&T::type_id()).map(|column| unsafe {
+            std::slice::from_raw_parts(column.data.ptr.as_ptr() as *const T, self.len())
+        })
+    }
+
+    /// Gets a tag value associated with all entities in the view.
+    ///
+    /// Returns `None` if the chunk does not contain the requested data type.
+    pub fn tag<T: Tag>(&self) -> Option<&T> {
+        unsafe {
+            self.tags
+                .get(&T::type_id())
+                .map(|s| (s.data().as_ptr() as *const T).as_ref().unwrap())
+        }
+    }
+}
+
+impl Drop for FrozenChunk {
+    fn drop(&mut self) {
+        let len = self.len();
+        for (_, column) in self.components.drain() {
+            unsafe {
+                ColumnData::release(column.data, len, column.component_size, column.drop_fn);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for FrozenChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FrozenChunk")
+            .field("id", &self.id)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
 fn align_down(addr: usize, align: usize) -> usize {
     addr & align.wrapping_neg()
 }
@@ -569,10 +1530,111 @@ fn align_up(addr: usize, align: usize) -> usize {
     (addr + (align - 1)) & align.wrapping_neg()
 }
 
+struct PooledBlock(std::alloc::Layout, NonNull<u8>);
+
+unsafe impl Send for PooledBlock {}
+
+/// A pool of chunk memory allocations which can be re-used when constructing new chunks.
+///
+/// Memory released by dropped chunks is retained by the pool up to its capacity, beyond which
+/// it is returned to the allocator. A pool with a capacity of zero does not retain any memory.
+#[derive(Default)]
+pub struct ChunkPool {
+    capacity: AtomicUsize,
+    blocks: Mutex<Vec<PooledBlock>>,
+}
+
+impl ChunkPool {
+    /// Constructs a new `ChunkPool` which retains up to `capacity` chunk allocations.
+    pub fn new(capacity: usize) -> Self {
+        ChunkPool {
+            capacity: AtomicUsize::new(capacity),
+            blocks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Gets the maximum number of chunk allocations retained by the pool.
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    /// Sets the maximum number of chunk allocations retained by the pool.
+    ///
+    /// Any allocations beyond the new capacity are released.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let mut blocks = self.blocks.lock();
+        while blocks.len() > capacity {
+            let PooledBlock(layout, ptr) = blocks.pop().unwrap();
+            unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+
+    /// Gets the number of chunk allocations currently retained by the pool.
+    pub fn len(&self) -> usize {
+        self.blocks.lock().len()
+    }
+
+    /// Determines if the pool currently retains no chunk allocations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Releases all chunk allocations retained by the pool.
+    pub fn clear(&self) {
+        for PooledBlock(layout, ptr) in self.blocks.lock().drain(..) {
+            unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+
+    fn alloc(&self, layout: std::alloc::Layout) -> NonNull<u8> {
+        let pooled = {
+            let mut blocks = self.blocks.lock();
+            blocks
+                .iter()
+                .position(|PooledBlock(l, _)| *l == layout)
+                .map(|i| blocks.swap_remove(i).1)
+        };
+
+        pooled.unwrap_or_else(|| {
+            NonNull::new(unsafe { std::alloc::alloc(layout) })
+                .unwrap_or_else(|| std::alloc::handle_alloc_error(layout))
+        })
+    }
+
+    unsafe fn free(&self, ptr: NonNull<u8>, layout: std::alloc::Layout) {
+        let mut blocks = self.blocks.lock();
+        if blocks.len() < self.capacity() {
+            blocks.push(PooledBlock(layout, ptr));
+        } else {
+            std::alloc::dealloc(ptr.as_ptr(), layout);
+        }
+    }
+}
+
+impl Drop for ChunkPool {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl std::fmt::Debug for ChunkPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ChunkPool")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
 /// Constructs a new `Chunk`.
 pub struct ChunkBuilder {
     components: Vec<(ComponentTypeId, usize, Option<fn(*mut u8)>)>,
     tags: Vec<(TagTypeId, usize, TagStorageVTable)>,
+    buffers: Vec<ComponentBuffer>,
+    capacity: Option<usize>,
+    pool: Option<Arc<ChunkPool>>,
+    change_tick: Option<Arc<AtomicUsize>>,
 }
 
 impl ChunkBuilder {
@@ -583,9 +1645,23 @@ impl ChunkBuilder {
         ChunkBuilder {
             components: Vec::new(),
             tags: Vec::new(),
+            buffers: Vec::new(),
+            capacity: None,
+            pool: None,
+            change_tick: None,
         }
     }
 
+    /// Allocates the chunk's memory from, and returns it to, the given `ChunkPool`.
+    pub fn set_pool(&mut self, pool: Arc<ChunkPool>) {
+        self.pool = Some(pool);
+    }
+
+    /// Records the chunk's changes against the given world change tick.
+    pub(crate) fn set_change_tick(&mut self, change_tick: Arc<AtomicUsize>) {
+        self.change_tick = Some(change_tick);
+    }
+
     /// Registers an entity data component type.
     pub fn register_component<T: Component>(&mut self) {
         self.register_component_raw(
@@ -604,6 +1680,37 @@ impl ChunkBuilder {
         self.components.push((id, component_size, drop_fn));
     }
 
+    /// Removes a previously registered entity data component type.
+    pub fn unregister_component(&mut self, id: &ComponentTypeId) {
+        self.components.retain(|(ty, _, _)| ty != id);
+    }
+
+    /// Sets the double-buffered component types of the chunk.
+    ///
+    /// A `Prev<T>` column is added for each buffered type which has been registered, and the
+    /// columns of any previously set buffers are removed.
+    pub(crate) fn set_buffers(&mut self, buffers: &[ComponentBuffer]) {
+        for buffer in std::mem::take(&mut self.buffers) {
+            self.unregister_component(&buffer.prev);
+        }
+        for buffer in buffers {
+            if self
+                .components
+                .iter()
+                .any(|(ty, _, _)| *ty == buffer.component)
+            {
+                self.register_component_raw(buffer.prev, buffer.size, buffer.drop_fn);
+                self.buffers.push(*buffer);
+            }
+        }
+    }
+
+    /// Sets the number of entities the chunk can store, in place of the default capacity derived
+    /// from its layout.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = Some(capacity);
+    }
+
     /// Registers a tag type.
     pub fn register_tag<T: Tag>(&mut self) {
         self.tags.push((
@@ -626,7 +1733,9 @@ impl ChunkBuilder {
             .map(|(_, size, _)| size)
             .sum::<usize>()
             + std::mem::size_of::<Entity>();
-        let entity_capacity = std::cmp::max(1, ChunkBuilder::MAX_SIZE / size_per_entity);
+        let entity_capacity = self
+            .capacity
+            .unwrap_or_else(|| std::cmp::max(1, ChunkBuilder::MAX_SIZE / size_per_entity));
         let mut data_capacity = 0usize;
         let alignment = COMPONENT_STORAGE_ALIGNMENT;
         let mut tag_data_offsets = Vec::new();
@@ -652,29 +1761,45 @@ impl ChunkBuilder {
             .expect("invalid component data size/alignment");
 
         unsafe {
-            let data_storage = std::alloc::alloc(data_layout);
-            let storage_info: FnvHashMap<_, _> = component_data_offsets
+            let data_storage = match self.pool {
+                Some(ref pool) => pool.alloc(data_layout).as_ptr(),
+                None => std::alloc::alloc(data_layout),
+            };
+            let tag_info: FnvHashMap<_, _> = tag_data_offsets
                 .into_iter()
-                .map(|(ty, offset, size, drop_fn)| {
+                .map(|(ty, offset, size, vtable)| {
                     (
                         ty,
-                        ComponentStorageInfo {
+                        TagStorageInfo {
                             ptr: NonNull::new_unchecked(data_storage.offset(offset as isize)),
-                            component_size: size,
-                            drop_fn,
+                            data_size: size,
+                            vtable,
                         },
                     )
                 })
                 .collect();
-            let tag_info: FnvHashMap<_, _> = tag_data_offsets
+            let allocation = Arc::new(ChunkAllocation {
+                ptr: NonNull::new_unchecked(data_storage),
+                layout: data_layout,
+                pool: self.pool.clone(),
+                tags: tag_info.values().copied().collect(),
+            });
+            let storage_info: FnvHashMap<_, _> = component_data_offsets
                 .into_iter()
-                .map(|(ty, offset, size, vtable)| {
+                .map(|(ty, offset, size, drop_fn)| {
                     (
                         ty,
-                        TagStorageInfo {
-                            ptr: NonNull::new_unchecked(data_storage.offset(offset as isize)),
-                            data_size: size,
-                            vtable,
+                        ComponentStorageInfo {
+                            data: UnsafeCell::new(Arc::new(ColumnData {
+                                ptr: NonNull::new_unchecked(data_storage.offset(offset as isize)),
+                                allocation: allocation.clone(),
+                                len: 0,
+                                clone_fn: None,
+                            })),
+                            ticks: UnsafeCell::new(ChangeTicks::new(entity_capacity)),
+                            capacity: entity_capacity,
+                            component_size: size,
+                            drop_fn,
                         },
                     )
                 })
@@ -695,13 +1820,18 @@ impl ChunkBuilder {
                 entities: StorageVec::with_capacity(entity_capacity),
                 components: storage_info,
                 tags: tag_info,
-                component_data_layout: data_layout,
-                component_data: NonNull::new_unchecked(data_storage),
+                buffers: self.buffers,
+                allocation,
+                pool: self.pool,
+                change_tick: self
+                    .change_tick
+                    .unwrap_or_else(|| Arc::new(AtomicUsize::new(0))),
             }
         }
     }
 }
 
+#[derive(Default)]
 pub struct DynamicTagSet {
     tags: FnvHashMap<TagTypeId, OwnedTag>,
 }
@@ -718,6 +1848,17 @@ impl DynamicTagSet {
     }
 }
 
+impl Clone for DynamicTagSet {
+    fn clone(&self) -> Self {
+        let tags = self
+            .tags
+            .iter()
+            .map(|(ty, tag)| (*ty, unsafe { tag.info.unwrap().clone_into_owned() }))
+            .collect();
+        DynamicTagSet { tags }
+    }
+}
+
 impl TagSet for DynamicTagSet {
     fn is_archetype_match(&self, archetype: &Archetype) -> bool {
         archetype.tags.len() == self.tags.len()
@@ -755,94 +1896,220 @@ impl TagSet for DynamicTagSet {
     }
 }
 
-trait ChunkInit: Send {
-    fn call(self: Box<Self>, chunk: &mut Chunk, idx: usize);
+/// A single, owned, type-erased component value.
+///
+/// The value is dropped with its registered drop function unless it has been
+/// moved into a chunk.
+pub struct DynamicComponent {
+    ty: ComponentTypeId,
+    size: usize,
+    drop_fn: Option<fn(*mut u8)>,
+    data: NonNull<u8>,
+}
+
+unsafe impl Send for DynamicComponent {}
+unsafe impl Sync for DynamicComponent {}
+
+impl DynamicComponent {
+    fn alloc(size: usize) -> NonNull<u8> {
+        if size == 0 {
+            // zero sized components only require an aligned, non-null pointer
+            NonNull::new(COMPONENT_STORAGE_ALIGNMENT as *mut u8).unwrap()
+        } else {
+            let layout = std::alloc::Layout::from_size_align(size, COMPONENT_STORAGE_ALIGNMENT)
+                .expect("invalid component size/alignment");
+            NonNull::new(unsafe { std::alloc::alloc(layout) })
+                .unwrap_or_else(|| std::alloc::handle_alloc_error(layout))
+        }
+    }
+
+    /// Constructs a new `DynamicComponent` by moving `component` into it.
+    pub fn new<T: Component>(component: T) -> Self {
+        let data = Self::alloc(size_of::<T>());
+        unsafe { std::ptr::write(data.as_ptr() as *mut T, component) };
+        DynamicComponent {
+            ty: T::type_id(),
+            size: size_of::<T>(),
+            drop_fn: Some(|ptr| unsafe { std::ptr::drop_in_place::<T>(ptr as *mut T) }),
+            data,
+        }
+    }
+
+    /// Constructs a new `DynamicComponent` by taking ownership of the `size` bytes at `src`.
+    ///
+    /// # Safety
+    ///
+    /// `src` must point to a valid instance of the component type `ty`. The caller must
+    /// ensure that the value at `src` is not dropped afterwards.
+    pub unsafe fn from_raw(
+        ty: ComponentTypeId,
+        src: *const u8,
+        size: usize,
+        drop_fn: Option<fn(*mut u8)>,
+    ) -> Self {
+        let data = Self::alloc(size);
+        std::ptr::copy_nonoverlapping(src, data.as_ptr(), size);
+        DynamicComponent {
+            ty,
+            size,
+            drop_fn,
+            data,
+        }
+    }
+
+    /// Constructs a new `DynamicComponent` containing a clone of the component at `src`.
+    ///
+    /// Returns `None` if the component type has no clone function registered in `registry`.
+    ///
+    /// # Safety
+    ///
+    /// `src` must point to a valid instance of the component type `ty`.
+    unsafe fn clone_from_raw(
+        ty: ComponentTypeId,
+        src: *const u8,
+        size: usize,
+        drop_fn: Option<fn(*mut u8)>,
+        registry: &CloneRegistry,
+    ) -> Option<Self> {
+        let clone_fn = registry.clone_fns.get(&ty)?;
+        let data = Self::alloc(size);
+        clone_fn(src, data.as_ptr(), 1);
+        Some(DynamicComponent {
+            ty,
+            size,
+            drop_fn,
+            data,
+        })
+    }
+
+    /// Constructs a deep copy of this component.
+    ///
+    /// Returns `None` if the component type has no clone function registered in `registry`.
+    pub fn try_clone(&self, registry: &CloneRegistry) -> Option<Self> {
+        unsafe {
+            Self::clone_from_raw(
+                self.ty,
+                self.data.as_ptr(),
+                self.size,
+                self.drop_fn,
+                registry,
+            )
+        }
+    }
+
+    /// Gets the component type ID.
+    pub fn type_id(&self) -> ComponentTypeId {
+        self.ty
+    }
+
+    fn configure_chunk(&self, chunk: &mut ChunkBuilder) {
+        chunk.register_component_raw(self.ty, self.size, self.drop_fn);
+    }
+
+    /// Gets a pointer to the component value.
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.data.as_ptr()
+    }
+
+    /// Moves the component into the chunk at the given index, dropping the value it replaces.
+    ///
+    /// Returns the component if the chunk does not contain its type.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must refer to an initialized component within the chunk, and no other code may be
+    /// accessing the chunk's components of this type.
+    pub(crate) unsafe fn replace(self, chunk: &Chunk, idx: usize) -> Result<(), Self> {
+        let dst = match chunk.entity_component_mut_raw(&self.ty, idx) {
+            Some(dst) => dst,
+            None => return Err(self),
+        };
+
+        if let Some(drop_fn) = self.drop_fn {
+            drop_fn(dst.as_ptr());
+        }
+        std::ptr::copy_nonoverlapping(self.data.as_ptr(), dst.as_ptr(), self.size);
+        self.dealloc();
+        std::mem::forget(self);
+        Ok(())
+    }
+
+    /// Moves the component into the chunk at the given index.
+    unsafe fn write(self, chunk: &mut Chunk, idx: usize) {
+        let dst = chunk.entity_component_mut_raw(&self.ty, idx).unwrap();
+        std::ptr::copy_nonoverlapping(self.data.as_ptr(), dst.as_ptr(), self.size);
+        self.dealloc();
+        std::mem::forget(self);
+    }
+
+    unsafe fn dealloc(&self) {
+        Self::dealloc_raw(self.data, self.size);
+    }
+
+    unsafe fn dealloc_raw(data: NonNull<u8>, size: usize) {
+        if size > 0 {
+            std::alloc::dealloc(
+                data.as_ptr(),
+                std::alloc::Layout::from_size_align_unchecked(size, COMPONENT_STORAGE_ALIGNMENT),
+            );
+        }
+    }
+}
+
+impl Drop for DynamicComponent {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(drop_fn) = self.drop_fn {
+                drop_fn(self.data.as_ptr());
+            }
+            self.dealloc();
+        }
+    }
 }
 
-impl<'a, F: FnOnce(&mut Chunk, usize) + Send> ChunkInit for F {
-    fn call(self: Box<Self>, chunk: &mut Chunk, idx: usize) {
-        (*self)(chunk, idx);
+impl std::fmt::Debug for DynamicComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("DynamicComponent")
+            .field("ty", &self.ty)
+            .field("size", &self.size)
+            .finish()
     }
 }
 
+/// An `EntitySource` which re-inserts a single existing entity with a dynamic set of components.
 pub struct DynamicSingleEntitySource {
     entity: Entity,
-    components: Vec<(
-        ComponentTypeId,
-        Box<dyn Fn(&mut ChunkBuilder)>,
-        Box<dyn ChunkInit>,
-    )>,
+    components: Vec<DynamicComponent>,
+    prev: Vec<DynamicComponent>,
 }
 
 impl DynamicSingleEntitySource {
-    fn component_tuple<T: Component>(
-        component: T,
-    ) -> (
-        ComponentTypeId,
-        Box<dyn Fn(&mut ChunkBuilder)>,
-        Box<dyn ChunkInit>,
-    ) {
-        let chunk_setup = |chunk: &mut ChunkBuilder| chunk.register_component::<T>();
-
-        let ty = T::type_id();
-        let data_initializer = |chunk: &mut Chunk, idx: usize| unsafe {
-            std::ptr::write(
-                chunk
-                    .components_mut_raw::<T>()
-                    .unwrap()
-                    .as_ptr()
-                    .offset(idx as isize),
-                component,
-            );
-        };
-
-        (ty, Box::new(chunk_setup), Box::new(data_initializer))
+    /// Constructs a source which inserts `entity`, which must already be allocated, with the
+    /// given components.
+    pub(crate) fn new(entity: Entity, components: Vec<DynamicComponent>) -> Self {
+        DynamicSingleEntitySource {
+            entity,
+            components,
+            prev: Vec::new(),
+        }
     }
 
-    unsafe fn component_tuple_raw(
-        ty: ComponentTypeId,
-        component: Vec<u8>,
-        drop_fn: Option<fn(*mut u8)>,
-    ) -> (
-        ComponentTypeId,
-        Box<dyn Fn(&mut ChunkBuilder)>,
-        Box<dyn ChunkInit>,
-    ) {
-        let comp_size = component.len();
-        let chunk_setup =
-            move |chunk: &mut ChunkBuilder| chunk.register_component_raw(ty, comp_size, drop_fn);
-
-        let data_initializer = move |chunk: &mut Chunk, idx: usize| {
-            std::ptr::copy_nonoverlapping(
-                component.get_unchecked(0),
-                chunk
-                    .components_mut_raw_untyped(&ty, 0)
-                    .unwrap()
-                    .as_ptr()
-                    .offset((idx * component.len()) as isize),
-                component.len(),
-            );
-        };
-
-        (ty, Box::new(chunk_setup), Box::new(data_initializer))
+    /// Takes the previous values of the entity's double-buffered components, which are not
+    /// written into a chunk with the entity's other components.
+    pub(crate) fn take_prev(&mut self) -> Vec<DynamicComponent> {
+        std::mem::take(&mut self.prev)
     }
 
     pub fn add_component<T: Component>(&mut self, component: T) {
         self.remove_component::<T>();
-        self.components.push(Self::component_tuple(component));
+        self.components.push(DynamicComponent::new(component));
     }
 
     pub fn remove_component<T: Component>(&mut self) -> bool {
         let type_id = T::type_id();
-        if let Some(i) = self
-            .components
-            .iter()
-            .enumerate()
-            .filter(|(_, (id, _, _))| id == &type_id)
-            .map(|(i, _)| i)
-            .next()
-        {
-            self.components.remove(i);
+        if let Some(i) = self.components.iter().position(|c| c.ty == type_id) {
+            // dropping the removed `DynamicComponent` drops the component value
+            self.components.swap_remove(i);
             true
         } else {
             false
@@ -856,17 +2123,17 @@ impl EntitySource for DynamicSingleEntitySource {
             && self
                 .components
                 .iter()
-                .all(|(id, _, _)| archetype.components.contains(&id))
+                .all(|c| archetype.components.contains(&c.ty))
     }
 
     fn configure_chunk(&self, chunk: &mut ChunkBuilder) {
-        for (_, f, _) in self.components.iter() {
-            f(chunk);
+        for component in self.components.iter() {
+            component.configure_chunk(chunk);
         }
     }
 
     fn types(&self) -> FnvHashSet<ComponentTypeId> {
-        self.components.iter().map(|(id, _, _)| *id).collect()
+        self.components.iter().map(|c| c.ty).collect()
     }
 
     fn is_empty(&mut self) -> bool {
@@ -878,8 +2145,8 @@ impl EntitySource for DynamicSingleEntitySource {
             unsafe {
                 chunk.entities_unchecked().push(self.entity);
                 let idx = chunk.len() - 1;
-                for (_, _, f) in self.components.drain(..) {
-                    f.call(chunk, idx);
+                for component in self.components.drain(..) {
+                    component.write(chunk, idx);
                 }
             }
 
@@ -890,18 +2157,87 @@ impl EntitySource for DynamicSingleEntitySource {
     }
 }
 
+/// An `EntitySource` which inserts a single new entity with a dynamic set of components.
+#[derive(Default)]
+pub struct DynamicEntitySource {
+    components: Vec<DynamicComponent>,
+    written: bool,
+}
+
+impl DynamicEntitySource {
+    /// Constructs a new, empty `DynamicEntitySource`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a component, replacing any existing component of the same type.
+    pub fn add_component<T: Component>(&mut self, component: T) {
+        self.add_dynamic_component(DynamicComponent::new(component));
+    }
+
+    /// Adds a type-erased component, replacing any existing component of the same type.
+    pub fn add_dynamic_component(&mut self, component: DynamicComponent) {
+        self.components.retain(|c| c.ty != component.ty);
+        self.components.push(component);
+    }
+}
+
+impl EntitySource for DynamicEntitySource {
+    fn is_archetype_match(&self, archetype: &Archetype) -> bool {
+        archetype.components.len() == self.components.len()
+            && self
+                .components
+                .iter()
+                .all(|c| archetype.components.contains(&c.ty))
+    }
+
+    fn configure_chunk(&self, chunk: &mut ChunkBuilder) {
+        for component in self.components.iter() {
+            component.configure_chunk(chunk);
+        }
+    }
+
+    fn types(&self) -> FnvHashSet<ComponentTypeId> {
+        self.components.iter().map(|c| c.ty).collect()
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.written
+    }
+
+    fn write(&mut self, chunk: &mut Chunk, allocator: &mut EntityAllocator) -> usize {
+        if !self.written && !chunk.is_full() {
+            unsafe {
+                chunk.entities_unchecked().push(allocator.create_entity());
+                let idx = chunk.len() - 1;
+                for component in self.components.drain(..) {
+                    component.write(chunk, idx);
+                }
+            }
+
+            self.written = true;
+            1
+        } else {
+            0
+        }
+    }
+}
+
 /// Stores all chunks with a given data layout.
 pub struct Archetype {
     id: ArchetypeId,
     logger: slog::Logger,
-    next_chunk_id: u16,
-    version: u16,
+    next_chunk_id: u64,
+    version: u64,
     /// The entity data component types that all chunks contain.
     pub components: FnvHashSet<ComponentTypeId>,
     /// The tag types that all chunks contains.
     pub tags: FnvHashSet<TagTypeId>,
     /// The chunks that belong to this archetype.
     pub chunks: Vec<Chunk>,
+    buffers: Vec<ComponentBuffer>,
+    pool: Option<Arc<ChunkPool>>,
+    change_tick: Option<Arc<AtomicUsize>>,
 }
 
 impl Archetype {
@@ -920,16 +2256,88 @@ impl Archetype {
             components,
             tags,
             chunks: Vec::new(),
+            buffers: Vec::new(),
+            pool: None,
+            change_tick: None,
         }
     }
 
+    /// Sets the pool from which new chunks in this archetype allocate their memory.
+    pub fn set_chunk_pool(&mut self, pool: Arc<ChunkPool>) {
+        self.pool = Some(pool);
+    }
+
+    /// Sets the world change tick against which the archetype's chunks record their changes.
+    ///
+    /// Existing chunks which recorded their changes against another tick are marked as changed.
+    pub(crate) fn set_change_tick(&mut self, change_tick: Arc<AtomicUsize>) {
+        for chunk in self.chunks.iter_mut() {
+            if !Arc::ptr_eq(&change_tick, &chunk.change_tick) {
+                chunk.set_change_tick(change_tick.clone());
+            }
+        }
+        self.change_tick = Some(change_tick);
+    }
+
+    /// Double-buffers those of the given component types which the archetype contains.
+    ///
+    /// Existing chunks are rebuilt with a `Prev<T>` column for each newly buffered type, holding
+    /// clones of the current values.
+    pub(crate) fn set_buffers(&mut self, buffers: &[ComponentBuffer]) {
+        let added = buffers
+            .iter()
+            .filter(|b| self.components.contains(&b.component))
+            .filter(|b| !self.buffers.iter().any(|existing| existing.prev == b.prev))
+            .copied()
+            .collect::<Vec<_>>();
+        if added.is_empty() {
+            return;
+        }
+
+        self.buffers.extend(added);
+        self.chunks = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .map(|chunk| self.adopt(chunk))
+            .collect();
+        self.version = self.version.wrapping_add(1);
+
+        debug!(self.logger, "buffered components"; "buffers" => self.buffers.len());
+    }
+
+    /// Rebuilds a chunk which is to be moved into this archetype, if its buffers do not match.
+    ///
+    /// A chunk from another world is marked as changed in this archetype's world.
+    fn adopt(&self, mut chunk: Chunk) -> Chunk {
+        if let Some(ref change_tick) = self.change_tick {
+            if !Arc::ptr_eq(change_tick, &chunk.change_tick) {
+                chunk.set_change_tick(change_tick.clone());
+            }
+        }
+
+        let matching = chunk.buffers.len() == self.buffers.len()
+            && self
+                .buffers
+                .iter()
+                .all(|b| chunk.buffers.iter().any(|c| c.prev == b.prev));
+        if matching {
+            return chunk;
+        }
+
+        let mut builder = chunk.builder();
+        builder.set_buffers(&self.buffers);
+        chunk.rebuild(builder)
+    }
+
     /// Gets the archetype ID.
     pub fn id(&self) -> ArchetypeId {
         self.id
     }
 
     /// Gets the archetype version.
-    pub fn version(&self) -> u16 {
+    ///
+    /// The version changes each time a chunk is added to or removed from the archetype, and wraps
+    /// around on overflow.
+    pub fn version(&self) -> u64 {
         self.version
     }
 
@@ -949,8 +2357,10 @@ impl Archetype {
     }
 
     /// Determines if the archetype's chunks contain the given entity data component type id.
+    ///
+    /// This includes the `Prev<T>` columns of double-buffered components.
     pub fn has_component_type(&self, ty: &ComponentTypeId) -> bool {
-        self.components.contains(ty)
+        self.components.contains(ty) || self.buffers.iter().any(|b| b.prev == *ty)
     }
 
     /// Determines if the archetype's chunks contain the given tag type.
@@ -985,14 +2395,21 @@ impl Archetype {
             Some(i) => (i as ChunkIndex, unsafe { self.chunks.get_unchecked_mut(i) }),
             None => {
                 let mut builder = ChunkBuilder::new();
+                if let Some(ref pool) = self.pool {
+                    builder.set_pool(pool.clone());
+                }
+                if let Some(ref change_tick) = self.change_tick {
+                    builder.set_change_tick(change_tick.clone());
+                }
                 tags.configure_chunk(&mut builder);
                 components.configure_chunk(&mut builder);
+                builder.set_buffers(&self.buffers);
 
                 let chunk_id = self.id.chunk(self.next_chunk_id);
                 let chunk_index = self.chunks.len() as ChunkIndex;
                 self.next_chunk_id += 1;
                 self.chunks.push(builder.build(chunk_id));
-                self.version += 1;
+                self.version = self.version.wrapping_add(1);
 
                 let chunk = self.chunks.last_mut().unwrap();
 
@@ -1002,4 +2419,115 @@ impl Archetype {
             }
         }
     }
+
+    /// Allocates a new ID for a chunk which will be pushed into this archetype.
+    ///
+    /// IDs are not re-used once their chunk has been freed, so that caches keyed on chunk IDs
+    /// are not confused by a newly allocated chunk.
+    pub fn allocate_chunk_id(&mut self) -> ChunkId {
+        let id = self.id.chunk(self.next_chunk_id);
+        self.next_chunk_id += 1;
+        id
+    }
+
+    /// Gets the ID which will be given to the next chunk allocated in this archetype.
+    pub(crate) fn next_chunk_id(&self) -> u64 {
+        self.next_chunk_id
+    }
+
+    /// Replaces all of the archetype's chunks.
+    ///
+    /// The chunk ID counter is never rewound, so that the IDs of discarded chunks are not reused
+    /// by chunks which may have different contents. All of the restored components are marked as
+    /// changed.
+    pub(crate) fn restore_chunks(&mut self, chunks: Vec<Chunk>, next_chunk_id: u64) {
+        self.chunks = chunks
+            .into_iter()
+            .map(|c| {
+                let c = self.adopt(c);
+                c.mark_changed();
+                c
+            })
+            .collect();
+        self.next_chunk_id = self.next_chunk_id.max(next_chunk_id);
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Moves an existing chunk into this archetype.
+    ///
+    /// The chunk must contain exactly the component and tag types of this archetype.
+    pub fn push_chunk(&mut self, chunk: Chunk) -> ChunkIndex {
+        debug_assert!(chunk
+            .components
+            .keys()
+            .all(|ty| self.components.contains(ty) || chunk.is_buffer(ty)));
+        debug_assert!(chunk.tags.keys().all(|ty| self.tags.contains(ty)));
+
+        let chunk = self.adopt(chunk);
+        let chunk_index = self.chunks.len() as ChunkIndex;
+        debug!(self.logger, "adopted chunk"; "chunk_id" => chunk.id().2);
+
+        self.chunks.push(chunk);
+        self.version = self.version.wrapping_add(1);
+
+        chunk_index
+    }
+
+    /// Removes an empty chunk from the archetype, releasing its memory.
+    ///
+    /// The last chunk in the archetype is moved into the removed chunk's index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk still contains entities.
+    pub fn remove_chunk(&mut self, id: ChunkIndex) {
+        let chunk = self.take_chunk(id);
+        assert_eq!(0, chunk.len(), "chunk is not empty");
+
+        debug!(self.logger, "freed chunk"; "chunk_id" => chunk.id().2);
+    }
+
+    /// Removes a chunk from the archetype, returning ownership of it and its entities.
+    ///
+    /// The last chunk in the archetype is moved into the removed chunk's index.
+    pub fn take_chunk(&mut self, id: ChunkIndex) -> Chunk {
+        let chunk = self.chunks.swap_remove(id as usize);
+        self.version = self.version.wrapping_add(1);
+        chunk
+    }
+
+    /// Finds a non-full chunk whose tag values match those of `template`, or constructs a new
+    /// chunk with `builder` and initializes its tags with clones of the values in `template`.
+    pub fn get_or_create_chunk_matching<F: FnOnce() -> ChunkBuilder>(
+        &mut self,
+        template: &Chunk,
+        builder: F,
+    ) -> ChunkIndex {
+        match self
+            .chunks
+            .iter()
+            .position(|c| !c.is_full() && c.tags_match(template))
+        {
+            Some(i) => i as ChunkIndex,
+            None => {
+                let chunk_id = self.id.chunk(self.next_chunk_id);
+                let chunk_index = self.chunks.len() as ChunkIndex;
+                self.next_chunk_id += 1;
+
+                let mut builder = builder();
+                builder.set_buffers(&self.buffers);
+                if let Some(ref change_tick) = self.change_tick {
+                    builder.set_change_tick(change_tick.clone());
+                }
+                let mut chunk = builder.build(chunk_id);
+                unsafe { chunk.clone_tags_from(template) };
+                self.chunks.push(chunk);
+                self.version = self.version.wrapping_add(1);
+
+                debug!(self.logger, "allocated chunk"; "chunk_id" => chunk_id.2);
+
+                chunk_index
+            }
+        }
+    }
 }
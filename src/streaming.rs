@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use parking_lot::Mutex;
+use slog::{debug, error, o};
+
+use crate::*;
+
+/// The stage a streaming load has reached.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LoadStatus {
+    /// The load is waiting for a worker thread.
+    Queued,
+    /// The loader is running on a worker thread.
+    Loading,
+    /// The loader has finished, and its entities are waiting to be merged.
+    Loaded,
+    /// The loaded entities are being merged into the main world over one or more polls.
+    Merging,
+    /// The loaded entities have been merged into the main world.
+    Merged,
+    /// The load was cancelled. No entities from a cancelled load are merged.
+    Cancelled,
+    /// The loader panicked. No entities from a failed load are merged.
+    Failed,
+}
+
+impl LoadStatus {
+    fn from_usize(value: usize) -> LoadStatus {
+        match value {
+            0 => LoadStatus::Queued,
+            1 => LoadStatus::Loading,
+            2 => LoadStatus::Loaded,
+            3 => LoadStatus::Merging,
+            4 => LoadStatus::Merged,
+            5 => LoadStatus::Cancelled,
+            _ => LoadStatus::Failed,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LoadState {
+    status: AtomicUsize,
+    progress: AtomicU32,
+    cancelled: AtomicBool,
+}
+
+impl LoadState {
+    fn new() -> LoadState {
+        LoadState {
+            status: AtomicUsize::new(LoadStatus::Queued as usize),
+            progress: AtomicU32::new(0f32.to_bits()),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    fn set_status(&self, status: LoadStatus) {
+        self.status.store(status as usize, Ordering::SeqCst);
+    }
+}
+
+/// A handle to a load submitted to a `StreamingLoader`.
+///
+/// Handles may be freely cloned and sent to other threads.
+#[derive(Clone, Debug)]
+pub struct LoadHandle {
+    state: Arc<LoadState>,
+}
+
+impl LoadHandle {
+    /// Gets the current status of the load.
+    pub fn status(&self) -> LoadStatus {
+        LoadStatus::from_usize(self.state.status.load(Ordering::SeqCst))
+    }
+
+    /// Gets the progress most recently reported by the loader, in the range `0.0..=1.0`.
+    pub fn progress(&self) -> f32 {
+        f32::from_bits(self.state.progress.load(Ordering::SeqCst))
+    }
+
+    /// Requests that the load be cancelled.
+    ///
+    /// Cancellation takes effect immediately if the load has not yet started. A running loader
+    /// should observe `LoadContext::is_cancelled` and return early; its entities are discarded.
+    /// Loads which have already begun merging into the main world are not cancelled.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Determines if cancellation of the load has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Passed to loader closures to allow them to report progress and observe cancellation.
+pub struct LoadContext {
+    state: Arc<LoadState>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl LoadContext {
+    /// Reports the loader's progress, in the range `0.0..=1.0`.
+    pub fn set_progress(&self, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        self.state
+            .progress
+            .store(progress.to_bits(), Ordering::SeqCst);
+    }
+
+    /// Determines if the load has been cancelled, or the loader is shutting down.
+    ///
+    /// Long running loaders should check this periodically and return early if it is `true`.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst) || self.shutdown.load(Ordering::SeqCst)
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Loads entities into worlds on background worker threads, and merges them into a main world
+/// when they are ready.
+///
+/// Each load runs a user provided closure on a worker thread, which inserts entities into a
+/// fresh `World` created within the loader's `Universe`. The main thread calls `poll` (typically
+/// once per frame) to merge completed worlds into its own world, limited by a budget on the number
+/// of entities merged per call.
+///
+/// # Examples
+///
+/// ```
+/// # use legion::prelude::*;
+/// # use legion::streaming::*;
+/// # #[derive(Copy, Clone, Debug, PartialEq)]
+/// # struct Position(f32);
+/// let universe = Universe::new(None);
+/// let mut world = universe.create_world();
+/// let mut loader = StreamingLoader::new(&universe, 2);
+///
+/// let handle = loader.load(|world, ctx| {
+///     for i in 0..10 {
+///         if ctx.is_cancelled() {
+///             return;
+///         }
+///
+///         world.insert_from((), vec![(Position(i as f32),)]);
+///         ctx.set_progress((i + 1) as f32 / 10.0);
+///     }
+/// });
+///
+/// // once per frame, merge up to 1000 entities
+/// while handle.status() != LoadStatus::Merged {
+///     loader.poll(&mut world, 1000);
+/// }
+/// ```
+pub struct StreamingLoader {
+    logger: slog::Logger,
+    jobs: Option<Sender<Job>>,
+    completed_tx: Sender<(Arc<LoadState>, World)>,
+    completed: Receiver<(Arc<LoadState>, World)>,
+    merging: VecDeque<(Arc<LoadState>, World)>,
+    workers: Vec<JoinHandle<()>>,
+    factory: WorldFactory,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl StreamingLoader {
+    /// Creates a new `StreamingLoader` which loads worlds within the given `Universe` using
+    /// `worker_count` background threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `worker_count` is zero.
+    pub fn new(universe: &Universe, worker_count: usize) -> StreamingLoader {
+        assert!(
+            worker_count > 0,
+            "streaming loader requires at least one worker"
+        );
+
+        let factory = universe.world_factory();
+        let logger = factory.logger().new(o!("streaming_loader" => true));
+        let (jobs, job_rx) = channel::<Job>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let (completed_tx, completed) = channel();
+
+        let workers = (0..worker_count)
+            .map(|i| {
+                let job_rx = job_rx.clone();
+                std::thread::Builder::new()
+                    .name(format!("legion-loader-{}", i))
+                    .spawn(move || loop {
+                        let job = job_rx.lock().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn loader thread")
+            })
+            .collect();
+
+        debug!(logger, "started streaming loader"; "worker_count" => worker_count);
+
+        StreamingLoader {
+            logger,
+            jobs: Some(jobs),
+            completed_tx,
+            completed,
+            merging: VecDeque::new(),
+            workers,
+            factory,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Queues a loader closure to be run on a worker thread.
+    ///
+    /// The closure is given a fresh `World` to insert entities into, and a `LoadContext` with
+    /// which it can report progress and check for cancellation.
+    pub fn load<F>(&mut self, loader: F) -> LoadHandle
+    where
+        F: FnOnce(&mut World, &LoadContext) + Send + 'static,
+    {
+        let state = Arc::new(LoadState::new());
+        let ctx = LoadContext {
+            state: state.clone(),
+            shutdown: self.shutdown.clone(),
+        };
+        let factory = self.factory.clone();
+        let completed = self.completed_tx.clone();
+        let logger = self.logger.clone();
+
+        let job: Job = Box::new(move || {
+            if ctx.is_cancelled() {
+                ctx.state.set_status(LoadStatus::Cancelled);
+                return;
+            }
+
+            ctx.state.set_status(LoadStatus::Loading);
+            let mut world = factory.create_world();
+
+            // a panicking loader fails its own load, rather than taking down the worker thread
+            if let Err(panic) = catch_unwind(AssertUnwindSafe(|| loader(&mut world, &ctx))) {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("unknown panic");
+                error!(logger, "loader panicked"; "message" => message);
+                ctx.state.set_status(LoadStatus::Failed);
+                return;
+            }
+
+            if ctx.is_cancelled() {
+                ctx.state.set_status(LoadStatus::Cancelled);
+                return;
+            }
+
+            ctx.set_progress(1.0);
+            ctx.state.set_status(LoadStatus::Loaded);
+
+            // the loader may have been dropped while we were running
+            let _ = completed.send((ctx.state.clone(), world));
+        });
+
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send(job)
+            .expect("loader threads have stopped");
+
+        LoadHandle { state }
+    }
+
+    /// Merges completed loads into `world`.
+    ///
+    /// Loads are merged in the order they complete, a chunk at a time, until either all
+    /// completed loads have been merged or `budget` entities have been merged. At least one chunk
+    /// is merged per call if any loads are ready, so progress is always made, but only the first
+    /// chunk merged in a call may exceed the budget.
+    ///
+    /// Returns the number of loads which finished merging.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `world` belongs to a different `Universe` than this loader.
+    pub fn poll(&mut self, world: &mut World, budget: usize) -> usize {
+        while let Ok(loaded) = self.completed.try_recv() {
+            self.merging.push_back(loaded);
+        }
+
+        let mut remaining = budget;
+        let mut moved = 0;
+        let mut merged = 0;
+        let mut started = false;
+        while let Some((state, source)) = self.merging.front_mut() {
+            if started && remaining == 0 {
+                break;
+            }
+
+            // loads which are cancelled before merging begins are discarded
+            let status = LoadStatus::from_usize(state.status.load(Ordering::SeqCst));
+            if status != LoadStatus::Merging && state.cancelled.load(Ordering::SeqCst) {
+                state.set_status(LoadStatus::Cancelled);
+                self.merging.pop_front();
+                continue;
+            }
+
+            started = true;
+            state.set_status(LoadStatus::Merging);
+            if !world.merge_incremental_within(source, &mut remaining, &mut moved) {
+                break;
+            }
+
+            state.set_status(LoadStatus::Merged);
+            self.merging.pop_front();
+            merged += 1;
+        }
+
+        if merged > 0 {
+            debug!(
+                self.logger,
+                "merged {load_count} loads",
+                load_count = merged
+            );
+        }
+
+        merged
+    }
+
+    /// Gets the number of completed loads which are waiting to be merged.
+    pub fn pending(&mut self) -> usize {
+        while let Ok(loaded) = self.completed.try_recv() {
+            self.merging.push_back(loaded);
+        }
+
+        self.merging.len()
+    }
+}
+
+impl Drop for StreamingLoader {
+    fn drop(&mut self) {
+        // cancel outstanding loads and wait for the workers to exit
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+
+        debug!(self.logger, "stopped streaming loader");
+    }
+}
@@ -0,0 +1,124 @@
+//! Read-only views of a `World`, which can be read on other threads while the world changes.
+//!
+//! `World::freeze` constructs a `WorldView` of the world's current entities, components and tags.
+//! The view shares the memory of the world's chunks rather than copying them. Once a chunk has
+//! been frozen, the first mutable access to each of its component columns, whether through a
+//! query, `World::component_mut` or a structural change, copies that column with its clone
+//! function before it is written. Columns which are not written are never copied, so the cost of
+//! each view scales with what changes while it is alive, rather than with the size of the world.
+//!
+//! Views are `Send + Sync`, and remain valid after the world itself has been dropped.
+//!
+//! # Examples
+//!
+//! ```
+//! # use legion::prelude::*;
+//! # use legion::storage::CloneRegistry;
+//! #[derive(Copy, Clone, Debug, PartialEq)]
+//! struct Position(f32);
+//!
+//! let mut registry = CloneRegistry::default();
+//! registry.register::<Position>();
+//!
+//! let universe = Universe::new(None);
+//! let mut world = universe.create_world();
+//! let entity = world.insert_from((), vec![(Position(0.),)])[0];
+//!
+//! let view = world.freeze(&registry).unwrap();
+//! *world.component_mut::<Position>(entity).unwrap() = Position(1.);
+//!
+//! let render = std::thread::spawn(move || view.iter::<Position>().next().map(|(_, p)| *p));
+//! assert_eq!(Some(Position(0.)), render.join().unwrap());
+//! assert_eq!(Position(1.), *world.component::<Position>(entity).unwrap());
+//! ```
+
+use crate::storage::{CloneRegistry, FrozenChunk};
+use crate::*;
+
+/// An error returned when freezing a `World`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FreezeError {
+    /// A component type has no clone function registered in the `CloneRegistry`.
+    UnregisteredComponent(ComponentTypeId),
+}
+
+impl Display for FreezeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FreezeError::UnregisteredComponent(ty) => write!(
+                f,
+                "component type {:?} has no registered clone function",
+                ty
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FreezeError {}
+
+/// A read-only view of the contents of a `World` at the time it was frozen.
+///
+/// Constructed by `World::freeze`.
+#[derive(Debug)]
+pub struct WorldView {
+    chunks: Vec<FrozenChunk>,
+}
+
+impl WorldView {
+    /// Gets the number of entities in the view.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.len()).sum()
+    }
+
+    /// Determines if the view contains no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the views of each non-empty chunk in the world.
+    pub fn chunks(&self) -> &[FrozenChunk] {
+        &self.chunks
+    }
+
+    /// Iterates through all entities which have a component of type `T`, along with the value of
+    /// that component.
+    pub fn iter<T: Component>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.chunks.iter().flat_map(|chunk| {
+            chunk
+                .components::<T>()
+                .into_iter()
+                .flat_map(move |components| chunk.entities().iter().copied().zip(components))
+        })
+    }
+}
+
+impl World {
+    /// Constructs a read-only view of the world's current contents, which shares its memory.
+    ///
+    /// While the view is alive, each component column is copied with its clone function in
+    /// `registry` before it is next written, so the view is unaffected by later changes to the
+    /// world. All component types in the world must have a clone function registered in
+    /// `registry`.
+    pub fn freeze(&mut self, registry: &CloneRegistry) -> Result<WorldView, FreezeError> {
+        if let Some(ty) = self
+            .archetypes
+            .iter()
+            .flat_map(|a| a.components.iter())
+            .find(|ty| !registry.is_registered(ty))
+        {
+            return Err(FreezeError::UnregisteredComponent(*ty));
+        }
+
+        let chunks = self
+            .archetypes
+            .iter_mut()
+            .flat_map(|a| a.chunks.iter_mut())
+            .filter(|c| c.len() > 0)
+            .map(|c| c.freeze(registry).unwrap())
+            .collect::<Vec<_>>();
+
+        trace!(self.logger, "froze world"; "chunks" => chunks.len());
+
+        Ok(WorldView { chunks })
+    }
+}
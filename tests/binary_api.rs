@@ -0,0 +1,295 @@
+use legion::binary::*;
+use legion::prelude::*;
+use legion::registry::StableTypeId;
+use legion::{EntityMap, EntityMappers, MapEntities};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Rot(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Target(Entity);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Model(u32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Static;
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Rot {}
+    impl DefaultComponentImpl for Target {}
+    impl DefaultComponentImpl for Model {}
+    impl DefaultComponentImpl for Static {}
+}
+
+impl MapEntities for Target {
+    fn map_entities(&mut self, map: &EntityMap) {
+        if let Some(entity) = map.get(&self.0) {
+            self.0 = *entity;
+        }
+    }
+}
+
+fn registry() -> PodRegistry {
+    let mut registry = PodRegistry::default();
+    unsafe {
+        registry
+            .register_component::<Pos>("pos")
+            .register_component::<Rot>("rot")
+            .register_component::<Target>("target")
+            .register_tag::<Model>("model")
+            .register_tag::<Static>("static");
+    }
+    registry
+}
+
+fn populate(world: &mut World) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    entities.extend_from_slice(world.insert_from(
+        (Model(1), Static).as_tags(),
+        (0..2000).map(|i| (Pos(i as f32, 0., 0.), Rot(0., i as f32, 0.))),
+    ));
+    let first = entities[0];
+    entities.extend_from_slice(world.insert_from(
+        (Model(2),).as_tags(),
+        (0..10).map(|i| (Pos(i as f32, 1., 0.), Target(first))),
+    ));
+    entities
+}
+
+#[test]
+fn round_trip() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = populate(&mut world);
+    world.delete(entities[5]);
+
+    let registry = registry();
+    let mut data = Vec::new();
+    world.write_binary(&registry, &mut data).unwrap();
+
+    let load_universe = Universe::new(None);
+    let mut loaded = World::read_binary(&load_universe, &registry, data.as_slice()).unwrap();
+
+    assert!(!loaded.is_alive(&entities[5]));
+    for entity in entities.iter().filter(|e| **e != entities[5]) {
+        assert!(loaded.is_alive(entity));
+        assert_eq!(
+            world.component::<Pos>(*entity).map(|c| *c),
+            loaded.component::<Pos>(*entity).map(|c| *c)
+        );
+        assert_eq!(
+            world.component::<Rot>(*entity).map(|c| *c),
+            loaded.component::<Rot>(*entity).map(|c| *c)
+        );
+        assert_eq!(
+            world.component::<Target>(*entity).map(|c| *c),
+            loaded.component::<Target>(*entity).map(|c| *c)
+        );
+        assert_eq!(world.tag::<Model>(*entity), loaded.tag::<Model>(*entity));
+        assert_eq!(world.tag::<Static>(*entity), loaded.tag::<Static>(*entity));
+    }
+
+    // the restored IDs are not allocated again
+    let created = loaded.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    assert!(!entities.contains(&created));
+}
+
+#[test]
+fn read_into_live_world() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = populate(&mut world);
+
+    let registry = registry();
+    let mut data = Vec::new();
+    world.write_binary(&registry, &mut data).unwrap();
+
+    let mut mappers = EntityMappers::default();
+    mappers.register::<Target>();
+
+    // load the same data twice into the world it was written from
+    let first = world
+        .read_binary_into(&registry, &mappers, data.as_slice())
+        .unwrap();
+    let second = world
+        .read_binary_into(&registry, &mappers, data.as_slice())
+        .unwrap();
+
+    for map in &[&first, &second] {
+        assert_eq!(entities.len(), map.len());
+        for entity in entities.iter() {
+            let loaded = map[entity];
+            assert!(!entities.contains(&loaded));
+            assert_eq!(
+                world.component::<Pos>(*entity).map(|c| *c),
+                world.component::<Pos>(loaded).map(|c| *c)
+            );
+            assert_eq!(world.tag::<Model>(*entity), world.tag::<Model>(loaded));
+            if let Some(target) = world.component::<Target>(loaded) {
+                assert_eq!(map[&entities[0]], target.0);
+            }
+        }
+    }
+    assert!(first.values().all(|e| !second.values().any(|f| e == f)));
+    assert_eq!(
+        entities.len() * 3,
+        Read::<Pos>::query().iter_entities(&world).count()
+    );
+}
+
+#[test]
+fn read_into_invalid_data() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let registry = registry();
+    let mut data = Vec::new();
+    world.write_binary(&registry, &mut data).unwrap();
+    data.truncate(data.len() - 1);
+
+    let mut target = universe.create_world();
+    assert!(target
+        .read_binary_into(&registry, &EntityMappers::default(), data.as_slice())
+        .is_err());
+    assert_eq!(0, Read::<Pos>::query().iter(&target).count());
+}
+
+#[test]
+fn write_unregistered_type() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let mut registry = PodRegistry::default();
+    unsafe {
+        registry
+            .register_component::<Pos>("pos")
+            .register_tag::<Model>("model")
+            .register_tag::<Static>("static");
+    }
+
+    let mut data = Vec::new();
+    match world.write_binary(&registry, &mut data) {
+        Err(BinaryError::UnregisteredComponent(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(data.is_empty());
+}
+
+#[test]
+fn read_unknown_type() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let mut data = Vec::new();
+    world.write_binary(&registry(), &mut data).unwrap();
+
+    let mut partial = PodRegistry::default();
+    unsafe {
+        partial
+            .register_component::<Pos>("pos")
+            .register_component::<Rot>("rot")
+            .register_tag::<Model>("model")
+            .register_tag::<Static>("static");
+    }
+
+    let load_universe = Universe::new(None);
+    match World::read_binary(&load_universe, &partial, data.as_slice()) {
+        Err(BinaryError::UnknownType(id)) => assert_eq!(StableTypeId::from_name("target"), id),
+        _ => panic!("expected unknown type"),
+    }
+}
+
+#[test]
+fn read_changed_size() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Pos(0., 0., 0.),)]);
+
+    let mut data = Vec::new();
+    world.write_binary(&registry(), &mut data).unwrap();
+
+    let mut changed = PodRegistry::default();
+    unsafe { changed.register_component::<Model>("pos") };
+
+    let load_universe = Universe::new(None);
+    match World::read_binary(&load_universe, &changed, data.as_slice()) {
+        Err(BinaryError::InvalidData(_)) => {}
+        _ => panic!("expected invalid data"),
+    }
+}
+
+#[test]
+fn read_truncated() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let registry = registry();
+    let mut data = Vec::new();
+    world.write_binary(&registry, &mut data).unwrap();
+
+    for len in &[0, 3, 20, data.len() / 2, data.len() - 1] {
+        let load_universe = Universe::new(None);
+        assert!(World::read_binary(&load_universe, &registry, &data[..*len]).is_err());
+    }
+}
+
+#[test]
+fn read_ids_in_use() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let registry = registry();
+    let mut data = Vec::new();
+    world.write_binary(&registry, &mut data).unwrap();
+
+    match World::read_binary(&universe, &registry, data.as_slice()) {
+        Err(BinaryError::EntityIdsInUse) => {}
+        _ => panic!("expected entity IDs in use"),
+    }
+}
+
+#[test]
+fn read_duplicate_entity_index() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from((), vec![(Pos(0., 0., 0.),), (Pos(1., 0., 0.),)])
+        .to_vec();
+
+    let registry = registry();
+    let mut data = Vec::new();
+    world.write_binary(&registry, &mut data).unwrap();
+
+    // give the second entity the first entity's index, with a different version
+    let id = |entity: Entity| {
+        entity
+            .to_string()
+            .split('#')
+            .flat_map(|v| v.parse::<u32>().unwrap().to_ne_bytes().to_vec())
+            .collect::<Vec<_>>()
+    };
+    let (first, second) = (id(entities[0]), id(entities[1]));
+    let start = data
+        .windows(16)
+        .position(|w| w[..8] == first[..] && w[8..] == second[..])
+        .unwrap();
+    data[start + 8..start + 12].copy_from_slice(&first[..4]);
+    data[start + 12..start + 16].copy_from_slice(&0u32.to_ne_bytes());
+
+    let load_universe = Universe::new(None);
+    match World::read_binary(&load_universe, &registry, data.as_slice()) {
+        Err(BinaryError::InvalidData(_)) => {}
+        _ => panic!("expected invalid data"),
+    }
+}
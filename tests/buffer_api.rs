@@ -0,0 +1,237 @@
+mod common;
+
+use common::{Counted, Counts};
+use legion::prelude::*;
+use legion::storage::CloneRegistry;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Vel(f32, f32, f32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Model(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Vel {}
+    impl DefaultComponentImpl for Model {}
+}
+
+fn prev(world: &mut World) -> Vec<(Pos, Pos)> {
+    let mut query = <(Read<Pos>, Read<Prev<Pos>>)>::query();
+    query
+        .iter(world)
+        .map(|(pos, prev)| (*pos, **prev))
+        .collect()
+}
+
+#[test]
+fn advance_saves_values() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.buffer::<Pos>();
+    world.insert_from((), vec![(Pos(1., 2., 3.), Vel(1., 1., 1.))]);
+
+    // inserted entities start with their current values
+    assert_eq!(vec![(Pos(1., 2., 3.), Pos(1., 2., 3.))], prev(&mut world));
+
+    let mut query = <(Write<Pos>, Read<Vel>)>::query();
+    for (mut pos, vel) in query.iter(&mut world) {
+        pos.0 += vel.0;
+    }
+    assert_eq!(vec![(Pos(2., 2., 3.), Pos(1., 2., 3.))], prev(&mut world));
+
+    world.advance();
+    assert_eq!(vec![(Pos(2., 2., 3.), Pos(2., 2., 3.))], prev(&mut world));
+
+    for (mut pos, vel) in query.iter(&mut world) {
+        pos.0 += vel.0;
+    }
+    assert_eq!(vec![(Pos(3., 2., 3.), Pos(2., 2., 3.))], prev(&mut world));
+}
+
+#[test]
+fn unbuffered_components_have_no_previous_values() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.buffer::<Pos>();
+    let entity = world.insert_from((), vec![(Pos(1., 2., 3.), Vel(1., 1., 1.))])[0];
+
+    let mut query = <Read<Prev<Vel>>>::query();
+    assert_eq!(0, query.iter(&mut world).count());
+    assert!(world.component::<Prev<Vel>>(entity).is_none());
+    assert_eq!(
+        Pos(1., 2., 3.),
+        **world.component::<Prev<Pos>>(entity).unwrap()
+    );
+}
+
+#[test]
+fn buffer_existing_entities() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (Model(1),),
+            (0..1000)
+                .map(|i| (Pos(i as f32, 0., 0.),))
+                .collect::<Vec<_>>(),
+        )
+        .to_vec();
+
+    world.buffer::<Pos>();
+    for (i, entity) in entities.iter().enumerate() {
+        assert_eq!(
+            Pos(i as f32, 0., 0.),
+            **world.component::<Prev<Pos>>(*entity).unwrap()
+        );
+        assert_eq!(Model(1), *world.tag::<Model>(*entity).unwrap());
+    }
+}
+
+#[test]
+fn previous_values_follow_entities() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.buffer::<Pos>();
+    let entities = world
+        .insert_from(
+            (),
+            vec![(Pos(1., 0., 0.),), (Pos(2., 0., 0.),), (Pos(3., 0., 0.),)],
+        )
+        .to_vec();
+    world.advance();
+    for entity in entities.iter() {
+        world.component_mut::<Pos>(*entity).unwrap().1 = 1.;
+    }
+
+    // the last entity is swapped into the deleted entity's place
+    world.delete(entities[0]);
+    assert_eq!(
+        Pos(3., 0., 0.),
+        **world.component::<Prev<Pos>>(entities[2]).unwrap()
+    );
+
+    // entities which are moved to another archetype keep their previous values
+    world.add_component_where(component::<Pos>(), |_| Vel(0., 0., 0.));
+    assert_eq!(
+        Pos(2., 0., 0.),
+        **world.component::<Prev<Pos>>(entities[1]).unwrap()
+    );
+    world.mutate_entity(entities[2], |e| e.add_component(Model(1)));
+    assert_eq!(
+        Pos(3., 0., 0.),
+        **world.component::<Prev<Pos>>(entities[2]).unwrap()
+    );
+    let mut other_world = universe.create_world();
+    other_world.buffer::<Pos>();
+    world.move_entity(entities[1], &mut other_world);
+    assert_eq!(
+        Pos(2., 0., 0.),
+        **other_world.component::<Prev<Pos>>(entities[1]).unwrap()
+    );
+
+    // components added to an entity start with their current values
+    let other = world.insert_from((), vec![(Vel(0., 0., 0.),)])[0];
+    world.add_component_where(component::<Vel>(), |_| Pos(4., 0., 0.));
+    assert_eq!(
+        Pos(4., 0., 0.),
+        **world.component::<Prev<Pos>>(other).unwrap()
+    );
+
+    // entities merged from another world start with their current values
+    let mut loaded = universe.create_world();
+    let merged = loaded.insert_from((), vec![(Pos(5., 0., 0.),)])[0];
+    world.merge(loaded);
+    assert_eq!(
+        Pos(5., 0., 0.),
+        **world.component::<Prev<Pos>>(merged).unwrap()
+    );
+}
+
+#[test]
+fn buffered_values_are_cloned_and_dropped() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Arc::new(Counts::default());
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.buffer::<Counted>();
+    let entity = world.insert_from((), vec![(Counted(1, counts.clone()),)])[0];
+    assert_eq!(1, counts.clones());
+
+    world.component_mut::<Counted>(entity).unwrap().0 = 2;
+    world.advance();
+    assert_eq!(2, counts.clones());
+    assert_eq!(1, counts.drops());
+    assert_eq!(2, world.component::<Prev<Counted>>(entity).unwrap().0);
+
+    world.delete(entity);
+    assert_eq!(3, counts.drops());
+}
+
+#[test]
+fn panicking_clone_keeps_previous_values() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Arc::new(Counts::default());
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.buffer::<Counted>();
+    let entities = world
+        .insert_from(
+            (),
+            (1..4)
+                .map(|i| (Counted(i, counts.clone()),))
+                .collect::<Vec<_>>(),
+        )
+        .to_vec();
+    assert_eq!(3, counts.clones());
+
+    world.component_mut::<Counted>(entities[1]).unwrap().0 = u32::MAX;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| world.advance()));
+    assert!(result.is_err());
+    for (i, entity) in entities.iter().enumerate() {
+        assert_eq!(
+            i as u32 + 1,
+            world.component::<Prev<Counted>>(*entity).unwrap().0
+        );
+    }
+
+    // the clone made before the panic is leaked, but nothing is dropped twice
+    drop(world);
+    assert_eq!(4, counts.clones());
+    assert_eq!(6, counts.drops());
+}
+
+#[test]
+fn snapshots_keep_previous_values() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.buffer::<Pos>();
+    let entity = world.insert_from((), vec![(Pos(1., 0., 0.),)])[0];
+
+    let mut registry = CloneRegistry::default();
+    registry.register::<Pos>();
+    let snapshot = world.snapshot(&registry).unwrap();
+
+    *world.component_mut::<Pos>(entity).unwrap() = Pos(2., 0., 0.);
+    world.advance();
+    world.restore(&snapshot).unwrap();
+
+    assert_eq!(vec![(Pos(1., 0., 0.), Pos(1., 0., 0.))], prev(&mut world));
+}
@@ -0,0 +1,71 @@
+//! Fixtures shared by the integration tests.
+//!
+//! Each test crate uses only some of these, so unused items are allowed.
+#![allow(dead_code)]
+
+use legion::prelude::*;
+use legion::{EntityMap, EntityMappers, MapEntities};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// counts how many times it has been cloned and dropped, and panics when a value of
+// `u32::MAX` is cloned
+#[derive(Debug)]
+pub struct Counted(pub u32, pub Arc<Counts>);
+
+#[derive(Debug, Default)]
+pub struct Counts {
+    clones: AtomicUsize,
+    drops: AtomicUsize,
+}
+
+impl Counts {
+    pub fn clones(&self) -> usize {
+        self.clones.load(Ordering::SeqCst)
+    }
+
+    pub fn drops(&self) -> usize {
+        self.drops.load(Ordering::SeqCst)
+    }
+}
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        assert_ne!(u32::MAX, self.0, "clone failed");
+        self.1.clones.fetch_add(1, Ordering::SeqCst);
+        Counted(self.0, self.1.clone())
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.1.drops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// refers to another entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Target(pub Entity);
+
+impl MapEntities for Target {
+    fn map_entities(&mut self, map: &EntityMap) {
+        if let Some(entity) = map.get(&self.0) {
+            self.0 = *entity;
+        }
+    }
+}
+
+pub fn mappers() -> EntityMappers {
+    let mut mappers = EntityMappers::default();
+    mappers.register::<Target>();
+    mappers
+}
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Counted {}
+    impl DefaultComponentImpl for Target {}
+}
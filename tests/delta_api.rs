@@ -0,0 +1,297 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::{mappers, Target};
+use legion::delta::*;
+use legion::prelude::*;
+use legion::serialize::SerializeRegistry;
+use legion::storage::CloneRegistry;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Rot(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Unregistered(u32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Model(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Rot {}
+    impl DefaultComponentImpl for Unregistered {}
+    impl DefaultComponentImpl for Model {}
+}
+
+fn registry() -> SerializeRegistry {
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_component::<Rot>("rot")
+        .register_component::<Target>("target")
+        .register_tag::<Model>("model");
+    registry
+}
+
+fn sync(
+    encoder: &mut DeltaEncoder,
+    decoder: &mut DeltaDecoder,
+    server: &World,
+    client: &mut World,
+) -> serde_json::Value {
+    let registry = registry();
+    let delta = encoder.encode(server, &registry).unwrap();
+    let json = serde_json::to_string(&delta).unwrap();
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    decoder
+        .apply(client, &registry, &mappers(), &mut deserializer)
+        .unwrap();
+
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn initial_sync() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let entities = server
+        .insert_from(
+            (Model(5),),
+            vec![
+                (Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3)),
+                (Pos(4., 5., 6.), Rot(0.4, 0.5, 0.6)),
+            ],
+        )
+        .to_vec();
+
+    let mut client = universe.create_world();
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    for (entity, pos) in entities.iter().zip(&[Pos(1., 2., 3.), Pos(4., 5., 6.)]) {
+        let local = decoder.entity(*entity).unwrap();
+        assert_eq!(*pos, *client.component::<Pos>(local).unwrap());
+        assert_eq!(Model(5), *client.tag::<Model>(local).unwrap());
+    }
+    assert_eq!(2, decoder.entities().len());
+}
+
+#[test]
+fn unchanged_world_is_empty() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    server.insert_from((Model(5),), vec![(Pos(1., 2., 3.),)]);
+
+    let registry = registry();
+    let mut encoder = DeltaEncoder::new();
+    assert!(!encoder.encode(&server, &registry).unwrap().is_empty());
+    assert!(encoder.encode(&server, &registry).unwrap().is_empty());
+}
+
+#[test]
+fn changed_columns_only() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let entity = server.insert_from((Model(5),), vec![(Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3))])[0];
+
+    let mut client = universe.create_world();
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    *server.component_mut::<Pos>(entity).unwrap() = Pos(7., 8., 9.);
+    let delta = sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    let chunks = delta["chunks"].as_array().unwrap();
+    assert_eq!(1, chunks.len());
+    assert!(chunks[0]["tags"].is_null());
+    assert_eq!(1, chunks[0]["components"].as_array().unwrap().len());
+
+    let local = decoder.entity(entity).unwrap();
+    assert_eq!(Pos(7., 8., 9.), *client.component::<Pos>(local).unwrap());
+    assert_eq!(Rot(0.1, 0.2, 0.3), *client.component::<Rot>(local).unwrap());
+}
+
+#[test]
+fn create_and_delete() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let entities = server
+        .insert_from((), vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+
+    let mut client = universe.create_world();
+    let own = client.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    let deleted = decoder.entity(entities[0]).unwrap();
+    server.delete(entities[0]);
+    let created = server.insert_from((Model(1),), vec![(Pos(7., 8., 9.),)])[0];
+    let delta = sync(&mut encoder, &mut decoder, &server, &mut client);
+    assert_eq!(1, delta["deleted"].as_array().unwrap().len());
+
+    assert!(!client.is_alive(&deleted));
+    assert_eq!(None, decoder.entity(entities[0]));
+
+    let local = decoder.entity(entities[1]).unwrap();
+    assert_eq!(Pos(4., 5., 6.), *client.component::<Pos>(local).unwrap());
+    let local = decoder.entity(created).unwrap();
+    assert_eq!(Pos(7., 8., 9.), *client.component::<Pos>(local).unwrap());
+    assert_eq!(Model(1), *client.tag::<Model>(local).unwrap());
+
+    // the client's own entities are untouched
+    assert_eq!(Pos(0., 0., 0.), *client.component::<Pos>(own).unwrap());
+}
+
+#[test]
+fn structural_change() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let entity = server.insert_from((Model(5),), vec![(Pos(1., 2., 3.),)])[0];
+
+    let mut client = universe.create_world();
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+    let local = decoder.entity(entity).unwrap();
+
+    server.mutate_entity(entity, |e| {
+        e.add_component(Rot(0.1, 0.2, 0.3));
+        e.set_tag(Model(6));
+    });
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    assert_eq!(Some(local), decoder.entity(entity));
+    assert_eq!(Pos(1., 2., 3.), *client.component::<Pos>(local).unwrap());
+    assert_eq!(Rot(0.1, 0.2, 0.3), *client.component::<Rot>(local).unwrap());
+    assert_eq!(Model(6), *client.tag::<Model>(local).unwrap());
+
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(1, query.iter_entities(&mut client).count());
+}
+
+#[test]
+fn reset_resends_world() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let entity = server.insert_from((), vec![(Pos(1., 2., 3.),)])[0];
+
+    let mut client = universe.create_world();
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    encoder.reset();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    let local = decoder.entity(entity).unwrap();
+    assert_eq!(Pos(1., 2., 3.), *client.component::<Pos>(local).unwrap());
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(1, query.iter_entities(&mut client).count());
+}
+
+#[test]
+fn reused_chunk_ids_are_resent() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let mut clone_registry = CloneRegistry::default();
+    clone_registry.register::<Pos>().register::<Rot>();
+    let snapshot = server.snapshot(&clone_registry).unwrap();
+
+    let mut client = universe.create_world();
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    server.insert_from((), vec![(Pos(1., 2., 3.),)]);
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    // a chunk with a different layout may be given the ID of a chunk the client has seen
+    server.restore(&snapshot).unwrap();
+    let entity = server.insert_from((), vec![(Rot(0.1, 0.2, 0.3),)])[0];
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    let local = decoder.entity(entity).unwrap();
+    assert_eq!(Rot(0.1, 0.2, 0.3), *client.component::<Rot>(local).unwrap());
+    assert_eq!(0, <Read<Pos>>::query().iter(&client).count());
+    assert_eq!(1, <Read<Rot>>::query().iter(&client).count());
+}
+
+#[test]
+fn entity_references_are_remapped() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let a = server.insert_from((), vec![(Pos(1., 2., 3.),)])[0];
+    let b = server.insert_from((), vec![(Target(a),)])[0];
+
+    let mut client = universe.create_world();
+    client.insert_from((), vec![(Pos(0., 0., 0.),), (Pos(0., 0., 0.),)]);
+    let mut encoder = DeltaEncoder::new();
+    let mut decoder = DeltaDecoder::new();
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    let local_a = decoder.entity(a).unwrap();
+    let local_b = decoder.entity(b).unwrap();
+    assert_ne!(a, local_a);
+    assert_eq!(
+        Target(local_a),
+        *client.component::<Target>(local_b).unwrap()
+    );
+
+    // references in partial updates are remapped too
+    let c = server.insert_from((Model(1),), vec![(Pos(4., 5., 6.),)])[0];
+    *server.component_mut::<Target>(b).unwrap() = Target(c);
+    sync(&mut encoder, &mut decoder, &server, &mut client);
+
+    let local_c = decoder.entity(c).unwrap();
+    assert_eq!(
+        Target(local_c),
+        *client.component::<Target>(local_b).unwrap()
+    );
+}
+
+#[test]
+fn unregistered_types_are_errors() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    server.insert_from((), vec![(Pos(1., 2., 3.),)]);
+    server.insert_from((), vec![(Unregistered(1),)]);
+
+    let registry = registry();
+    let mut encoder = DeltaEncoder::new();
+    assert_eq!(
+        Some(DeltaError::UnregisteredComponent(
+            <Unregistered as legion::Component>::type_id()
+        )),
+        encoder.encode(&server, &registry).err()
+    );
+}
+
+#[test]
+fn unknown_entities_are_errors() {
+    let universe = Universe::new(None);
+    let mut server = universe.create_world();
+    let entity = server.insert_from((), vec![(Pos(1., 2., 3.),)])[0];
+
+    let registry = registry();
+    let mut encoder = DeltaEncoder::new();
+    encoder.encode(&server, &registry).unwrap();
+    *server.component_mut::<Pos>(entity).unwrap() = Pos(4., 5., 6.);
+    let json = serde_json::to_string(&encoder.encode(&server, &registry).unwrap()).unwrap();
+
+    // the client missed the first delta
+    let mut client = universe.create_world();
+    let mut decoder = DeltaDecoder::new();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert!(decoder
+        .apply(&mut client, &registry, &mappers(), &mut deserializer)
+        .is_err());
+    assert_eq!(None, decoder.entity(entity));
+}
@@ -0,0 +1,387 @@
+mod common;
+
+use common::{mappers, Target};
+use legion::journal::*;
+use legion::prelude::*;
+use legion::storage::CloneRegistry;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Vel(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Unregistered(u32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Model(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Vel {}
+    impl DefaultComponentImpl for Unregistered {}
+    impl DefaultComponentImpl for Model {}
+}
+
+fn registry() -> CloneRegistry {
+    let mut registry = CloneRegistry::default();
+    registry
+        .register::<Pos>()
+        .register::<Vel>()
+        .register::<Target>();
+    registry
+}
+
+#[test]
+fn record_operations() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Pos(0., 0., 0.),)]);
+    world.record(Journal::new(&registry()));
+
+    let entities = world
+        .insert_from((Model(1),), vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+    *world.component_mut::<Pos>(entities[0]).unwrap() = Pos(7., 8., 9.);
+    world.mutate_entity(entities[1], |e| e.add_component(Vel(1., 1., 1.)));
+    world.delete(entities[0]);
+
+    let journal = world.take_journal().unwrap();
+    let recorded = journal
+        .entries()
+        .iter()
+        .map(|entry| {
+            let kind = match entry {
+                JournalEntry::Insert { .. } => "insert",
+                JournalEntry::Mutate { .. } => "mutate",
+                JournalEntry::Delete { .. } => "delete",
+                JournalEntry::Write { .. } => "write",
+            };
+            (kind, entry.entity())
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            ("insert", entities[0]),
+            ("insert", entities[1]),
+            ("write", entities[0]),
+            ("mutate", entities[1]),
+            ("delete", entities[0]),
+        ],
+        recorded
+    );
+
+    // recording has stopped
+    world.insert_from((), vec![(Pos(0., 0., 0.),)]);
+    assert!(world.journal().is_none());
+}
+
+#[test]
+fn replay_reproduces_state() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let entities = world
+        .insert_from(
+            (Model(1),),
+            vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),), (Pos(7., 8., 9.),)],
+        )
+        .to_vec();
+    world.component_mut::<Pos>(entities[0]).unwrap().0 = 10.;
+    world.mutate_entity(entities[1], |e| {
+        e.add_component(Vel(1., 1., 1.));
+        e.set_tag(Model(2));
+    });
+    world.delete(entities[2]);
+    world.component_mut::<Vel>(entities[1]).unwrap().1 = 5.;
+
+    let journal = world.take_journal().unwrap();
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    replay.run(&mut repro);
+    assert_eq!(0, replay.remaining());
+
+    let a = replay.entity(entities[0]).unwrap();
+    let b = replay.entity(entities[1]).unwrap();
+    assert_eq!(None, replay.entity(entities[2]));
+
+    assert_eq!(Pos(10., 2., 3.), *repro.component::<Pos>(a).unwrap());
+    assert_eq!(Model(1), *repro.tag::<Model>(a).unwrap());
+    assert_eq!(Pos(4., 5., 6.), *repro.component::<Pos>(b).unwrap());
+    assert_eq!(Vel(1., 5., 1.), *repro.component::<Vel>(b).unwrap());
+    assert_eq!(Model(2), *repro.tag::<Model>(b).unwrap());
+
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(2, query.iter(&mut repro).count());
+}
+
+#[test]
+fn replay_removing_every_component() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let entities = world
+        .insert_from((), vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+    world.mutate_entity(entities[0], |e| {
+        e.remove_component::<Pos>();
+    });
+    world.delete(entities[0]);
+    assert_eq!(
+        Pos(4., 5., 6.),
+        *world.component::<Pos>(entities[1]).unwrap()
+    );
+
+    let journal = world.take_journal().unwrap();
+    match &journal.entries()[2] {
+        JournalEntry::Mutate { components, .. } => assert!(components.is_empty()),
+        _ => panic!("expected a mutation"),
+    }
+
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    replay.run(&mut repro);
+
+    assert_eq!(None, replay.entity(entities[0]));
+    let b = replay.entity(entities[1]).unwrap();
+    assert_eq!(Pos(4., 5., 6.), *repro.component::<Pos>(b).unwrap());
+
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(1, query.iter(&mut repro).count());
+}
+
+#[test]
+fn replay_bulk_operations() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let entities = world
+        .insert_from((Model(1),), vec![(Pos(1., 0., 0.),), (Pos(2., 0., 0.),)])
+        .to_vec();
+    let deleted = world.insert_from((Model(2),), vec![(Pos(3., 0., 0.),)])[0];
+    let split = world.insert_from((Model(4),), vec![(Pos(4., 0., 0.),)])[0];
+    let moved = world.insert_from((Model(5),), vec![(Pos(5., 0., 0.),)])[0];
+
+    world.add_component_where(tag_value(&Model(1)), |_| Vel(1., 1., 1.));
+    world.delete_where(tag_value(&Model(2)));
+
+    let mut other = universe.create_world();
+    let merged = other.insert_from((Model(3),), vec![(Pos(6., 0., 0.),)])[0];
+    world.merge(other);
+
+    world.remove_component_where::<Pos, _>(tag_value(&Model(1)));
+    let section = world.split_off(tag_value(&Model(4)));
+    assert!(section.is_alive(&split));
+    let mut elsewhere = universe.create_world();
+    assert!(world.move_entity(moved, &mut elsewhere));
+
+    let journal = world.take_journal().unwrap();
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    replay.run(&mut repro);
+
+    for entity in &entities {
+        let replayed = replay.entity(*entity).unwrap();
+        assert_eq!(None, repro.component::<Pos>(replayed));
+        assert_eq!(Vel(1., 1., 1.), *repro.component::<Vel>(replayed).unwrap());
+        assert_eq!(Model(1), *repro.tag::<Model>(replayed).unwrap());
+    }
+    assert_eq!(None, replay.entity(deleted));
+    assert_eq!(None, replay.entity(split));
+    assert_eq!(None, replay.entity(moved));
+
+    let merged = replay.entity(merged).unwrap();
+    assert_eq!(Pos(6., 0., 0.), *repro.component::<Pos>(merged).unwrap());
+    assert_eq!(Model(3), *repro.tag::<Model>(merged).unwrap());
+
+    let mut positions = <Read<Pos>>::query();
+    assert_eq!(1, positions.iter(&mut repro).count());
+    let mut velocities = <Read<Vel>>::query();
+    assert_eq!(2, velocities.iter(&mut repro).count());
+}
+
+#[test]
+fn replay_clear() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    world.insert_from((), vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)]);
+    world.clear();
+    let entity = world.insert_from((), vec![(Pos(7., 8., 9.),)])[0];
+
+    let journal = world.take_journal().unwrap();
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    replay.run(&mut repro);
+
+    assert_eq!(1, replay.entities().len());
+    let replayed = replay.entity(entity).unwrap();
+    assert_eq!(Pos(7., 8., 9.), *repro.component::<Pos>(replayed).unwrap());
+
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(1, query.iter(&mut repro).count());
+}
+
+#[test]
+fn step_through_replay() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let entity = world.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    for i in 1..4 {
+        world.component_mut::<Pos>(entity).unwrap().0 = i as f32;
+    }
+
+    let journal = world.take_journal().unwrap();
+    assert_eq!(4, journal.len());
+
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    for i in 0..4 {
+        assert_eq!(i, replay.position());
+        assert_eq!(entity, replay.step(&mut repro).unwrap().entity());
+
+        let replayed = replay.entity(entity).unwrap();
+        assert_eq!(i as f32, repro.component::<Pos>(replayed).unwrap().0);
+    }
+    assert!(replay.step(&mut repro).is_none());
+}
+
+#[test]
+fn replayed_writes_mark_only_the_written_entity() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let entities = world
+        .insert_from(
+            (Model(1),),
+            vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),), (Pos(7., 8., 9.),)],
+        )
+        .to_vec();
+    world.component_mut::<Pos>(entities[1]).unwrap().0 = 10.;
+
+    let journal = world.take_journal().unwrap();
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    for _ in 0..entities.len() {
+        replay.step(&mut repro).unwrap();
+    }
+
+    let mut query = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(3, query.iter(&repro).count());
+
+    replay.step(&mut repro).unwrap();
+    let changed = query
+        .iter_entities(&repro)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![replay.entity(entities[1]).unwrap()], changed);
+}
+
+#[test]
+fn replay_remaps_references() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let a = world.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    let b = world.insert_from((), vec![(Target(a),)])[0];
+    let c = world.insert_from((), vec![(Pos(1., 1., 1.),)])[0];
+    *world.component_mut::<Target>(b).unwrap() = Target(c);
+
+    let journal = world.take_journal().unwrap();
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+
+    replay.step(&mut repro);
+    replay.step(&mut repro);
+    let local_a = replay.entity(a).unwrap();
+    let local_b = replay.entity(b).unwrap();
+    assert_eq!(
+        Target(local_a),
+        *repro.component::<Target>(local_b).unwrap()
+    );
+
+    replay.run(&mut repro);
+    let local_c = replay.entity(c).unwrap();
+    assert_eq!(
+        Target(local_c),
+        *repro.component::<Target>(local_b).unwrap()
+    );
+}
+
+#[test]
+fn append_journals() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let registry = registry();
+
+    world.record(Journal::new(&registry));
+    let entity = world.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    let mut journal = world.take_journal().unwrap();
+
+    world.record(Journal::new(&registry));
+    *world.component_mut::<Pos>(entity).unwrap() = Pos(1., 1., 1.);
+    journal.append(world.take_journal().unwrap());
+    assert_eq!(2, journal.len());
+
+    let mappers = mappers();
+    let mut replay = journal.replay(&mappers);
+    let mut repro = universe.create_world();
+    replay.run(&mut repro);
+
+    let replayed = replay.entity(entity).unwrap();
+    assert_eq!(Pos(1., 1., 1.), *repro.component::<Pos>(replayed).unwrap());
+}
+
+#[test]
+fn unregistered_components_are_skipped() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.record(Journal::new(&registry()));
+
+    let skipped = world.insert_from((), vec![(Pos(0., 0., 0.), Unregistered(1))])[0];
+    let entity = world.insert_from((), vec![(Pos(1., 1., 1.),)])[0];
+    world.component_mut::<Unregistered>(skipped).unwrap().0 = 2;
+    world.mutate_entity(skipped, |e| e.add_component(Vel(1., 1., 1.)));
+    assert!(world.component::<Unregistered>(skipped).is_some());
+
+    let journal = world.take_journal().unwrap();
+    let recorded = journal
+        .entries()
+        .iter()
+        .map(|entry| entry.entity())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entity], recorded);
+}
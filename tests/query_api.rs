@@ -564,3 +564,429 @@ fn query_on_changed_self_changes() {
 
     assert_eq!(components.len(), count);
 }
+
+#[test]
+fn query_on_changed_single_entity() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..1000).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut query = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(1000, query.iter(&world).count());
+
+    *world.component_mut::<Pos>(entities[500]).unwrap() = Pos(0., 1., 0.);
+
+    let changed = query
+        .iter_entities(&world)
+        .map(|(e, pos)| (e, *pos))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(entities[500], Pos(0., 1., 0.))], changed);
+    assert_eq!(0, query.iter(&world).count());
+
+    // the tick moves with the entity when another entity is deleted
+    *world.component_mut::<Pos>(entities[999]).unwrap() = Pos(0., 2., 0.);
+    world.delete(entities[0]);
+    let changed = query
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[999]], changed);
+}
+
+#[test]
+fn query_on_changed_chunk_iterators() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..1000).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut query = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(1000, query.iter(&world).count());
+
+    world.component_mut::<Pos>(entities[70]).unwrap().1 = 1.;
+    world.component_mut::<Pos>(entities[500]).unwrap().1 = 1.;
+
+    let mut matching = Vec::new();
+    for mut chunk in query.iter_chunks(&world) {
+        // `iter` yields every entity in the chunk, while `iter_matching` skips unchanged entities
+        let len = chunk.entities().len();
+        assert_eq!(len, chunk.iter().count());
+        matching.extend(chunk.iter_matching().map(|pos| pos.0));
+
+        let matches = (0..len).filter(|i| chunk.is_match(*i)).count();
+        assert_eq!(matches, chunk.iter_matching().count());
+    }
+    assert_eq!(vec![70., 500.], matching);
+}
+
+#[test]
+fn query_on_changed_tracked() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut changed = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(10, changed.iter(&world).count());
+
+    // borrowing without writing does not mark any entity as changed
+    let mut tracked = Tracked::<Pos>::query();
+    for pos in tracked.iter(&world) {
+        assert_eq!(0., pos.1);
+    }
+    assert_eq!(0, changed.iter(&world).count());
+
+    // only the entities which were written are marked
+    for mut pos in tracked.iter(&world) {
+        if pos.0 as usize % 2 == 0 {
+            pos.1 = 1.;
+        }
+    }
+    let written = changed
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entities.iter().step_by(2).copied().collect::<Vec<_>>(),
+        written
+    );
+
+    // writing to another component type does not mark `Pos`
+    let mut query = <(Read<Pos>, Write<Rot>)>::query();
+    for (_, rot) in query.iter(&world) {
+        rot.0 = 1.;
+    }
+    assert_eq!(0, changed.iter(&world).count());
+
+    // `Write` marks every entity it yields
+    let mut query = Write::<Pos>::query();
+    assert_eq!(10, query.iter(&world).count());
+    assert_eq!(10, changed.iter(&world).count());
+}
+
+#[test]
+fn query_on_changed_combined() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut either = Read::<Pos>::query().filter(changed::<Pos>() | changed::<Rot>());
+    let mut both = Read::<Pos>::query().filter(changed::<Pos>() & changed::<Rot>());
+    either.iter(&world).count();
+    both.iter(&world).count();
+
+    world.component_mut::<Pos>(entities[1]).unwrap().1 = 1.;
+    world.component_mut::<Rot>(entities[1]).unwrap().1 = 1.;
+    world.component_mut::<Rot>(entities[2]).unwrap().1 = 1.;
+
+    let changed = either
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[1], entities[2]], changed);
+    let changed = both
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[1]], changed);
+}
+
+#[test]
+fn query_on_changed_negated_and_combined_with_tags() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Model(1),).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+    let others = world
+        .insert_from(
+            (Model(2),).as_tags(),
+            (0..2).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    let mut unchanged = Read::<Pos>::query().filter(!changed::<Pos>());
+    let mut either = Read::<Pos>::query().filter(changed::<Pos>() | tag_value(&Model(2)));
+    let mut both = Read::<Pos>::query().filter(changed::<Pos>() & tag_value(&Model(2)));
+    assert_eq!(0, unchanged.iter(&world).count());
+    assert_eq!(12, either.iter(&world).count());
+    assert_eq!(2, both.iter(&world).count());
+
+    world.component_mut::<Pos>(entities[3]).unwrap().1 = 1.;
+
+    let matched = unchanged
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(11, matched.len());
+    assert!(!matched.contains(&entities[3]));
+
+    let matched = either
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[3], others[0], others[1]], matched);
+
+    assert_eq!(0, both.iter(&world).count());
+}
+
+#[test]
+fn query_on_changed_shared_query() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    // two consumers share the query, each keeping the tick at which it last ran
+    let mut query = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(10, query.iter(&world).count());
+    let first = query.last_run();
+    query.set_last_run(None);
+    assert_eq!(10, query.iter(&world).count());
+    let second = query.last_run();
+    assert!(second.unwrap().1 > first.unwrap().1);
+
+    world.component_mut::<Pos>(entities[3]).unwrap().1 = 1.;
+    query.set_last_run(second);
+    assert_eq!(1, query.iter(&world).count());
+    let second = query.last_run();
+
+    world.component_mut::<Pos>(entities[5]).unwrap().1 = 1.;
+    query.set_last_run(first);
+    let changed = query
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[3], entities[5]], changed);
+
+    query.set_last_run(second);
+    let changed = query
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[5]], changed);
+    assert_eq!(query.last_run(), Some((world.id(), world.change_tick())));
+}
+
+#[test]
+fn query_on_changed_other_world() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world_a = universe.create_world();
+    let mut world_b = universe.create_world();
+    world_a.insert_from((), (0..10).map(|i| (Pos(i as f32, 0., 0.),)));
+    world_b.insert_from((), (0..5).map(|i| (Pos(i as f32, 0., 0.),)));
+
+    // advance the first world's tick well past the second's
+    let mut query = Read::<Pos>::query().filter(changed::<Pos>());
+    for _ in 0..10 {
+        query.iter(&world_a).count();
+    }
+    assert_eq!(0, query.iter(&world_a).count());
+
+    // a query which last ran on another world has not yet run on this one
+    assert_eq!(5, query.iter(&world_b).count());
+    assert_eq!(0, query.iter(&world_b).count());
+    assert_eq!(10, query.iter(&world_a).count());
+}
+
+#[test]
+fn query_on_changed_new_chunks() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    let mut query = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(10, query.iter(&world).count());
+    assert_eq!(0, query.iter(&world).count());
+
+    // entities inserted into a freed chunk are changed
+    for entity in entities {
+        world.delete(entity);
+    }
+    world.defrag(None);
+    world.insert_from((Static,).as_tags(), vec![(Pos(1., 0., 0.),)]);
+    assert_eq!(1, query.iter(&world).count());
+    assert_eq!(0, query.iter(&world).count());
+
+    // chunks merged from another world are changed
+    let mut other = universe.create_world();
+    other.insert_from((Static,).as_tags(), vec![(Pos(2., 0., 0.),)]);
+    let mut other_query = Read::<Pos>::query().filter(changed::<Pos>());
+    assert_eq!(1, other_query.iter(&other).count());
+    world.merge(other);
+    let changed = query.iter(&world).map(|pos| pos.0).collect::<Vec<_>>();
+    assert!(changed.contains(&2.));
+    assert_eq!(0, query.iter(&world).count());
+}
+
+#[test]
+fn query_on_added() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    let mut query = Read::<Pos>::query().filter(added::<Pos>());
+    assert_eq!(10, query.iter(&world).count());
+    assert_eq!(0, query.iter(&world).count());
+
+    // writes do not add components
+    *world.component_mut::<Pos>(entities[0]).unwrap() = Pos(1., 1., 1.);
+    assert_eq!(0, query.iter(&world).count());
+
+    let inserted = world.insert_from((Static,).as_tags(), vec![(Pos(10., 0., 0.),)])[0];
+    let matched = query
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![inserted], matched);
+    assert_eq!(0, query.iter(&world).count());
+}
+
+#[test]
+fn query_on_added_negated_and_combined_with_tags() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    world.insert_from(
+        (Model(1),).as_tags(),
+        (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+    );
+    let others = world
+        .insert_from(
+            (Model(2),).as_tags(),
+            (0..2).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    let mut not_added = Read::<Pos>::query().filter(!added::<Pos>());
+    let mut either = Read::<Pos>::query().filter(added::<Pos>() | tag_value(&Model(2)));
+    assert_eq!(0, not_added.iter(&world).count());
+    assert_eq!(12, either.iter(&world).count());
+
+    let inserted = world.insert_from((Model(1),).as_tags(), vec![(Pos(10., 0., 0.),)])[0];
+
+    let matched = not_added
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(12, matched.len());
+    assert!(!matched.contains(&inserted));
+
+    let matched = either
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![inserted, others[0], others[1]], matched);
+}
+
+#[test]
+fn query_on_added_mutate_and_merge() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    let mut pos_added = Read::<Pos>::query().filter(added::<Pos>());
+    let mut rot_added = Read::<Rot>::query().filter(added::<Rot>());
+    pos_added.iter(&world).count();
+    rot_added.iter(&world).count();
+
+    // only the component added by mutate_entity is added
+    world.mutate_entity(entities[3], |e| e.add_component(Rot(0., 0., 0.)));
+    assert_eq!(0, pos_added.iter(&world).count());
+    let matched = rot_added
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[3]], matched);
+
+    world.add_component_where(tag::<Static>(), |_| Rot(0., 0., 0.));
+    assert_eq!(0, pos_added.iter(&world).count());
+    assert_eq!(9, rot_added.iter(&world).count());
+
+    // merged entities are added, whether moved into existing chunks or not
+    let mut other = universe.create_world();
+    let merged = other
+        .insert_from(
+            (Static,).as_tags(),
+            vec![(Pos(10., 0., 0.), Rot(0., 0., 0.))],
+        )
+        .to_vec();
+    let mut other_query = Read::<Pos>::query().filter(added::<Pos>());
+    assert_eq!(1, other_query.iter(&other).count());
+    world.merge(other);
+    let matched = pos_added
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(merged, matched);
+
+    let mut other = universe.create_world();
+    let merged = other
+        .insert_from((Model(1),).as_tags(), vec![(Pos(11., 0., 0.),)])
+        .to_vec();
+    world.merge(other);
+    let matched = pos_added
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(merged, matched);
+}
@@ -0,0 +1,252 @@
+#![cfg(feature = "serde")]
+
+use legion::prelude::*;
+use legion::serialize::SerializeRegistry;
+use legion::{EntityMap, EntityMappers, MapEntities};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Name(String);
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Parent(Entity);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Model(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Name {}
+    impl DefaultComponentImpl for Parent {}
+    impl DefaultComponentImpl for Model {}
+}
+
+impl MapEntities for Parent {
+    fn map_entities(&mut self, map: &EntityMap) {
+        if let Some(entity) = map.get(&self.0) {
+            self.0 = *entity;
+        }
+    }
+}
+
+fn registry() -> SerializeRegistry {
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_component::<Name>("name")
+        .register_component::<Parent>("parent")
+        .register_tag::<Model>("model");
+    registry
+}
+
+fn mappers() -> EntityMappers {
+    let mut mappers = EntityMappers::default();
+    mappers.register::<Parent>();
+    mappers
+}
+
+fn entity(index: u32) -> Entity {
+    serde_json::from_value(serde_json::json!({ "index": index, "version": 0 })).unwrap()
+}
+
+const SCENE: &str = r#"{
+    "entities": [
+        {
+            "id": { "index": 1, "version": 0 },
+            "tags": { "model": 3 },
+            "components": { "pos": [0.0, 0.0, 0.0], "name": "root" }
+        },
+        {
+            "components": {
+                "parent": { "index": 1, "version": 0 },
+                "pos": [1.0, 0.0, 0.0],
+                "name": "child"
+            },
+            "id": { "index": 2, "version": 0 }
+        },
+        {
+            "components": { "pos": [2.0, 0.0, 0.0] }
+        }
+    ]
+}"#;
+
+#[test]
+fn load_json() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let existing = world.insert_from((), vec![(Parent(entity(1)),)])[0];
+
+    let mut deserializer = serde_json::Deserializer::from_str(SCENE);
+    let map = world
+        .load_scene(&registry(), &mappers(), &mut deserializer)
+        .unwrap();
+
+    assert_eq!(2, map.len());
+    let root = map[&entity(1)];
+    let child = map[&entity(2)];
+    assert_eq!(Some(&Model(3)), world.tag::<Model>(root));
+    assert_eq!(
+        Some(Name("root".to_owned())),
+        world.component::<Name>(root).map(|n| n.clone())
+    );
+    assert_eq!(None, world.tag::<Model>(child));
+    assert_eq!(
+        Some(Parent(root)),
+        world.component::<Parent>(child).map(|p| *p)
+    );
+    assert_eq!(
+        Some(Pos(1., 0., 0.)),
+        world.component::<Pos>(child).map(|p| *p)
+    );
+    assert_eq!(3, Read::<Pos>::query().iter(&world).count());
+
+    // references held by entities outside of the scene are not rewritten
+    assert_eq!(
+        Some(Parent(entity(1))),
+        world.component::<Parent>(existing).map(|p| *p)
+    );
+}
+
+#[test]
+fn load_ron() {
+    let scene = r#"(
+        entities: [
+            (
+                id: Entity(index: 7, version: 0),
+                tags: { "model": (1) },
+                components: { "pos": (1.0, 2.0, 3.0) },
+            ),
+            (
+                components: { "parent": (Entity(index: 7, version: 0)) },
+            ),
+        ],
+    )"#;
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut deserializer = ron::Deserializer::from_str(scene).unwrap();
+    let map = world
+        .load_scene(&registry(), &mappers(), &mut deserializer)
+        .unwrap();
+
+    let loaded = map[&entity(7)];
+    assert_eq!(
+        Some(Pos(1., 2., 3.)),
+        world.component::<Pos>(loaded).map(|p| *p)
+    );
+    let parents = Read::<Parent>::query()
+        .iter(&world)
+        .map(|p| *p)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![Parent(loaded)], parents);
+
+    // round trip through RON
+    let registry = registry();
+    let ron = ron::to_string(&world.serializable_scene(&registry, component::<Pos>())).unwrap();
+    let mut reloaded = universe.create_world();
+    let mut deserializer = ron::Deserializer::from_str(&ron).unwrap();
+    let map = reloaded
+        .load_scene(&registry, &mappers(), &mut deserializer)
+        .unwrap();
+    assert_eq!(
+        Some(Pos(1., 2., 3.)),
+        reloaded.component::<Pos>(map[&loaded]).map(|p| *p)
+    );
+    assert_eq!(Some(&Model(1)), reloaded.tag::<Model>(map[&loaded]));
+}
+
+#[test]
+fn export_filtered() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut deserializer = serde_json::Deserializer::from_str(SCENE);
+    let map = world
+        .load_scene(&registry(), &mappers(), &mut deserializer)
+        .unwrap();
+    world.insert_from((), vec![(Name("excluded".to_owned()),)]);
+
+    let registry = registry();
+    let json =
+        serde_json::to_string_pretty(&world.serializable_scene(&registry, component::<Pos>()))
+            .unwrap();
+
+    let mut loaded = universe.create_world();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let exported = loaded
+        .load_scene(&registry, &mappers(), &mut deserializer)
+        .unwrap();
+
+    assert_eq!(3, exported.len());
+    assert_eq!(3, Read::<Pos>::query().iter(&loaded).count());
+    assert_eq!(2, Read::<Name>::query().iter(&loaded).count());
+    for original in map.values() {
+        let copy = exported[original];
+        assert_eq!(
+            world.component::<Pos>(*original).map(|p| *p),
+            loaded.component::<Pos>(copy).map(|p| *p)
+        );
+        assert_eq!(world.tag::<Model>(*original), loaded.tag::<Model>(copy));
+    }
+
+    let child = exported[&map[&entity(2)]];
+    let root = exported[&map[&entity(1)]];
+    assert_eq!(
+        Some(Parent(root)),
+        loaded.component::<Parent>(child).map(|p| *p)
+    );
+}
+
+#[test]
+fn export_unregistered_type() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Pos(0., 0., 0.), Name("a".to_owned()))]);
+
+    let mut registry = SerializeRegistry::default();
+    registry.register_component::<Pos>("pos");
+    assert!(
+        serde_json::to_string(&world.serializable_scene(&registry, component::<Pos>())).is_err()
+    );
+}
+
+#[test]
+fn load_unknown_type() {
+    let scene = r#"{
+        "entities": [
+            { "components": { "pos": [0.0, 0.0, 0.0] } },
+            { "components": { "velocity": [0.0, 0.0, 0.0] } }
+        ]
+    }"#;
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut deserializer = serde_json::Deserializer::from_str(scene);
+    assert!(world
+        .load_scene(&registry(), &mappers(), &mut deserializer)
+        .is_err());
+
+    // nothing was inserted
+    assert_eq!(0, Read::<Pos>::query().iter(&world).count());
+}
+
+#[test]
+fn load_duplicate_id() {
+    let scene = r#"{
+        "entities": [
+            { "id": { "index": 1, "version": 0 }, "components": { "pos": [0.0, 0.0, 0.0] } },
+            { "id": { "index": 1, "version": 0 }, "components": { "pos": [1.0, 0.0, 0.0] } }
+        ]
+    }"#;
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut deserializer = serde_json::Deserializer::from_str(scene);
+    assert!(world
+        .load_scene(&registry(), &mappers(), &mut deserializer)
+        .is_err());
+}
@@ -0,0 +1,420 @@
+#![cfg(feature = "serde")]
+
+use bincode::Options;
+use legion::prelude::*;
+use legion::registry::StableTypeId;
+use legion::schema::*;
+use legion::serialize::SerializeRegistry;
+use serde::{Deserialize, Serialize};
+
+// version 0 of the position component
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct PosV0(f32, f32);
+// version 1 of the position component
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct PosV1 {
+    x: f32,
+    y: f32,
+}
+// version 2 of the position component
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Pos {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Name(String);
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Matrix([f32; 16]);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct ModelV0(u32);
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Model(String);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Static;
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for PosV0 {}
+    impl DefaultComponentImpl for PosV1 {}
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Name {}
+    impl DefaultComponentImpl for Matrix {}
+    impl DefaultComponentImpl for ModelV0 {}
+    impl DefaultComponentImpl for Model {}
+    impl DefaultComponentImpl for Static {}
+}
+
+fn pos_v1_schema() -> Schema<PosV1> {
+    Schema::<PosV0>::new().upgrade(|old| PosV1 { x: old.0, y: old.1 })
+}
+
+fn pos_schema() -> Schema<Pos> {
+    pos_v1_schema().upgrade(|old| Pos {
+        x: old.x,
+        y: old.y,
+        z: 0.,
+    })
+}
+
+fn model_schema() -> Schema<Model> {
+    Schema::<ModelV0>::new().upgrade(|old| Model(format!("model {}", old.0)))
+}
+
+fn registry() -> SerializeRegistry {
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_component::<Name>("name")
+        .register_tag::<Model>("model")
+        .register_tag::<Static>("static")
+        .set_component_schema(pos_schema())
+        .set_tag_schema(model_schema());
+    registry
+}
+
+fn save_v0() -> (Vec<Entity>, String) {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (ModelV0(3),).as_tags(),
+            (0..10).map(|i| (PosV0(i as f32, 1.), Name(format!("entity {}", i)))),
+        )
+        .to_vec();
+
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<PosV0>("pos")
+        .register_component::<Name>("name")
+        .register_tag::<ModelV0>("model");
+    let json = serde_json::to_string(&world.serializable(&registry)).unwrap();
+    (entities, json)
+}
+
+#[test]
+fn upgrade_old_versions() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let (entities, json) = save_v0();
+
+    let universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let world = World::deserialize(&universe, &registry(), &mut deserializer).unwrap();
+
+    for (i, entity) in entities.iter().enumerate() {
+        assert_eq!(
+            Some(Pos {
+                x: i as f32,
+                y: 1.,
+                z: 0.
+            }),
+            world.component::<Pos>(*entity).map(|p| *p)
+        );
+        assert_eq!(
+            Some(&Model("model 3".to_owned())),
+            world.tag::<Model>(*entity)
+        );
+    }
+}
+
+#[test]
+fn upgrade_saves_without_schema() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // saves made before types were versioned have only their archetypes
+    let (entities, json) = save_v0();
+    let mut value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    assert!(value.as_object_mut().unwrap().remove("schema").is_some());
+    let json = serde_json::to_string(&value).unwrap();
+
+    let universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let world = World::deserialize(&universe, &registry(), &mut deserializer).unwrap();
+
+    for (i, entity) in entities.iter().enumerate() {
+        assert_eq!(
+            Some(Pos {
+                x: i as f32,
+                y: 1.,
+                z: 0.
+            }),
+            world.component::<Pos>(*entity).map(|p| *p)
+        );
+        assert_eq!(
+            Some(&Model("model 3".to_owned())),
+            world.tag::<Model>(*entity)
+        );
+    }
+}
+
+#[test]
+fn upgrade_intermediate_version() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entity = world.insert_from((), vec![(PosV1 { x: 1., y: 2. },)])[0];
+
+    let mut v1 = SerializeRegistry::default();
+    v1.register_component::<PosV1>("pos")
+        .set_component_schema(pos_v1_schema());
+    let value = serde_json::to_value(&world.serializable(&v1)).unwrap();
+    assert_eq!(1, value["schema"]["components"][0][1]);
+
+    let load_universe = Universe::new(None);
+    let loaded = World::deserialize(&load_universe, &registry(), value).unwrap();
+    assert_eq!(
+        Some(Pos {
+            x: 1.,
+            y: 2.,
+            z: 0.
+        }),
+        loaded.component::<Pos>(entity).map(|p| *p)
+    );
+}
+
+#[test]
+fn upgrade_bincode() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entity = world.insert_from((), vec![(PosV0(1., 2.),)])[0];
+
+    let mut v0 = SerializeRegistry::default();
+    v0.register_component::<PosV0>("pos");
+    let bytes = bincode::DefaultOptions::new()
+        .serialize(&world.serializable(&v0))
+        .unwrap();
+
+    let load_universe = Universe::new(None);
+    let mut deserializer =
+        bincode::Deserializer::from_slice(&bytes, bincode::DefaultOptions::new());
+    let loaded = World::deserialize(&load_universe, &registry(), &mut deserializer).unwrap();
+    assert_eq!(
+        Some(Pos {
+            x: 1.,
+            y: 2.,
+            z: 0.
+        }),
+        loaded.component::<Pos>(entity).map(|p| *p)
+    );
+}
+
+#[test]
+fn upgrade_to_larger_type() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from((), (0..1000).map(|i| (i as u32,)))
+        .to_vec();
+
+    let mut small = SerializeRegistry::default();
+    small.register_component::<u32>("value");
+    let json = serde_json::to_string(&world.serializable(&small)).unwrap();
+
+    // the upgraded components no longer fit into a single chunk
+    let mut large = SerializeRegistry::default();
+    large
+        .register_component::<Matrix>("value")
+        .set_component_schema(Schema::<u32>::new().upgrade(|old| Matrix([old as f32; 16])));
+
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let loaded = World::deserialize(&load_universe, &large, &mut deserializer).unwrap();
+
+    for (i, entity) in entities.iter().enumerate() {
+        assert_eq!(
+            Some(Matrix([i as f32; 16])),
+            loaded.component::<Matrix>(*entity).map(|m| *m)
+        );
+    }
+}
+
+#[test]
+fn newer_version_is_error() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from(
+        (),
+        vec![(Pos {
+            x: 0.,
+            y: 0.,
+            z: 0.,
+        },)],
+    );
+    let json = serde_json::to_string(&world.serializable(&registry())).unwrap();
+
+    let mut v1 = SerializeRegistry::default();
+    v1.register_component::<PosV1>("pos")
+        .set_component_schema(pos_v1_schema());
+
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert!(World::deserialize(&load_universe, &v1, &mut deserializer).is_err());
+}
+
+fn save_current() -> (Universe, World, Vec<Entity>, String) {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut entities = world
+        .insert_from(
+            (Model("a".to_owned()), Static).as_tags(),
+            (0..5).map(|i| {
+                (
+                    Pos {
+                        x: i as f32,
+                        y: 0.,
+                        z: 0.,
+                    },
+                    Name(format!("entity {}", i)),
+                )
+            }),
+        )
+        .to_vec();
+    entities.extend_from_slice(world.insert_from(
+        (Model("b".to_owned()),).as_tags(),
+        (0..5).map(|i| {
+            (Pos {
+                x: 0.,
+                y: i as f32,
+                z: 0.,
+            },)
+        }),
+    ));
+
+    let json = serde_json::to_string(&world.serializable(&registry())).unwrap();
+    (universe, world, entities, json)
+}
+
+fn partial_registry(policy: UnknownTypePolicy) -> SerializeRegistry {
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_tag::<Model>("model")
+        .set_component_schema(pos_schema())
+        .set_tag_schema(model_schema())
+        .set_unknown_type_policy(policy);
+    registry
+}
+
+#[test]
+fn unknown_types_are_errors_by_default() {
+    let (_, _, _, json) = save_current();
+
+    let mut partial = partial_registry(UnknownTypePolicy::Drop);
+    partial.set_unknown_type_policy(UnknownTypePolicy::default());
+
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert!(World::deserialize(&load_universe, &partial, &mut deserializer).is_err());
+}
+
+#[test]
+fn drop_unknown_types() {
+    let (_, world, entities, json) = save_current();
+
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let loaded = World::deserialize(
+        &load_universe,
+        &partial_registry(UnknownTypePolicy::Drop),
+        &mut deserializer,
+    )
+    .unwrap();
+
+    for entity in entities.iter() {
+        assert_eq!(
+            world.component::<Pos>(*entity).map(|p| *p),
+            loaded.component::<Pos>(*entity).map(|p| *p)
+        );
+        assert_eq!(world.tag::<Model>(*entity), loaded.tag::<Model>(*entity));
+        assert!(loaded.component::<Name>(*entity).is_none());
+        assert!(loaded.tag::<Static>(*entity).is_none());
+        assert!(loaded.tag::<OpaqueTypes>(*entity).is_none());
+    }
+}
+
+#[test]
+fn preserve_unknown_types() {
+    let (_, world, entities, json) = save_current();
+
+    let partial = partial_registry(UnknownTypePolicy::Preserve);
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let loaded = World::deserialize(&load_universe, &partial, &mut deserializer).unwrap();
+
+    let opaque = loaded.tag::<OpaqueTypes>(entities[0]).unwrap();
+    assert_eq!(
+        vec![StableTypeId::from_name("name")],
+        opaque
+            .components()
+            .iter()
+            .map(|ty| ty.id())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(1, opaque.tags().len());
+    assert_eq!(StableTypeId::from_name("static"), opaque.tags()[0].0.id());
+    assert_eq!(
+        Some(vec![OpaqueValue::String("entity 0".to_owned())]),
+        loaded
+            .component::<OpaqueComponents>(entities[0])
+            .map(|c| c.values().to_vec())
+    );
+    assert!(loaded.tag::<OpaqueTypes>(entities[5]).is_none());
+    assert!(loaded.component::<OpaqueComponents>(entities[5]).is_none());
+
+    // the preserved values are written back out, and can be loaded once the types are known
+    let json = serde_json::to_string(&loaded.serializable(&partial)).unwrap();
+    let reload_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let reloaded = World::deserialize(&reload_universe, &registry(), &mut deserializer).unwrap();
+
+    for entity in entities.iter() {
+        assert_eq!(
+            world.component::<Pos>(*entity).map(|p| *p),
+            reloaded.component::<Pos>(*entity).map(|p| *p)
+        );
+        assert_eq!(
+            world.component::<Name>(*entity).map(|n| n.clone()),
+            reloaded.component::<Name>(*entity).map(|n| n.clone())
+        );
+        assert_eq!(world.tag::<Model>(*entity), reloaded.tag::<Model>(*entity));
+        assert_eq!(
+            world.tag::<Static>(*entity),
+            reloaded.tag::<Static>(*entity)
+        );
+    }
+}
+
+#[test]
+fn preserve_old_versions_of_unknown_types() {
+    let (entities, json) = save_v0();
+
+    // preserve the position component without knowing its current version
+    let mut names = SerializeRegistry::default();
+    names
+        .register_component::<Name>("name")
+        .set_unknown_type_policy(UnknownTypePolicy::Preserve);
+    let universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let loaded = World::deserialize(&universe, &names, &mut deserializer).unwrap();
+    assert_eq!(
+        0,
+        loaded.tag::<OpaqueTypes>(entities[0]).unwrap().components()[0].version()
+    );
+
+    // the preserved data keeps its version, and so is upgraded once loaded with the full registry
+    let json = serde_json::to_string(&loaded.serializable(&names)).unwrap();
+    let reload_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let reloaded = World::deserialize(&reload_universe, &registry(), &mut deserializer).unwrap();
+    assert_eq!(
+        Some(Pos {
+            x: 2.,
+            y: 1.,
+            z: 0.
+        }),
+        reloaded.component::<Pos>(entities[2]).map(|p| *p)
+    );
+}
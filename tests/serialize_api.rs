@@ -0,0 +1,301 @@
+#![cfg(feature = "serde")]
+
+use bincode::Options;
+use legion::prelude::*;
+use legion::registry::StableTypeId;
+use legion::serialize::*;
+use legion::{EntityMap, EntityMappers, MapEntities};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Rot(f32, f32, f32);
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Name(String);
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Target(Entity);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Model(u32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Static;
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Rot {}
+    impl DefaultComponentImpl for Name {}
+    impl DefaultComponentImpl for Target {}
+    impl DefaultComponentImpl for Model {}
+    impl DefaultComponentImpl for Static {}
+}
+
+impl MapEntities for Target {
+    fn map_entities(&mut self, map: &EntityMap) {
+        if let Some(entity) = map.get(&self.0) {
+            self.0 = *entity;
+        }
+    }
+}
+
+fn registry() -> SerializeRegistry {
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_component::<Rot>("rot")
+        .register_component::<Name>("name")
+        .register_tag::<Model>("model")
+        .register_tag::<Static>("static");
+    registry
+}
+
+fn populate(world: &mut World) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    entities.extend_from_slice(world.insert_from(
+        (Model(1), Static).as_tags(),
+        (0..2000).map(|i| (Pos(i as f32, 0., 0.), Rot(0., i as f32, 0.))),
+    ));
+    entities.extend_from_slice(world.insert_from(
+        (Model(2),).as_tags(),
+        (0..10).map(|i| (Pos(i as f32, 1., 0.), Name(format!("entity {}", i)))),
+    ));
+    entities
+}
+
+fn assert_same_entities(expected: &World, actual: &World, entities: &[Entity]) {
+    for entity in entities {
+        assert!(actual.is_alive(entity));
+        assert_eq!(
+            expected.component::<Pos>(*entity).map(|c| *c),
+            actual.component::<Pos>(*entity).map(|c| *c)
+        );
+        assert_eq!(
+            expected.component::<Rot>(*entity).map(|c| *c),
+            actual.component::<Rot>(*entity).map(|c| *c)
+        );
+        assert_eq!(
+            expected.component::<Name>(*entity).map(|c| c.clone()),
+            actual.component::<Name>(*entity).map(|c| c.clone())
+        );
+        assert_eq!(expected.tag::<Model>(*entity), actual.tag::<Model>(*entity));
+        assert_eq!(
+            expected.tag::<Static>(*entity),
+            actual.tag::<Static>(*entity)
+        );
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = populate(&mut world);
+    world.delete(entities[5]);
+
+    let registry = registry();
+    let json = serde_json::to_string(&world.serializable(&registry)).unwrap();
+
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let mut loaded = World::deserialize(&load_universe, &registry, &mut deserializer).unwrap();
+
+    assert!(!loaded.is_alive(&entities[5]));
+    let alive = entities
+        .iter()
+        .filter(|e| **e != entities[5])
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_same_entities(&world, &loaded, &alive);
+    assert_eq!(alive.len(), Read::<Pos>::query().iter(&loaded).count());
+
+    // the restored IDs are not allocated again
+    let created = loaded.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    assert!(!alive.contains(&created));
+    let other = load_universe
+        .create_world()
+        .insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    assert!(!alive.contains(&other));
+}
+
+#[test]
+fn bincode_round_trip() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = populate(&mut world);
+
+    let registry = registry();
+    let bytes = bincode::DefaultOptions::new()
+        .serialize(&world.serializable(&registry))
+        .unwrap();
+
+    let load_universe = Universe::new(None);
+    let mut deserializer =
+        bincode::Deserializer::from_slice(&bytes, bincode::DefaultOptions::new());
+    let loaded = World::deserialize(&load_universe, &registry, &mut deserializer).unwrap();
+
+    assert_same_entities(&world, &loaded, &entities);
+}
+
+#[test]
+fn types_identified_by_stable_id() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entity = world.insert_from((Model(3),).as_tags(), vec![(Pos(1., 2., 3.),)])[0];
+
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_tag_with_id::<Model>("model", StableTypeId::from_uuid(7));
+    let value = serde_json::to_value(&world.serializable(&registry)).unwrap();
+    assert_eq!(
+        StableTypeId::from_name("pos").to_string(),
+        value["archetypes"][0]["components"][0]
+    );
+    assert_eq!(
+        "00000000-0000-0000-0000-000000000007",
+        value["archetypes"][0]["tags"][0]
+    );
+
+    // the names may change, so long as the IDs do not
+    let mut renamed = SerializeRegistry::default();
+    renamed
+        .register_component_with_id::<Pos>("position", StableTypeId::from_name("pos"))
+        .register_tag_with_id::<Model>("mesh", StableTypeId::from_uuid(7));
+
+    let load_universe = Universe::new(None);
+    let loaded = World::deserialize(&load_universe, &renamed, value).unwrap();
+    assert_eq!(Pos(1., 2., 3.), *loaded.component::<Pos>(entity).unwrap());
+    assert_eq!(Some(&Model(3)), loaded.tag::<Model>(entity));
+}
+
+#[test]
+fn deserialize_into_live_world() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let parent = world.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    let child = world.insert_from(
+        (Model(1),).as_tags(),
+        vec![(Pos(1., 0., 0.), Target(parent))],
+    )[0];
+
+    let mut registry = registry();
+    registry.register_component::<Target>("target");
+    let json = serde_json::to_string(&world.serializable(&registry)).unwrap();
+
+    let mut mappers = EntityMappers::default();
+    mappers.register::<Target>();
+
+    // load the same data back into the world it was saved from
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let map = world
+        .deserialize_into(&registry, &mappers, &mut deserializer)
+        .unwrap();
+
+    assert_eq!(2, map.len());
+    assert_ne!(parent, map[&parent]);
+    assert_ne!(child, map[&child]);
+    assert_eq!(
+        Some(Target(parent)),
+        world.component::<Target>(child).map(|c| *c)
+    );
+    assert_eq!(
+        Some(Target(map[&parent])),
+        world.component::<Target>(map[&child]).map(|c| *c)
+    );
+    assert_eq!(Some(&Model(1)), world.tag::<Model>(map[&child]));
+    assert_eq!(4, Read::<Pos>::query().iter(&world).count());
+}
+
+#[test]
+fn serialize_unregistered_type() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let mut registry = SerializeRegistry::default();
+    registry
+        .register_component::<Pos>("pos")
+        .register_tag::<Model>("model")
+        .register_tag::<Static>("static");
+
+    assert!(serde_json::to_string(&world.serializable(&registry)).is_err());
+}
+
+#[test]
+fn deserialize_unknown_type() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let json = serde_json::to_string(&world.serializable(&registry())).unwrap();
+
+    let mut partial = SerializeRegistry::default();
+    partial
+        .register_component::<Pos>("pos")
+        .register_component::<Rot>("rot")
+        .register_tag::<Model>("model")
+        .register_tag::<Static>("static");
+
+    let load_universe = Universe::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert!(World::deserialize(&load_universe, &partial, &mut deserializer).is_err());
+}
+
+#[test]
+fn deserialize_ids_in_use() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    populate(&mut world);
+
+    let registry = registry();
+    let json = serde_json::to_string(&world.serializable(&registry)).unwrap();
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert!(World::deserialize(&universe, &registry, &mut deserializer).is_err());
+}
+
+#[test]
+fn deserialize_mismatched_column() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from(
+        (Model(2),).as_tags(),
+        (0..10).map(|i| (Pos(i as f32, 1., 0.), Name(format!("entity {}", i)))),
+    );
+
+    let registry = registry();
+    let mut value = serde_json::to_value(&world.serializable(&registry)).unwrap();
+
+    // remove a component from the last column of the chunk
+    let columns = value["archetypes"][0]["chunks"][0]["components"]
+        .as_array_mut()
+        .unwrap();
+    columns.last_mut().unwrap().as_array_mut().unwrap().pop();
+
+    let load_universe = Universe::new(None);
+    assert!(World::deserialize(&load_universe, &registry, value).is_err());
+}
+
+#[test]
+fn deserialize_duplicate_entity_index() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Pos(0., 0., 0.),), (Pos(1., 0., 0.),)]);
+
+    let registry = registry();
+    let mut value = serde_json::to_value(&world.serializable(&registry)).unwrap();
+
+    // give the second entity the first entity's index, with a different version
+    let entities = value["archetypes"][0]["chunks"][0]["entities"]
+        .as_array_mut()
+        .unwrap();
+    entities[1]["index"] = entities[0]["index"].clone();
+    entities[1]["version"] = serde_json::json!(1);
+
+    let load_universe = Universe::new(None);
+    assert!(World::deserialize(&load_universe, &registry, value).is_err());
+}
@@ -0,0 +1,274 @@
+mod common;
+
+use common::{Counted, Counts};
+use legion::prelude::*;
+use legion::snapshot::*;
+use legion::storage::CloneRegistry;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Vel(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Unregistered(u32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Model(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Vel {}
+    impl DefaultComponentImpl for Unregistered {}
+    impl DefaultComponentImpl for Model {}
+}
+
+fn registry() -> CloneRegistry {
+    let mut registry = CloneRegistry::default();
+    registry
+        .register::<Pos>()
+        .register::<Vel>()
+        .register::<Counted>();
+    registry
+}
+
+#[test]
+fn restore_component_values() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (Model(1),),
+            vec![
+                (Pos(1., 2., 3.), Vel(0., 0., 0.)),
+                (Pos(4., 5., 6.), Vel(0., 0., 0.)),
+            ],
+        )
+        .to_vec();
+
+    let snapshot = world.snapshot(&registry()).unwrap();
+    assert_eq!(2, snapshot.len());
+
+    let mut query = <Write<Pos>>::query();
+    for mut pos in query.iter(&mut world) {
+        pos.0 += 10.;
+    }
+    world.restore(&snapshot).unwrap();
+
+    assert_eq!(
+        Pos(1., 2., 3.),
+        *world.component::<Pos>(entities[0]).unwrap()
+    );
+    assert_eq!(
+        Pos(4., 5., 6.),
+        *world.component::<Pos>(entities[1]).unwrap()
+    );
+    assert_eq!(Model(1), *world.tag::<Model>(entities[0]).unwrap());
+}
+
+#[test]
+fn restore_structural_changes() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from((), vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),)])
+        .to_vec();
+
+    let snapshot = world.snapshot(&registry()).unwrap();
+
+    world.delete(entities[0]);
+    world.mutate_entity(entities[1], |e| e.add_component(Vel(1., 1., 1.)));
+    let created = world.insert_from((Model(2),), vec![(Pos(7., 8., 9.),)])[0];
+    world.restore(&snapshot).unwrap();
+
+    assert!(world.is_alive(&entities[0]));
+    assert!(!world.is_alive(&created));
+    assert_eq!(
+        Pos(1., 2., 3.),
+        *world.component::<Pos>(entities[0]).unwrap()
+    );
+    assert_eq!(
+        Pos(4., 5., 6.),
+        *world.component::<Pos>(entities[1]).unwrap()
+    );
+    assert_eq!(None, world.component::<Vel>(entities[1]));
+
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(2, query.iter(&mut world).count());
+}
+
+#[test]
+fn restore_allocates_same_ids() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Pos(1., 2., 3.),)]);
+
+    let snapshot = world.snapshot(&registry()).unwrap();
+    let first = world.insert_from((), vec![(Pos(4., 5., 6.),)])[0];
+
+    world.restore(&snapshot).unwrap();
+    let second = world.insert_from((), vec![(Pos(4., 5., 6.),)])[0];
+    assert_eq!(first, second);
+}
+
+#[test]
+fn restore_repeatedly() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entity = world.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+
+    let registry = registry();
+    let mut snapshots = Vec::new();
+    for i in 0..4 {
+        snapshots.push(world.snapshot(&registry).unwrap());
+        *world.component_mut::<Pos>(entity).unwrap() = Pos(i as f32 + 1., 0., 0.);
+        world.insert_from((), vec![(Vel(i as f32, 0., 0.),)]);
+    }
+
+    for (i, snapshot) in snapshots.iter().enumerate().rev() {
+        world.restore(snapshot).unwrap();
+        assert_eq!(
+            Pos(i as f32, 0., 0.),
+            *world.component::<Pos>(entity).unwrap()
+        );
+
+        let mut query = <Read<Vel>>::query();
+        assert_eq!(i, query.iter(&mut world).count());
+    }
+
+    // restoring a later snapshot moves forward again
+    world.restore(&snapshots[2]).unwrap();
+    assert_eq!(Pos(2., 0., 0.), *world.component::<Pos>(entity).unwrap());
+}
+
+#[test]
+fn unchanged_chunks_are_shared() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Arc::new(Counts::default());
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((Model(1),), vec![(Counted(1, counts.clone()),)]);
+    let entity = world.insert_from((Model(2),), vec![(Pos(1., 2., 3.),)])[0];
+
+    let registry = registry();
+    let first = world.snapshot(&registry).unwrap();
+    assert_eq!(1, counts.clones());
+
+    // the counted chunk is unchanged, so is shared with the first snapshot
+    *world.component_mut::<Pos>(entity).unwrap() = Pos(4., 5., 6.);
+    let second = world.snapshot(&registry).unwrap();
+    assert_eq!(1, counts.clones());
+
+    // only the changed chunk is replaced
+    world.restore(&first).unwrap();
+    assert_eq!(1, counts.clones());
+    assert_eq!(Pos(1., 2., 3.), *world.component::<Pos>(entity).unwrap());
+
+    world.restore(&second).unwrap();
+    assert_eq!(1, counts.clones());
+    assert_eq!(Pos(4., 5., 6.), *world.component::<Pos>(entity).unwrap());
+
+    // copies are not kept once no snapshot holds them
+    drop(first);
+    drop(second);
+    world.snapshot(&registry).unwrap();
+    assert_eq!(2, counts.clones());
+}
+
+#[test]
+fn restore_does_not_reuse_chunk_ids() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((Model(1),), vec![(Pos(1., 0., 0.),)]);
+
+    let registry = registry();
+    let snapshot = world.snapshot(&registry).unwrap();
+    world.insert_from((Model(2),), vec![(Pos(2., 0., 0.),)]);
+    world.insert_from((), vec![(Vel(1., 0., 0.),)]);
+    let mut chunk_ids = |world: &mut World| {
+        world
+            .freeze(&registry)
+            .unwrap()
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.id())
+            .collect::<Vec<_>>()
+    };
+    let discarded = chunk_ids(&mut world);
+
+    // chunks created after restoring are given new IDs, in both new and restored archetypes
+    world.restore(&snapshot).unwrap();
+    world.insert_from((Model(3),), vec![(Pos(3., 0., 0.),)]);
+    world.insert_from((), vec![(Pos(4., 0., 0.), Vel(1., 0., 0.))]);
+    let restored = chunk_ids(&mut world);
+    assert_eq!(3, restored.len());
+    assert_eq!(
+        1,
+        restored.iter().filter(|id| discarded.contains(id)).count()
+    );
+}
+
+#[test]
+fn restore_logs_removed_components() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.track_removed::<Pos>();
+    let kept = world.insert_from((), vec![(Pos(1., 0., 0.),)])[0];
+    let changed = world.insert_from((), vec![(Vel(1., 0., 0.),)])[0];
+
+    let snapshot = world.snapshot(&registry()).unwrap();
+    let inserted = world.insert_from((), vec![(Pos(2., 0., 0.),)])[0];
+    world.mutate_entity(changed, |e| e.add_component(Pos(3., 0., 0.)));
+    assert!(world.drain_removed::<Pos>().is_empty());
+
+    // entities which lose their position when the world is rewound are logged
+    world.restore(&snapshot).unwrap();
+    let removed = world.drain_removed::<Pos>();
+    assert_eq!(2, removed.len());
+    assert!(removed.contains(&inserted) && removed.contains(&changed));
+    assert!(world.component::<Pos>(kept).is_some());
+}
+
+#[test]
+fn unregistered_component_is_error() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Unregistered(1),)]);
+
+    assert_eq!(
+        Some(SnapshotError::UnregisteredComponent(
+            <Unregistered as legion::Component>::type_id()
+        )),
+        world.snapshot(&registry()).err()
+    );
+}
+
+#[test]
+fn wrong_world_is_error() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut a = universe.create_world();
+    let mut b = universe.create_world();
+    a.insert_from((), vec![(Pos(1., 2., 3.),)]);
+
+    let snapshot = a.snapshot(&registry()).unwrap();
+    assert_eq!(Err(SnapshotError::WrongWorld), b.restore(&snapshot));
+}
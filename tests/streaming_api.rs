@@ -0,0 +1,185 @@
+use legion::prelude::*;
+use legion::streaming::*;
+use std::sync::mpsc::channel;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pos(f32, f32, f32);
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct Section(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+impl DefaultComponentImpl for Pos {}
+#[cfg(not(feature = "blanket-impl-comp"))]
+impl DefaultComponentImpl for Section {}
+
+fn wait_for<F: FnMut() -> bool>(mut f: F) {
+    let start = std::time::Instant::now();
+    while !f() {
+        assert!(start.elapsed().as_secs() < 10, "timed out");
+        std::thread::yield_now();
+    }
+}
+
+#[test]
+fn load_and_merge() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut loader = StreamingLoader::new(&universe, 2);
+
+    let handles = (0..4)
+        .map(|section| {
+            loader.load(move |world, ctx| {
+                world.insert_from(
+                    (Section(section),).as_tags(),
+                    (0..100).map(|i| (Pos(i as f32, section as f32, 0.),)),
+                );
+                ctx.set_progress(0.5);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    wait_for(|| {
+        loader.poll(&mut world, 10000);
+        handles.iter().all(|h| h.status() == LoadStatus::Merged)
+    });
+
+    for handle in handles.iter() {
+        assert_eq!(1.0, handle.progress());
+    }
+
+    let mut query = Read::<Pos>::query();
+    assert_eq!(400, query.iter(&world).count());
+
+    let mut query = Read::<Pos>::query().filter(tag_value(&Section(2)));
+    for pos in query.iter(&world) {
+        assert_eq!(2., pos.1);
+    }
+}
+
+#[test]
+fn poll_budget() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut loader = StreamingLoader::new(&universe, 1);
+
+    let handle = loader.load(|world, _| {
+        world.insert_from((), (0..4000).map(|i| (Pos(i as f32, 0., 0.),)));
+    });
+    wait_for(|| loader.pending() == 1);
+
+    let mut query = Read::<Pos>::query();
+    let mut polls = 0;
+    while handle.status() != LoadStatus::Merged {
+        loader.poll(&mut world, 1000);
+        polls += 1;
+
+        let count = query.iter(&world).count();
+        assert!(count <= polls * 1000);
+    }
+
+    assert!(polls > 1);
+    assert_eq!(4000, query.iter(&world).count());
+}
+
+#[test]
+fn poll_budget_spans_loads() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut loader = StreamingLoader::new(&universe, 1);
+
+    let small = loader.load(|world, _| {
+        world.insert_from((), (0..5).map(|i| (Pos(i as f32, 0., 0.),)));
+    });
+    let large = loader.load(|world, _| {
+        world.insert_from((), (0..1000).map(|i| (Pos(i as f32, 0., 0.),)));
+    });
+    wait_for(|| loader.pending() == 2);
+
+    // the large load's chunk would exceed what is left of the budget
+    let mut query = Read::<Pos>::query();
+    assert_eq!(1, loader.poll(&mut world, 10));
+    assert_eq!(LoadStatus::Merged, small.status());
+    assert_ne!(LoadStatus::Merged, large.status());
+    assert_eq!(5, query.iter(&world).count());
+
+    while large.status() != LoadStatus::Merged {
+        loader.poll(&mut world, 10);
+    }
+    assert_eq!(1005, query.iter(&world).count());
+}
+
+#[test]
+fn cancel_queued() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut loader = StreamingLoader::new(&universe, 1);
+
+    // block the only worker until the second load has been cancelled
+    let (tx, rx) = channel::<()>();
+    let first = loader.load(move |world, _| {
+        rx.recv().unwrap();
+        world.insert_from((), vec![(Pos(1., 0., 0.),)]);
+    });
+    let second = loader.load(|world, _| {
+        world.insert_from((), vec![(Pos(2., 0., 0.),)]);
+    });
+
+    assert_eq!(LoadStatus::Queued, second.status());
+    second.cancel();
+    tx.send(()).unwrap();
+
+    wait_for(|| {
+        loader.poll(&mut world, 100);
+        first.status() == LoadStatus::Merged && second.status() == LoadStatus::Cancelled
+    });
+
+    let mut query = Read::<Pos>::query();
+    let positions = query.iter(&world).map(|p| *p).collect::<Vec<_>>();
+    assert_eq!(vec![Pos(1., 0., 0.)], positions);
+}
+
+#[test]
+fn cancel_loaded() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut loader = StreamingLoader::new(&universe, 1);
+
+    let handle = loader.load(|world, _| {
+        world.insert_from((), vec![(Pos(1., 0., 0.),)]);
+    });
+    wait_for(|| loader.pending() == 1);
+
+    handle.cancel();
+    assert_eq!(0, loader.poll(&mut world, 100));
+    assert_eq!(LoadStatus::Cancelled, handle.status());
+
+    let mut query = Read::<Pos>::query();
+    assert_eq!(0, query.iter(&world).count());
+}
+
+#[test]
+fn panicking_loader_fails() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let mut loader = StreamingLoader::new(&universe, 1);
+
+    let failed = loader.load(|world, _| {
+        world.insert_from((), vec![(Pos(1., 0., 0.),)]);
+        panic!("missing asset");
+    });
+    wait_for(|| failed.status() == LoadStatus::Failed);
+
+    // the worker survives to run later loads
+    let handle = loader.load(|world, _| {
+        world.insert_from((), vec![(Pos(2., 0., 0.),)]);
+    });
+    wait_for(|| {
+        loader.poll(&mut world, 100);
+        handle.status() == LoadStatus::Merged
+    });
+    assert_eq!(LoadStatus::Failed, failed.status());
+
+    let mut query = Read::<Pos>::query();
+    let positions = query.iter(&world).map(|p| *p).collect::<Vec<_>>();
+    assert_eq!(vec![Pos(2., 0., 0.)], positions);
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use common::{Counted, Counts};
+use legion::prelude::*;
+use legion::storage::CloneRegistry;
+use legion::view::*;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pos(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Vel(f32, f32, f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Unregistered(u32);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Model(u32);
+
+#[cfg(not(feature = "blanket-impl-comp"))]
+mod custom_type_id {
+    use super::*;
+
+    impl DefaultComponentImpl for Pos {}
+    impl DefaultComponentImpl for Vel {}
+    impl DefaultComponentImpl for Unregistered {}
+    impl DefaultComponentImpl for Model {}
+}
+
+fn registry() -> CloneRegistry {
+    let mut registry = CloneRegistry::default();
+    registry
+        .register::<Pos>()
+        .register::<Vel>()
+        .register::<Counted>();
+    registry
+}
+
+#[test]
+fn view_is_unaffected_by_writes() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (Model(1),),
+            vec![
+                (Pos(1., 2., 3.), Vel(1., 1., 1.)),
+                (Pos(4., 5., 6.), Vel(1., 1., 1.)),
+            ],
+        )
+        .to_vec();
+
+    let view = world.freeze(&registry()).unwrap();
+    assert_eq!(2, view.len());
+
+    let mut query = <(Write<Pos>, Read<Vel>)>::query();
+    for (mut pos, vel) in query.iter(&mut world) {
+        pos.0 += vel.0;
+    }
+    *world.component_mut::<Vel>(entities[1]).unwrap() = Vel(2., 2., 2.);
+
+    assert_eq!(
+        vec![
+            (entities[0], Pos(1., 2., 3.)),
+            (entities[1], Pos(4., 5., 6.))
+        ],
+        view.iter::<Pos>().map(|(e, p)| (e, *p)).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![Vel(1., 1., 1.), Vel(1., 1., 1.)],
+        view.iter::<Vel>().map(|(_, v)| *v).collect::<Vec<_>>()
+    );
+    assert_eq!(Some(&Model(1)), view.chunks()[0].tag::<Model>());
+
+    assert_eq!(
+        Pos(2., 2., 3.),
+        *world.component::<Pos>(entities[0]).unwrap()
+    );
+    assert_eq!(
+        Vel(2., 2., 2.),
+        *world.component::<Vel>(entities[1]).unwrap()
+    );
+}
+
+#[test]
+fn view_is_unaffected_by_structural_changes() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (Model(1),),
+            vec![(Pos(1., 2., 3.),), (Pos(4., 5., 6.),), (Pos(7., 8., 9.),)],
+        )
+        .to_vec();
+
+    let view = world.freeze(&registry()).unwrap();
+
+    world.delete(entities[0]);
+    world.mutate_entity(entities[1], |e| e.add_component(Vel(1., 1., 1.)));
+    world.insert_from((Model(1),), vec![(Pos(0., 0., 0.),)]);
+
+    assert_eq!(1, view.chunks().len());
+    assert_eq!(&entities[..], view.chunks()[0].entities());
+    assert_eq!(
+        Some(&[Pos(1., 2., 3.), Pos(4., 5., 6.), Pos(7., 8., 9.)][..]),
+        view.chunks()[0].components::<Pos>()
+    );
+    assert_eq!(0, view.iter::<Vel>().count());
+
+    let mut query = <Read<Pos>>::query();
+    assert_eq!(3, query.iter(&mut world).count());
+}
+
+#[test]
+fn columns_are_copied_on_write() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Arc::new(Counts::default());
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (),
+            vec![
+                (Counted(1, counts.clone()), Pos(0., 0., 0.)),
+                (Counted(2, counts.clone()), Pos(0., 0., 0.)),
+            ],
+        )
+        .to_vec();
+
+    let registry = registry();
+    let view = world.freeze(&registry).unwrap();
+    assert_eq!(0, counts.clones());
+
+    // writing another column does not copy this one
+    let mut query = <Write<Pos>>::query();
+    for mut pos in query.iter(&mut world) {
+        pos.0 = 1.;
+    }
+    assert_eq!(0, counts.clones());
+
+    // the first write copies the column, later writes do not
+    world.component_mut::<Counted>(entities[0]).unwrap().0 = 3;
+    world.component_mut::<Counted>(entities[1]).unwrap().0 = 4;
+    assert_eq!(2, counts.clones());
+
+    assert_eq!(
+        vec![1, 2],
+        view.iter::<Counted>().map(|(_, c)| c.0).collect::<Vec<_>>()
+    );
+    assert_eq!(3, world.component::<Counted>(entities[0]).unwrap().0);
+
+    // once the view has been dropped, nothing is copied
+    drop(view);
+    assert_eq!(2, counts.drops());
+    world.component_mut::<Counted>(entities[0]).unwrap().0 = 5;
+    assert_eq!(2, counts.clones());
+
+    // freezing again shares the column until it is written
+    let view = world.freeze(&registry).unwrap();
+    let again = world.freeze(&registry).unwrap();
+    assert_eq!(2, counts.clones());
+    world.delete(entities[1]);
+    assert_eq!(4, counts.clones());
+    assert_eq!(2, view.iter::<Counted>().count());
+    assert_eq!(2, again.iter::<Counted>().count());
+}
+
+#[test]
+fn values_are_dropped_once() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Arc::new(Counts::default());
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Counted(1, counts.clone()),)]);
+
+    // the world is dropped first, while sharing the unwritten column
+    let view = world.freeze(&registry()).unwrap();
+    drop(world);
+    assert_eq!(0, counts.drops());
+    assert_eq!(1, view.iter::<Counted>().next().unwrap().1 .0);
+    drop(view);
+    assert_eq!(1, counts.drops());
+
+    // the view is dropped first, after the column was copied
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Counted(1, counts.clone()),)]);
+    let view = world.freeze(&registry()).unwrap();
+    let mut query = <Write<Counted>>::query();
+    for mut counted in query.iter(&mut world) {
+        counted.0 = 2;
+    }
+    assert_eq!(1, counts.clones());
+    drop(view);
+    assert_eq!(2, counts.drops());
+    drop(world);
+    assert_eq!(3, counts.drops());
+}
+
+#[test]
+fn view_is_send_and_sync() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entity = world.insert_from((Model(1),), vec![(Pos(1., 2., 3.),)])[0];
+
+    let view = Arc::new(world.freeze(&registry()).unwrap());
+    let render = {
+        let view = view.clone();
+        std::thread::spawn(move || view.iter::<Pos>().map(|(_, p)| *p).collect::<Vec<_>>())
+    };
+    *world.component_mut::<Pos>(entity).unwrap() = Pos(4., 5., 6.);
+
+    assert_eq!(vec![Pos(1., 2., 3.)], render.join().unwrap());
+    assert_eq!(Pos(4., 5., 6.), *world.component::<Pos>(entity).unwrap());
+}
+
+#[test]
+fn unregistered_component_is_error() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Unregistered(1),)]);
+
+    assert_eq!(
+        Some(FreezeError::UnregisteredComponent(
+            <Unregistered as legion::Component>::type_id()
+        )),
+        world.freeze(&registry()).err()
+    );
+}
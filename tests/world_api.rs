@@ -1,4 +1,5 @@
 use legion::prelude::*;
+use legion::query::Filter;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Pos(f32, f32, f32);
@@ -14,6 +15,18 @@ struct Accel(f32, f32, f32);
 struct Model(u32);
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 struct Static;
+#[derive(Clone, Debug)]
+struct Tracked(std::sync::Arc<()>);
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Target(Entity);
+
+impl legion::MapEntities for Target {
+    fn map_entities(&mut self, map: &legion::EntityMap) {
+        if let Some(entity) = map.get(&self.0) {
+            self.0 = *entity;
+        }
+    }
+}
 
 #[test]
 fn insert() {
@@ -226,6 +239,504 @@ fn merge() {
     }
 }
 
+#[test]
+fn merge_unifies_archetypes() {
+    let universe = Universe::new(None);
+    let mut world_1 = universe.create_world();
+    let mut world_2 = universe.create_world();
+
+    let mut entities = Vec::new();
+    let mut insert = |world: &mut World, model: u32| {
+        let inserted = world.insert_from(
+            (Model(model),).as_tags(),
+            (0..600).map(|i| (Pos(i as f32, model as f32, 0.), Rot(0., 0., 0.))),
+        );
+        entities.extend(inserted.iter().enumerate().map(|(i, e)| (*e, i, model)));
+    };
+    insert(&mut world_1, 1);
+    insert(&mut world_1, 2);
+    insert(&mut world_2, 1);
+    let full = world_2.insert_from(
+        (Model(3),).as_tags(),
+        (0..512).map(|i| (Pos(i as f32, 3., 0.), Rot(0., 0., 0.))),
+    )[0];
+
+    world_1.merge(world_2);
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    let chunks = query
+        .iter_chunks(&world_1)
+        .map(|c| {
+            (
+                *c.tag::<Model>().unwrap(),
+                c.components::<Pos>().unwrap().len(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Model(1): 1200 entities folded into 3 chunks rather than 4
+    assert_eq!(3, chunks.iter().filter(|(m, _)| *m == Model(1)).count());
+    assert_eq!(2, chunks.iter().filter(|(m, _)| *m == Model(2)).count());
+    assert_eq!(
+        vec![(Model(3), 512)],
+        chunks
+            .iter()
+            .filter(|(m, _)| *m == Model(3))
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    for (entity, i, model) in entities {
+        assert_eq!(
+            Pos(i as f32, model as f32, 0.),
+            *world_1.component::<Pos>(entity).unwrap()
+        );
+        assert_eq!(Some(&Model(model)), world_1.tag::<Model>(entity));
+    }
+    assert_eq!(Pos(0., 3., 0.), *world_1.component::<Pos>(full).unwrap());
+    assert_eq!(1800 + 512, query.iter(&world_1).count());
+}
+
+#[test]
+fn try_merge_foreign() {
+    let universe_1 = Universe::new(None);
+    let universe_2 = Universe::new(None);
+    let mut world_1 = universe_1.create_world();
+    let mut world_2 = universe_2.create_world();
+
+    let entity = world_2.insert_from((), vec![(Pos(1., 2., 3.),)])[0];
+
+    let world_2 = match world_1.try_merge(world_2) {
+        Err(err) => err.into_world(),
+        Ok(_) => panic!("merged a world from a foreign universe"),
+    };
+    assert!(world_2.is_alive(&entity));
+    assert_eq!(Pos(1., 2., 3.), *world_2.component::<Pos>(entity).unwrap());
+
+    let world_3 = universe_1.create_world();
+    assert!(world_1.try_merge(world_3).is_ok());
+}
+
+#[test]
+fn merge_foreign() {
+    let universe_1 = Universe::new(None);
+    let universe_2 = Universe::new(None);
+    let mut world_1 = universe_1.create_world();
+    let mut world_2 = universe_2.create_world();
+
+    let existing = world_1.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+
+    let a = world_2.insert_from((), vec![(Pos(1., 2., 3.),)])[0];
+    let b = world_2.insert_from((Model(1),).as_tags(), vec![(Pos(4., 5., 6.), Target(a))])[0];
+    let c = world_2.insert_from((), vec![(Pos(7., 8., 9.), Target(b))])[0];
+
+    let mut mappers = legion::EntityMappers::default();
+    mappers.register::<Target>();
+
+    let map = world_1.merge_foreign(world_2, &mappers);
+    assert_eq!(3, map.len());
+
+    let (a, b, c) = (map[&a], map[&b], map[&c]);
+    assert_ne!(existing, a);
+    assert_ne!(existing, b);
+    assert_ne!(existing, c);
+
+    assert_eq!(
+        Pos(0., 0., 0.),
+        *world_1.component::<Pos>(existing).unwrap()
+    );
+    assert_eq!(Pos(1., 2., 3.), *world_1.component::<Pos>(a).unwrap());
+    assert_eq!(Pos(4., 5., 6.), *world_1.component::<Pos>(b).unwrap());
+    assert_eq!(Pos(7., 8., 9.), *world_1.component::<Pos>(c).unwrap());
+    assert_eq!(Target(a), *world_1.component::<Target>(b).unwrap());
+    assert_eq!(Target(b), *world_1.component::<Target>(c).unwrap());
+    assert_eq!(Some(&Model(1)), world_1.tag::<Model>(b));
+
+    assert!(world_1.delete(a));
+    assert!(!world_1.is_alive(&a));
+    assert_eq!(Pos(4., 5., 6.), *world_1.component::<Pos>(b).unwrap());
+}
+
+#[test]
+fn split_off() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let mut entities = Vec::new();
+    for model in 0..3 {
+        let inserted = world.insert_from(
+            (Model(model),).as_tags(),
+            (0..600).map(|i| (Pos(i as f32, model as f32, 0.), Rot(0., 0., 0.))),
+        );
+        entities.extend(inserted.iter().enumerate().map(|(i, e)| (*e, i, model)));
+    }
+
+    let mut split = world.split_off(tag_value(&Model(1)));
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    assert_eq!(1200, query.iter(&world).count());
+    assert_eq!(600, query.iter(&split).count());
+
+    for (entity, i, model) in entities.iter() {
+        let (owner, other) = if *model == 1 {
+            (&split, &world)
+        } else {
+            (&world, &split)
+        };
+        assert!(owner.is_alive(entity));
+        assert!(!other.is_alive(entity));
+        assert_eq!(
+            Pos(*i as f32, *model as f32, 0.),
+            *owner.component::<Pos>(*entity).unwrap()
+        );
+    }
+
+    // newly created entities must not collide with those which were split off
+    let created = split
+        .insert_from((), (0..2000).map(|_| (Pos(0., 0., 0.),)))
+        .to_vec();
+    for entity in created.iter() {
+        assert!(!entities.iter().any(|(e, _, _)| e == entity));
+    }
+
+    for entity in created {
+        split.delete(entity);
+    }
+    world.merge(split);
+
+    assert_eq!(1800, query.iter(&world).count());
+    for (entity, i, model) in entities {
+        assert_eq!(
+            Pos(i as f32, model as f32, 0.),
+            *world.component::<Pos>(entity).unwrap()
+        );
+        assert_eq!(Some(&Model(model)), world.tag::<Model>(entity));
+    }
+}
+
+#[test]
+fn merge_incremental() {
+    let universe = Universe::new(None);
+    let mut world_1 = universe.create_world();
+    let mut world_2 = universe.create_world();
+
+    let existing = world_1.insert_from((), vec![(Pos(0., 0., 0.), Rot(0., 0., 0.))])[0];
+    let entities = world_2
+        .insert_from(
+            (Model(1),).as_tags(),
+            (0..2000).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+    world_2.delete(entities[0]);
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    let mut passes = 0;
+    loop {
+        let mut budget = 600;
+        let complete = world_1.merge_incremental(&mut world_2, &mut budget);
+        passes += 1;
+
+        assert_eq!(
+            2000,
+            query.iter(&world_1).count() + query.iter(&world_2).count()
+        );
+        for entity in entities.iter().skip(1) {
+            assert!(world_1.is_alive(entity) != world_2.is_alive(entity));
+        }
+
+        if complete {
+            break;
+        }
+    }
+    assert!(passes > 1);
+
+    assert_eq!(
+        Pos(0., 0., 0.),
+        *world_1.component::<Pos>(existing).unwrap()
+    );
+    assert!(!world_1.is_alive(&entities[0]));
+    for (i, entity) in entities.iter().enumerate().skip(1) {
+        assert_eq!(
+            Pos(i as f32, 0., 0.),
+            *world_1.component::<Pos>(*entity).unwrap()
+        );
+        assert_eq!(Some(&Model(1)), world_1.tag::<Model>(*entity));
+    }
+
+    // IDs owned by the merged world's blocks are recycled in the target
+    assert!(world_1.delete(entities[1]));
+    let created = world_1.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    assert!(world_1.is_alive(&created));
+    assert!(!world_1.is_alive(&entities[1]));
+}
+
+#[test]
+fn move_entity() {
+    let universe = Universe::new(None);
+    let mut world_1 = universe.create_world();
+    let mut world_2 = universe.create_world();
+
+    let entities = world_1
+        .insert_from(
+            (Model(5),).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    assert!(world_1.move_entity(entities[3], &mut world_2));
+    assert!(!world_1.move_entity(entities[3], &mut world_2));
+
+    assert!(!world_1.is_alive(&entities[3]));
+    assert!(world_2.is_alive(&entities[3]));
+    assert_eq!(
+        Pos(3., 0., 0.),
+        *world_2.component::<Pos>(entities[3]).unwrap()
+    );
+    assert_eq!(Some(&Model(5)), world_2.tag::<Model>(entities[3]));
+
+    for (i, entity) in entities.iter().enumerate().filter(|(i, _)| *i != 3) {
+        assert_eq!(
+            Pos(i as f32, 0., 0.),
+            *world_1.component::<Pos>(*entity).unwrap()
+        );
+    }
+
+    // move back into the world which allocated the entity
+    assert!(world_2.move_entity(entities[3], &mut world_1));
+    assert!(world_1.is_alive(&entities[3]));
+    assert!(!world_2.is_alive(&entities[3]));
+    assert_eq!(
+        Pos(3., 0., 0.),
+        *world_1.component::<Pos>(entities[3]).unwrap()
+    );
+
+    // deleting a moved entity recycles its ID in the world which now owns it
+    assert!(world_1.move_entity(entities[4], &mut world_2));
+    assert!(world_2.delete(entities[4]));
+    assert!(!world_2.is_alive(&entities[4]));
+    let created = world_2.insert_from((), vec![(Pos(0., 0., 0.),)])[0];
+    assert!(world_2.is_alive(&created));
+    assert!(!world_1.is_alive(&created));
+    assert!(!entities.contains(&created));
+}
+
+fn clone_registry() -> legion::storage::CloneRegistry {
+    let mut registry = legion::storage::CloneRegistry::default();
+    registry
+        .register::<Pos>()
+        .register::<Rot>()
+        .register::<Tracked>()
+        .register::<Target>();
+    registry
+}
+
+#[test]
+fn clone_into() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let entities = world
+        .insert_from(
+            (Model(3),).as_tags(),
+            (0..1000).map(|i| (Pos(i as f32, 0., 0.), Tracked(tracker.clone()))),
+        )
+        .to_vec();
+    world.delete(entities[10]);
+    let other = world.insert_from((), vec![(Rot(1., 2., 3.),)])[0];
+
+    let lookahead = Universe::new(None);
+    let mut clone = world.clone_into(&lookahead, &clone_registry()).unwrap();
+    assert_eq!(1 + 999 * 2, std::sync::Arc::strong_count(&tracker));
+
+    assert!(!clone.is_alive(&entities[10]));
+    for (i, entity) in entities.iter().enumerate().filter(|(i, _)| *i != 10) {
+        assert!(clone.is_alive(entity));
+        assert_eq!(
+            Pos(i as f32, 0., 0.),
+            *clone.component::<Pos>(*entity).unwrap()
+        );
+        assert_eq!(Some(&Model(3)), clone.tag::<Model>(*entity));
+    }
+    assert_eq!(Rot(1., 2., 3.), *clone.component::<Rot>(other).unwrap());
+
+    // the clone is independent of the original
+    *clone.component_mut::<Pos>(entities[0]).unwrap() = Pos(-1., 0., 0.);
+    assert_eq!(
+        Pos(0., 0., 0.),
+        *world.component::<Pos>(entities[0]).unwrap()
+    );
+    assert!(clone.delete(entities[1]));
+    assert!(world.is_alive(&entities[1]));
+
+    // new entities in the target universe do not collide with the preserved IDs
+    let created = clone
+        .insert_from((), (0..2000).map(|_| (Pos(0., 0., 0.),)))
+        .to_vec();
+    let mut other_world = lookahead.create_world();
+    let created_other = other_world
+        .insert_from((), (0..2000).map(|_| (Pos(0., 0., 0.),)))
+        .to_vec();
+    for entity in created.iter().chain(created_other.iter()) {
+        assert!(!entities.contains(entity));
+        assert_ne!(other, *entity);
+    }
+
+    drop(clone);
+    assert_eq!(1000, std::sync::Arc::strong_count(&tracker));
+}
+
+#[test]
+fn clone_into_errors() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.insert_from((), vec![(Pos(1., 2., 3.), Vel(0., 0., 0.))]);
+
+    let registry = clone_registry();
+    assert_eq!(
+        Some(legion::CloneError::UnregisteredComponent(
+            <Vel as legion::Component>::type_id()
+        )),
+        world.clone_into(&Universe::new(None), &registry).err()
+    );
+
+    world.insert_from((), vec![(Pos(1., 2., 3.),)]);
+    assert_eq!(
+        Some(legion::CloneError::EntityIdsInUse),
+        world
+            .clone_entities(!component::<Vel>(), &registry)
+            .and_then(|_| world.clone_into(&universe, &registry))
+            .err()
+    );
+}
+
+#[test]
+fn clone_entities() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let parent = world.insert_from((Model(1),).as_tags(), vec![(Pos(1., 0., 0.),)])[0];
+    let child = world.insert_from(
+        (Model(1),).as_tags(),
+        vec![(Pos(2., 0., 0.), Target(parent))],
+    )[0];
+    let unrelated = world.insert_from((Model(2),).as_tags(), vec![(Pos(3., 0., 0.),)])[0];
+
+    let (mut clone, map) = world
+        .clone_entities(tag_value(&Model(1)), &clone_registry())
+        .unwrap();
+    assert_eq!(2, map.len());
+    assert!(!map.contains_key(&unrelated));
+
+    let mut mappers = legion::EntityMappers::default();
+    mappers.register::<Target>();
+    clone.map_entities(&mappers, &map);
+
+    let (new_parent, new_child) = (map[&parent], map[&child]);
+    assert_ne!(parent, new_parent);
+    assert_ne!(child, new_child);
+    assert_eq!(
+        Pos(1., 0., 0.),
+        *clone.component::<Pos>(new_parent).unwrap()
+    );
+    assert_eq!(
+        Target(new_parent),
+        *clone.component::<Target>(new_child).unwrap()
+    );
+    assert!(!clone.is_alive(&parent));
+
+    // the clones can be merged back as duplicates
+    world.merge(clone);
+    let mut query = Read::<Pos>::query();
+    assert_eq!(5, query.iter(&world).count());
+    assert_eq!(Target(parent), *world.component::<Target>(child).unwrap());
+    assert_eq!(
+        Target(new_parent),
+        *world.component::<Target>(new_child).unwrap()
+    );
+}
+
+#[test]
+fn instantiate() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let template = legion::Template::new(
+        (Model(2), Static).as_tags(),
+        (Pos(1., 2., 3.), Rot(0., 0., 0.), Tracked(tracker.clone())),
+    );
+
+    let entities = world.instantiate(&template, 2000).to_vec();
+    assert_eq!(2000, entities.len());
+    assert_eq!(2002, std::sync::Arc::strong_count(&tracker));
+
+    for entity in entities.iter() {
+        assert_eq!(Pos(1., 2., 3.), *world.component::<Pos>(*entity).unwrap());
+        assert_eq!(Some(&Model(2)), world.tag::<Model>(*entity));
+    }
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    assert_eq!(2000, query.iter(&world).count());
+
+    world.delete(entities[0]);
+    drop(template);
+    assert_eq!(2000, std::sync::Arc::strong_count(&tracker));
+}
+
+#[test]
+fn instantiate_with() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let template = legion::Template::new((Model(2),).as_tags(), (Pos(0., 0., 0.), Rot(1., 1., 1.)));
+    let existing = world.instantiate(&template, 10).to_vec();
+    let entities = world
+        .instantiate_with(&template, 1000, |i, (pos, _)| pos.0 = i as f32)
+        .to_vec();
+
+    for (i, entity) in entities.iter().enumerate() {
+        assert_eq!(
+            Pos(i as f32, 0., 0.),
+            *world.component::<Pos>(*entity).unwrap()
+        );
+        assert_eq!(Rot(1., 1., 1.), *world.component::<Rot>(*entity).unwrap());
+    }
+    for entity in existing.iter() {
+        assert_eq!(Pos(0., 0., 0.), *world.component::<Pos>(*entity).unwrap());
+    }
+    assert_eq!(&Pos(0., 0., 0.), &template.components().0);
+}
+
+#[test]
+fn instantiate_with_panicking_override() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let template = legion::Template::new(
+        (Model(2),).as_tags(),
+        (Pos(0., 0., 0.), Tracked(tracker.clone())),
+    );
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        world.instantiate_with(&template, 10, |i, _| {
+            if i == 5 {
+                panic!("override failed");
+            }
+        });
+    }));
+    assert!(result.is_err());
+
+    // only the entities whose values were constructed are held by the world
+    let mut query = Read::<Pos>::query();
+    assert_eq!(5, query.iter(&world).count());
+    assert_eq!(7, std::sync::Arc::strong_count(&tracker));
+
+    drop(world);
+    assert_eq!(2, std::sync::Arc::strong_count(&tracker));
+}
+
 #[test]
 fn mutate_add_component() {
     let universe = Universe::new(None);
@@ -366,6 +877,667 @@ fn mutate_change_tag() {
     assert_eq!(1, query_model_3.iter(&world).count());
 }
 
+#[test]
+fn mutate_preserves_swapped_entity() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let components = vec![
+        (Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3)),
+        (Pos(4., 5., 6.), Rot(0.4, 0.5, 0.6)),
+        (Pos(7., 8., 9.), Rot(0.7, 0.8, 0.9)),
+    ];
+
+    let entities = world.insert_from((), components.clone()).to_vec();
+
+    world.mutate_entity(entities[0], |e| e.add_component(Scale(0.5, 0.5, 0.5)));
+
+    for (i, e) in entities.iter().enumerate() {
+        let (pos, rot) = components.get(i).unwrap();
+        assert_eq!(pos, &world.component(*e).unwrap() as &Pos);
+        assert_eq!(rot, &world.component(*e).unwrap() as &Rot);
+    }
+}
+
+#[test]
+fn mutate_drop_count() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let components = vec![
+        (Pos(1., 2., 3.), Tracked(tracker.clone())),
+        (Pos(4., 5., 6.), Tracked(tracker.clone())),
+    ];
+
+    let entities = world.insert_from((), components).to_vec();
+    assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+
+    world.mutate_entity(entities[0], |e| e.add_component(Scale(0.5, 0.5, 0.5)));
+    assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+
+    world.mutate_entity(entities[0], |e| e.set_tag(Static));
+    assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+
+    world.mutate_entity(entities[1], |e| {
+        e.add_component(Tracked(tracker.clone()));
+    });
+    assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+
+    drop(world);
+    assert_eq!(1, std::sync::Arc::strong_count(&tracker));
+}
+
+#[test]
+fn mutate_remove_component_drop_count() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let components = vec![
+        (Pos(1., 2., 3.), Tracked(tracker.clone())),
+        (Pos(4., 5., 6.), Tracked(tracker.clone())),
+    ];
+
+    let entities = world.insert_from((), components).to_vec();
+    assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+
+    world.mutate_entity(entities[0], |e| {
+        e.remove_component::<Tracked>();
+    });
+    assert_eq!(2, std::sync::Arc::strong_count(&tracker));
+    assert!(world.component::<Tracked>(entities[0]).is_none());
+    assert!(world.component::<Tracked>(entities[1]).is_some());
+
+    drop(world);
+    assert_eq!(1, std::sync::Arc::strong_count(&tracker));
+}
+
+#[test]
+fn delete_drop_count() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let components = vec![
+        (Pos(1., 2., 3.), Tracked(tracker.clone())),
+        (Pos(4., 5., 6.), Tracked(tracker.clone())),
+        (Pos(7., 8., 9.), Tracked(tracker.clone())),
+    ];
+
+    let entities = world.insert_from((), components).to_vec();
+    assert_eq!(4, std::sync::Arc::strong_count(&tracker));
+
+    world.delete(entities[0]);
+    assert_eq!(3, std::sync::Arc::strong_count(&tracker));
+
+    world.delete(entities[2]);
+    assert_eq!(2, std::sync::Arc::strong_count(&tracker));
+    assert_eq!(
+        &Pos(4., 5., 6.),
+        &world.component(entities[1]).unwrap() as &Pos
+    );
+
+    drop(world);
+    assert_eq!(1, std::sync::Arc::strong_count(&tracker));
+}
+
+#[test]
+fn add_component_where() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let components = (0..5000)
+        .map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.)))
+        .collect::<Vec<_>>();
+    let frozen = world.insert_from((Static,).as_tags(), components).to_vec();
+    let unfrozen = world
+        .insert_from(
+            (Model(1),).as_tags(),
+            vec![(Pos(1., 2., 3.), Rot(0.1, 0.2, 0.3))],
+        )
+        .to_vec();
+
+    world.add_component_where(tag::<Static>(), |e| {
+        Scale(e.to_string().len() as f32, 0., 0.)
+    });
+
+    let mut query = <(Read<Pos>, Read<Scale>)>::query();
+    assert_eq!(frozen.len(), query.iter(&world).count());
+
+    for (i, e) in frozen.iter().enumerate() {
+        assert_eq!(Pos(i as f32, 0., 0.), *world.component::<Pos>(*e).unwrap());
+        assert_eq!(
+            Scale(e.to_string().len() as f32, 0., 0.),
+            *world.component::<Scale>(*e).unwrap()
+        );
+        assert_eq!(Some(&Static), world.tag(*e));
+    }
+
+    assert!(world.component::<Scale>(unfrozen[0]).is_none());
+    assert_eq!(
+        Pos(1., 2., 3.),
+        *world.component::<Pos>(unfrozen[0]).unwrap()
+    );
+}
+
+#[test]
+fn remove_component_where() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let components = (0..3000)
+        .map(|i| (Pos(i as f32, 0., 0.), Tracked(tracker.clone())))
+        .collect::<Vec<_>>();
+    let entities = world
+        .insert_from((Model(5),).as_tags(), components)
+        .to_vec();
+    let kept = world
+        .insert_from(
+            (Model(3),).as_tags(),
+            vec![(Pos(0., 0., 0.), Tracked(tracker.clone()))],
+        )
+        .to_vec();
+    assert_eq!(3002, std::sync::Arc::strong_count(&tracker));
+
+    world.remove_component_where::<Tracked, _>(tag_value(&Model(5)));
+    assert_eq!(2, std::sync::Arc::strong_count(&tracker));
+
+    let mut query = Read::<Pos>::query().filter(!component::<Tracked>());
+    assert_eq!(entities.len(), query.iter(&world).count());
+
+    for (i, e) in entities.iter().enumerate() {
+        assert_eq!(Pos(i as f32, 0., 0.), *world.component::<Pos>(*e).unwrap());
+        assert!(world.component::<Tracked>(*e).is_none());
+        assert_eq!(Some(&Model(5)), world.tag(*e));
+    }
+
+    assert!(world.component::<Tracked>(kept[0]).is_some());
+
+    world.delete(entities[0]);
+    world.delete(kept[0]);
+    assert_eq!(1, std::sync::Arc::strong_count(&tracker));
+}
+
+#[test]
+fn delete_where() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let components = (0..3000)
+        .map(|i| (Pos(i as f32, 0., 0.), Tracked(tracker.clone())))
+        .collect::<Vec<_>>();
+    let dead = world.insert_from((Static,).as_tags(), components).to_vec();
+    let alive = world
+        .insert_from(
+            (Model(1),).as_tags(),
+            vec![(Pos(1., 2., 3.), Tracked(tracker.clone()))],
+        )
+        .to_vec();
+
+    assert_eq!(dead.len(), world.delete_where(tag::<Static>()));
+    assert_eq!(2, std::sync::Arc::strong_count(&tracker));
+
+    for e in dead.iter() {
+        assert_eq!(false, world.is_alive(e));
+        assert!(world.component::<Pos>(*e).is_none());
+    }
+
+    assert_eq!(true, world.is_alive(&alive[0]));
+    assert_eq!(Pos(1., 2., 3.), *world.component::<Pos>(alive[0]).unwrap());
+
+    let mut query = Read::<Pos>::query();
+    assert_eq!(1, query.iter(&world).count());
+
+    let reinserted = world
+        .insert_from(
+            (Static,).as_tags(),
+            vec![(Pos(4., 5., 6.), Tracked(tracker.clone()))],
+        )
+        .to_vec();
+    assert_eq!(
+        Pos(4., 5., 6.),
+        *world.component::<Pos>(reinserted[0]).unwrap()
+    );
+    assert_eq!(2, query.iter(&world).count());
+}
+
+/// Inserts ten entities into a single chunk, and writes the `Pos` of the fourth, returning a
+/// `changed` filter which matches only that write.
+fn partly_changed_world(
+    universe: &Universe,
+) -> (
+    World,
+    Vec<Entity>,
+    legion::query::ComponentChangedFilter<Pos>,
+) {
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut query = Read::<Pos>::query();
+    query.iter(&world).count();
+    world.component_mut::<Pos>(entities[3]).unwrap().1 = 1.;
+
+    let mut filter = changed::<Pos>();
+    filter.set_last_run(query.last_run().map(|(_, tick)| tick));
+    (world, entities, filter)
+}
+
+#[test]
+fn delete_where_partly_changed_chunk() {
+    let universe = Universe::new(None);
+    let (mut world, entities, filter) = partly_changed_world(&universe);
+
+    assert_eq!(1, world.delete_where(filter));
+    assert!(!world.is_alive(&entities[3]));
+    for e in entities.iter().filter(|e| **e != entities[3]) {
+        assert!(world.is_alive(e));
+    }
+
+    let mut query = Read::<Pos>::query();
+    assert_eq!(9, query.iter(&world).count());
+}
+
+#[test]
+fn delete_where_compacts_partly_deleted_chunks() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    let entities = world
+        .insert_from(
+            (Model(5),).as_tags(),
+            (0..1024).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    assert_eq!(2, query.iter_chunks(&world).count());
+
+    // change the first half of each chunk
+    let mut read = Read::<Pos>::query();
+    read.iter(&world).count();
+    for e in entities[..256].iter().chain(entities[512..768].iter()) {
+        world.component_mut::<Pos>(*e).unwrap().1 = 1.;
+    }
+    let mut filter = changed::<Pos>();
+    filter.set_last_run(read.last_run().map(|(_, tick)| tick));
+
+    assert_eq!(512, world.delete_where(filter));
+    assert_eq!(1, query.iter_chunks(&world).count());
+    for (i, e) in entities.iter().enumerate() {
+        let kept = (256..512).contains(&i) || i >= 768;
+        assert_eq!(kept, world.is_alive(e));
+        if kept {
+            assert_eq!(Pos(i as f32, 0., 0.), *world.component::<Pos>(*e).unwrap());
+        }
+    }
+}
+
+#[test]
+fn delete_where_partly_added_chunk() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..10).map(|i| (Pos(i as f32, 0., 0.),)),
+        )
+        .to_vec();
+
+    let mut query = Read::<Pos>::query();
+    query.iter(&world).count();
+    let inserted = world.insert_from((Static,).as_tags(), vec![(Pos(10., 0., 0.),)])[0];
+
+    let mut filter = added::<Pos>();
+    filter.set_last_run(query.last_run().map(|(_, tick)| tick));
+    assert_eq!(1, world.delete_where(filter));
+    assert!(!world.is_alive(&inserted));
+    for e in entities.iter() {
+        assert!(world.is_alive(e));
+    }
+}
+
+#[test]
+fn bulk_operations_on_partly_changed_chunk() {
+    let universe = Universe::new(None);
+
+    let (mut world, entities, filter) = partly_changed_world(&universe);
+    world.add_component_where(filter, |_| Scale(1., 1., 1.));
+    let with_scale = <(Read<Pos>, Read<Scale>)>::query()
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![entities[3]], with_scale);
+
+    let (mut world, entities, filter) = partly_changed_world(&universe);
+    world.remove_component_where::<Pos, _>(filter);
+    assert!(world.component::<Pos>(entities[3]).is_none());
+    assert_eq!(9, Read::<Pos>::query().iter(&world).count());
+
+    let (mut world, entities, filter) = partly_changed_world(&universe);
+    let section = world.split_off(filter);
+    assert!(section.is_alive(&entities[3]));
+    assert!(!world.is_alive(&entities[3]));
+    assert_eq!(1, Read::<Pos>::query().iter(&section).count());
+    assert_eq!(9, Read::<Pos>::query().iter(&world).count());
+
+    let (world, entities, filter) = partly_changed_world(&universe);
+    let (clone, map) = world.clone_entities(filter, &clone_registry()).unwrap();
+    assert_eq!(1, map.len());
+    assert_eq!(
+        Pos(3., 1., 0.),
+        *clone.component::<Pos>(map[&entities[3]]).unwrap()
+    );
+    assert_eq!(1, Read::<Pos>::query().iter(&clone).count());
+}
+
+#[test]
+fn clear() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let tracker = std::sync::Arc::new(());
+    let entities = world
+        .insert_from(
+            (Static,).as_tags(),
+            (0..100).map(|i| (Pos(i as f32, 0., 0.), Tracked(tracker.clone()))),
+        )
+        .to_vec();
+    assert_eq!(101, std::sync::Arc::strong_count(&tracker));
+
+    world.clear();
+    assert_eq!(1, std::sync::Arc::strong_count(&tracker));
+
+    for e in entities.iter() {
+        assert_eq!(false, world.is_alive(e));
+    }
+
+    let mut query = Read::<Pos>::query();
+    assert_eq!(0, query.iter(&world).count());
+
+    let entity = world.insert_from((), vec![(Pos(1., 2., 3.),)])[0];
+    assert_eq!(Pos(1., 2., 3.), *world.component::<Pos>(entity).unwrap());
+}
+
+fn fragmented_world(universe: &Universe) -> (World, Vec<(Entity, Pos)>) {
+    let mut world = universe.create_world();
+
+    let components = (0..4000)
+        .map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.)))
+        .collect::<Vec<_>>();
+    let mut entities = world
+        .insert_from((Model(1),).as_tags(), components.clone())
+        .iter()
+        .zip(components.iter())
+        .map(|(e, (pos, _))| (*e, *pos))
+        .collect::<Vec<_>>();
+    entities.extend(
+        world
+            .insert_from((Model(2),).as_tags(), components.clone())
+            .iter()
+            .zip(components.iter())
+            .map(|(e, (pos, _))| (*e, *pos)),
+    );
+
+    let mut kept = Vec::new();
+    for (i, (entity, pos)) in entities.into_iter().enumerate() {
+        if i % 3 == 0 {
+            kept.push((entity, pos));
+        } else {
+            world.delete(entity);
+        }
+    }
+
+    (world, kept)
+}
+
+#[test]
+fn defrag() {
+    let universe = Universe::new(None);
+    let (mut world, entities) = fragmented_world(&universe);
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    let chunks_before = query.iter_chunks(&world).count();
+
+    assert!(world.defrag(None));
+
+    let chunks_after = query.iter_chunks(&world).count();
+    assert!(chunks_after < chunks_before);
+    assert_eq!(
+        1,
+        query
+            .iter_chunks(&world)
+            .filter(|c| c.tag() == Some(&Model(1)))
+            .filter(|c| c.components::<Pos>().unwrap().len() < 512)
+            .count()
+    );
+
+    for (entity, pos) in entities.iter() {
+        assert_eq!(pos, &world.component(*entity).unwrap() as &Pos);
+    }
+    assert_eq!(entities.len(), query.iter(&world).count());
+}
+
+#[test]
+fn defrag_incremental() {
+    let universe = Universe::new(None);
+    let (mut world, entities) = fragmented_world(&universe);
+
+    let mut passes = 0;
+    while !world.defrag(Some(100)) {
+        passes += 1;
+
+        for (entity, pos) in entities.iter() {
+            assert_eq!(pos, &world.component(*entity).unwrap() as &Pos);
+        }
+    }
+    assert!(passes > 1);
+
+    for (entity, pos) in entities.iter() {
+        assert_eq!(pos, &world.component(*entity).unwrap() as &Pos);
+        assert!(world.tag::<Model>(*entity).is_some());
+    }
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    assert_eq!(entities.len(), query.iter(&world).count());
+}
+
+#[test]
+fn delete_frees_empty_chunks() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (Model(5),).as_tags(),
+            (0..1000).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+
+    let mut query = <(Read<Pos>, Read<Rot>)>::query();
+    assert_eq!(2, query.iter_chunks(&world).count());
+
+    for e in entities.iter().skip(512) {
+        assert!(world.delete(*e));
+    }
+    assert_eq!(1, query.iter_chunks(&world).count());
+
+    for (i, e) in entities.iter().take(512).enumerate() {
+        assert_eq!(Pos(i as f32, 0., 0.), *world.component::<Pos>(*e).unwrap());
+    }
+
+    for e in entities.iter().take(512) {
+        assert!(world.delete(*e));
+    }
+    assert_eq!(0, query.iter_chunks(&world).count());
+}
+
+#[test]
+fn chunk_allocation_cycles_do_not_overflow() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    // each iteration allocates and frees a chunk, exceeding the range of a 16-bit chunk ID
+    for i in 0..70_000 {
+        let entity = world.insert_from((Static,).as_tags(), vec![(Pos(i as f32, 0., 0.),)])[0];
+        world.delete(entity);
+    }
+
+    let entity = world.insert_from((Static,).as_tags(), vec![(Pos(1., 2., 3.),)])[0];
+    assert_eq!(Pos(1., 2., 3.), *world.component::<Pos>(entity).unwrap());
+}
+
+#[test]
+fn chunk_pool_reuse() {
+    let universe = Universe::new(None);
+    universe.chunk_pool().set_capacity(4);
+    let mut world = universe.create_world();
+
+    let entities = world
+        .insert_from(
+            (),
+            (0..1024).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+    assert_eq!(0, universe.chunk_pool().len());
+
+    for e in entities.iter() {
+        world.delete(*e);
+    }
+    assert_eq!(2, universe.chunk_pool().len());
+
+    let mut other = universe.create_world();
+    other.insert_from((), vec![(Pos(1., 2., 3.), Rot(0., 0., 0.))]);
+    assert_eq!(1, universe.chunk_pool().len());
+
+    universe.chunk_pool().set_capacity(0);
+    assert!(universe.chunk_pool().is_empty());
+}
+
+#[test]
+fn shrink_to_fit() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+
+    let a = world.insert_from((), vec![(Pos(1., 0., 0.),)])[0];
+    let b = world.insert_from((), vec![(Pos(2., 0., 0.), Rot(0., 0., 0.))])[0];
+    let c = world.insert_from((), vec![(Pos(3., 0., 0.), Scale(0., 0., 0.))])[0];
+
+    world.delete(b);
+    world.shrink_to_fit();
+
+    assert_eq!(Pos(1., 0., 0.), *world.component::<Pos>(a).unwrap());
+    assert_eq!(Pos(3., 0., 0.), *world.component::<Pos>(c).unwrap());
+    assert!(world.component::<Scale>(c).is_some());
+
+    let mut query = Read::<Pos>::query();
+    assert_eq!(2, query.iter(&world).count());
+
+    let d = world.insert_from((), vec![(Pos(4., 0., 0.), Rot(0., 0., 0.))])[0];
+    assert_eq!(Pos(4., 0., 0.), *world.component::<Pos>(d).unwrap());
+    assert_eq!(3, query.iter(&world).count());
+}
+
+#[test]
+fn removed_components_are_logged() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.track_removed::<Rot>();
+
+    let entities = world
+        .insert_from(
+            (Model(1),).as_tags(),
+            (0..5).map(|i| (Pos(i as f32, 0., 0.), Rot(0., 0., 0.))),
+        )
+        .to_vec();
+    world.insert_from((Model(2),).as_tags(), vec![(Pos(0., 0., 0.),)]);
+    assert!(world.drain_removed::<Rot>().is_empty());
+
+    world.delete(entities[0]);
+    world.mutate_entity(entities[1], |e| {
+        e.remove_component::<Rot>();
+    });
+    world.mutate_entity(entities[2], |e| e.add_component(Scale(1., 1., 1.)));
+    assert_eq!(vec![entities[0], entities[1]], world.drain_removed::<Rot>());
+    assert!(world.drain_removed::<Rot>().is_empty());
+
+    world.remove_component_where::<Rot, _>(tag_value(&Model(1)));
+    let removed = world.drain_removed::<Rot>();
+    assert_eq!(3, removed.len());
+    assert!(entities[2..].iter().all(|e| removed.contains(e)));
+
+    // components which are not tracked are not logged
+    world.delete_where(tag_value(&Model(2)));
+    world.clear();
+    assert!(world.drain_removed::<Rot>().is_empty());
+    assert!(world.drain_removed::<Pos>().is_empty());
+}
+
+#[test]
+fn removing_every_component() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.track_removed::<Pos>();
+    world.track_removed::<Rot>();
+
+    // the entity's chunk is released once it is empty
+    let alone = world.insert_from((), vec![(Pos(1., 0., 0.), Rot(0., 0., 0.))])[0];
+    world.mutate_entity(alone, |e| {
+        e.remove_component::<Pos>();
+        e.remove_component::<Rot>();
+    });
+    assert!(world.is_alive(&alone));
+    assert_eq!(vec![alone], world.drain_removed::<Pos>());
+    assert_eq!(vec![alone], world.drain_removed::<Rot>());
+
+    // the entity swapped into the removed entity's place keeps its added ticks
+    let first = world.insert_from((), vec![(Pos(2., 0., 0.),)])[0];
+    let mut query = Read::<Pos>::query().filter(added::<Pos>());
+    assert_eq!(1, query.iter(&world).count());
+    let second = world.insert_from((), vec![(Pos(3., 0., 0.),)])[0];
+    world.mutate_entity(first, |e| {
+        e.remove_component::<Pos>();
+    });
+
+    let added = query
+        .iter_entities(&world)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![second], added);
+    assert_eq!(vec![first], world.drain_removed::<Pos>());
+}
+
+#[test]
+fn removed_components_are_logged_when_moved() {
+    let universe = Universe::new(None);
+    let mut world = universe.create_world();
+    world.track_removed::<Pos>();
+
+    let entities = world
+        .insert_from(
+            (Model(1),).as_tags(),
+            vec![(Pos(1., 0., 0.),), (Pos(2., 0., 0.),)],
+        )
+        .to_vec();
+    let other = world.insert_from((Model(2),).as_tags(), vec![(Pos(3., 0., 0.),)])[0];
+
+    let mut target = universe.create_world();
+    world.move_entity(entities[0], &mut target);
+    let split = world.split_off(tag_value(&Model(2)));
+    assert!(split.is_alive(&other));
+    assert_eq!(vec![entities[0], other], world.drain_removed::<Pos>());
+
+    world.delete_where(tag_value(&Model(1)));
+    assert_eq!(vec![entities[1]], world.drain_removed::<Pos>());
+}
+
 #[cfg(not(feature = "blanket-impl-comp"))]
 mod custom_type_id {
     use super::*;
@@ -376,6 +1548,8 @@ mod custom_type_id {
     impl DefaultComponentImpl for Scale {}
     impl DefaultComponentImpl for Pos {}
     impl DefaultComponentImpl for Rot {}
+    impl DefaultComponentImpl for Tracked {}
+    impl DefaultComponentImpl for Target {}
 
     #[derive(Debug, Copy, Clone, PartialEq)]
     struct RustType(u32);